@@ -3,13 +3,19 @@ extern crate clap;
 
 use clap::App;
 
+use color::color_space::{ChromaticAdaptation, ColorSpace};
+use color::illuminant::Illuminant;
+use color::response::ResponseCurve;
 use lazy_static::lazy_static;
-use rust_v::configuration::{Configuration, DemoType, IntegratorType, PixelType};
+use rust_v::configuration::{Configuration, DemoType, IntegratorType, PixelType, SpectrumType};
 use std::convert::TryInto;
 
 const LIVE: &str = "LIVE_WINDOW";
 const SPHERE_SCENE: &str = "spheres";
 const CORNELL_SCENE: &str = "cornell";
+const FOGGY_CORNELL_SCENE: &str = "foggy-cornell";
+const SMOKY_CORNELL_SCENE: &str = "smoky-cornell";
+const WAX_CORNELL_SCENE: &str = "wax-cornell";
 const DEBUG_SCENE: &str = "debug";
 const VERBOSE: &str = "VERBOSE";
 #[allow(dead_code)]
@@ -21,7 +27,17 @@ const DEPTH: &str = "DEPTH";
 const WIDTH: &str = "WIDTH";
 const HEIGHT: &str = "HEIGHT";
 const FORMAT: &str = "FORMAT";
+const SPECTRUM: &str = "SPECTRUM";
+const OUTPUT_SPACE: &str = "OUTPUT_SPACE";
+const ADAPTATION: &str = "ADAPTATION";
+const WHITE_BALANCE: &str = "WHITE_BALANCE";
+const RESPONSE: &str = "RESPONSE";
 const INTEGRATOR_BACKEND: &str = "INTEGRATOR_BACKEND";
+const PHOTONS: &str = "PHOTONS";
+const RADIUS: &str = "RADIUS";
+const AO_DISTANCE: &str = "AO_DISTANCE";
+const AO_SAMPLES: &str = "AO_SAMPLES";
+const SPHERE: &str = "SPHERE";
 const THREADS: &str = "THREADS";
 
 lazy_static! {
@@ -36,11 +52,24 @@ lazy_static! {
         let demo = if let Some(spheres) = app_matches.subcommand_matches(SPHERE_SCENE) {
             (spheres, DemoType::SphereScene)
         } else if let Some(cornell) = app_matches.subcommand_matches(CORNELL_SCENE) {
-            (cornell, DemoType::CornellScene)
+            let sphere = match cornell.value_of(SPHERE).unwrap_or("glass").try_into() {
+                Ok(sphere) => sphere,
+                Err(err) => panic!("Cannot parse sphere material: {}", err),
+            };
+            (cornell, DemoType::CornellScene(sphere))
+        } else if let Some(foggy) = app_matches.subcommand_matches(FOGGY_CORNELL_SCENE) {
+            (foggy, DemoType::FoggyCornellScene)
+        } else if let Some(smoky) = app_matches.subcommand_matches(SMOKY_CORNELL_SCENE) {
+            (smoky, DemoType::SmokyCornellScene)
+        } else if let Some(wax) = app_matches.subcommand_matches(WAX_CORNELL_SCENE) {
+            (wax, DemoType::WaxCornellScene)
         } else if let Some(debug) = app_matches.subcommand_matches(DEBUG_SCENE) {
             (debug, DemoType::DebugScene)
         } else {
-            panic!("Currently we only support the subcommands (spheres, cornell, debug)!");
+            panic!(
+                "Currently we only support the subcommands \
+                 (spheres, cornell, foggy-cornell, smoky-cornell, wax-cornell, debug)!"
+            );
         };
 
         let matches = demo.0;
@@ -79,6 +108,35 @@ lazy_static! {
             Ok(format) => format,
             Err(err) => panic!("Cannot parse pixel format: {}", err),
         };
+        let output_space: Option<ColorSpace> = match matches.value_of(OUTPUT_SPACE) {
+            Some(space) => match space.try_into() {
+                Ok(space) => Some(space),
+                Err(err) => panic!("Cannot parse output space: {}", err),
+            },
+            None => None,
+        };
+        let adaptation: ChromaticAdaptation = match matches
+            .value_of(ADAPTATION)
+            .unwrap_or("Bradford")
+            .try_into()
+        {
+            Ok(adaptation) => adaptation,
+            Err(err) => panic!("Cannot parse chromatic adaptation: {}", err),
+        };
+        let white_balance: Option<Illuminant> = match matches.value_of(WHITE_BALANCE) {
+            Some(white) => match white.try_into() {
+                Ok(white) => Some(white),
+                Err(err) => panic!("Cannot parse white balance: {}", err),
+            },
+            None => None,
+        };
+        let response: Option<ResponseCurve> = match matches.value_of(RESPONSE) {
+            Some(response) => match response.try_into() {
+                Ok(response) => Some(response),
+                Err(err) => panic!("Cannot parse response curve: {}", err),
+            },
+            None => None,
+        };
         let integrator_type: IntegratorType = match matches
             .value_of(INTEGRATOR_BACKEND)
             .unwrap_or("whitted")
@@ -87,6 +145,41 @@ lazy_static! {
             Ok(integrator) => integrator,
             Err(err) => panic!("Cannot parse integrator backend: {}", err),
         };
+        let spectrum_type: SpectrumType = match matches.value_of(SPECTRUM) {
+            Some(spectrum) => match spectrum.try_into() {
+                Ok(spectrum) => spectrum,
+                Err(err) => panic!("Cannot parse spectrum: {}", err),
+            },
+            None => SpectrumType::default_for(&integrator_type),
+        };
+        let photons = match matches.value_of(PHOTONS) {
+            Some(photons) => match photons.parse() {
+                Ok(photons) => Some(photons),
+                Err(err) => panic!("Cannot parse photons: {}", err),
+            },
+            None => None,
+        };
+        let radius = match matches.value_of(RADIUS) {
+            Some(radius) => match radius.parse() {
+                Ok(radius) => Some(radius),
+                Err(err) => panic!("Cannot parse radius: {}", err),
+            },
+            None => None,
+        };
+        let ao_distance = match matches.value_of(AO_DISTANCE) {
+            Some(distance) => match distance.parse() {
+                Ok(distance) => Some(distance),
+                Err(err) => panic!("Cannot parse ambient occlusion distance: {}", err),
+            },
+            None => None,
+        };
+        let ao_samples = match matches.value_of(AO_SAMPLES) {
+            Some(samples) => match samples.parse() {
+                Ok(samples) => Some(samples),
+                Err(err) => panic!("Cannot parse ambient occlusion samples: {}", err),
+            },
+            None => None,
+        };
 
         let output = if let Some(o) = matches.value_of(OUTPUT) {
             o.to_string()
@@ -118,7 +211,16 @@ lazy_static! {
             threads,
             output,
             pixel_type,
+            spectrum_type,
+            output_space,
+            adaptation,
+            white_balance,
+            response,
             integrator_type,
+            photons,
+            radius,
+            ao_distance,
+            ao_samples,
             demo_type,
         }
     };
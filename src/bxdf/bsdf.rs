@@ -1,16 +1,21 @@
 use ultraviolet::Vec3;
 
 use crate::bxdf::{same_hemisphere, world_to_bxdf, BxDF, BxDFSample, BxDFType};
+use crate::polarization::Mueller;
 use crate::sampler::Sample;
 use crate::Spectrum;
 
+/// The minimum weight of picking a matching BxDF, such that BxDFs with underestimated albedos
+/// are still sampled.
+const MIN_WEIGHT: f32 = 0.01;
+
 #[derive(Debug)]
-pub struct BSDF {
-    bxdfs: Vec<Box<dyn BxDF>>,
+pub struct BSDF<S: Spectrum> {
+    bxdfs: Vec<Box<dyn BxDF<S>>>,
 }
 
-impl BSDF {
-    pub fn new(bxdfs: Vec<Box<dyn BxDF>>) -> Self {
+impl<S: Spectrum> BSDF<S> {
+    pub fn new(bxdfs: Vec<Box<dyn BxDF<S>>>) -> Self {
         Self { bxdfs }
     }
 
@@ -30,17 +35,88 @@ impl BSDF {
         self.bxdfs.iter().filter(|bxdf| bxdf.is_type(t)).count()
     }
 
+    /// # Summary
+    /// Computes the weight of picking the BxDF, proportional to its estimated albedo.
+    ///
+    /// # Arguments
+    /// * `bxdf` - The BxDF
+    /// * `outgoing` - The outgoing light direction in the local frame
+    /// * `t` - The types of BxDFs to pick from
+    ///
+    /// # Results
+    /// * `f32` - The weight, or `0` if the BxDF does not match
+    #[inline]
+    fn weight(bxdf: &dyn BxDF<S>, outgoing: &Vec3, t: BxDFType) -> f32 {
+        if bxdf.is_type(t) {
+            bxdf.albedo(outgoing).max(MIN_WEIGHT)
+        } else {
+            0.0
+        }
+    }
+
+    #[inline]
+    fn total_weight(&self, outgoing: &Vec3, t: BxDFType) -> f32 {
+        self.bxdfs
+            .iter()
+            .map(|bxdf| Self::weight(bxdf.as_ref(), outgoing, t))
+            .sum()
+    }
+
+    /// # Summary
+    /// Picks a matching BxDF proportionally to its weight.
+    ///
+    /// # Arguments
+    /// * `t` - The types of BxDFs to pick from
+    /// * `outgoing` - The outgoing light direction in the local frame
+    /// * `rand` - A random sample in `[0, 1)`
+    ///
+    /// # Results
+    /// * `Option<(&Box<dyn BxDF<S>>, f32)>` - The BxDF and the probability of picking it, if any
+    ///   BxDF matches
     #[allow(clippy::borrowed_box)]
     #[inline]
-    fn random_matching_bxdf(&self, t: BxDFType, rand: f32) -> Option<&Box<dyn BxDF>> {
-        let count = self.num_types(t);
-        if count == 0 {
+    fn random_matching_bxdf(
+        &self,
+        t: BxDFType,
+        outgoing: &Vec3,
+        rand: f32,
+    ) -> Option<(&Box<dyn BxDF<S>>, f32)> {
+        let total = self.total_weight(outgoing, t);
+        if total <= 0.0 {
             return None;
         }
 
-        let index = (rand * count as f32) as usize;
+        let target = rand * total;
+        let mut sum = 0.0;
+        let mut matching = None;
 
-        self.bxdfs.iter().filter(|bxdf| bxdf.is_type(t)).nth(index)
+        for bxdf in &self.bxdfs {
+            let weight = Self::weight(bxdf.as_ref(), outgoing, t);
+            if weight > 0.0 {
+                matching = Some((bxdf, weight / total));
+
+                sum += weight;
+                if target < sum {
+                    break;
+                }
+            }
+        }
+
+        matching
+    }
+
+    /// Whether the BxDF matches the types and scatters between the hemispheres of a pair of
+    /// directions.
+    #[inline]
+    fn scatters(bxdf: &dyn BxDF<S>, t: BxDFType, reflection: bool) -> bool {
+        let typ = bxdf.get_type();
+
+        bxdf.is_type(t)
+            && if reflection {
+                typ.is_reflection()
+            } else {
+                typ.is_transmission()
+            }
     }
 
     pub fn evaluate(
@@ -48,49 +124,260 @@ impl BSDF {
         normal: &Vec3,
         incident_world: &Vec3,
         outgoing_world: &Vec3,
-        mut types: BxDFType,
-    ) -> Spectrum {
+        types: BxDFType,
+    ) -> S {
         let rotation = world_to_bxdf(normal);
         let incident = rotation * *incident_world;
         let outgoing = rotation * *outgoing_world;
 
+        self.evaluate_local(&incident, &outgoing, types, true)
+    }
+
+    /// # Summary
+    /// Evaluates the matching BxDFs in the local frame.
+    ///
+    /// # Arguments
+    /// * `incident` - The incident direction in the local frame
+    /// * `outgoing` - The outgoing light direction in the local frame
+    /// * `types` - The types of BxDFs to evaluate
+    /// * `stochastic` - Whether to include BxDFs only estimating their value stochastically
+    ///
+    /// # Results
+    /// * `S` - The sum of the values
+    fn evaluate_local(
+        &self,
+        incident: &Vec3,
+        outgoing: &Vec3,
+        types: BxDFType,
+        stochastic: bool,
+    ) -> S {
         // transmission or reflection
-        if same_hemisphere(&incident, &outgoing) {
-            types &= !BxDFType::TRANSMISSION;
-        } else {
-            types &= !BxDFType::REFLECTION;
-        }
+        let reflection = same_hemisphere(incident, outgoing);
 
         self.bxdfs
             .iter()
-            .filter_map(|bxdf| {
-                if bxdf.is_type(types) {
-                    Some(bxdf.evaluate(&incident, &outgoing))
-                } else {
-                    None
-                }
-            })
+            .filter(|bxdf| stochastic || !bxdf.is_stochastic())
+            .filter(|bxdf| Self::scatters(bxdf.as_ref(), types, reflection))
+            .map(|bxdf| bxdf.evaluate(incident, outgoing))
             .sum()
     }
 
+    /// # Summary
+    /// Samples an incident direction from a matching BxDF, picked proportionally to its
+    /// estimated albedo.
+    /// For non-specular BxDFs, the value and pdf of the sample are those of all matching
+    /// non-specular BxDFs, i.e. consistent with `evaluate` and `pdf`, except for stochastic
+    /// BxDFs (see `combine`).
+    ///
+    /// # Arguments
+    /// * `normal` - The normal vector at the intersection
+    /// * `outgoing_world` - The outgoing light direction
+    /// * `types` - The types of BxDFs to sample
+    /// * `sample` - The sample space for randomization
+    ///
+    /// # Results
+    /// * `Option<BxDFSample<S>>` - The sample, if any BxDF matches
     pub fn sample(
         &self,
         normal: &Vec3,
         outgoing_world: &Vec3,
         types: BxDFType,
         sample: &Sample,
-    ) -> Option<BxDFSample> {
+    ) -> Option<BxDFSample<S>> {
         let rotation = world_to_bxdf(normal);
         let outgoing = rotation * *outgoing_world;
 
-        let bxdf = self.random_matching_bxdf(types, sample.one_d)?;
+        let (bxdf, probability) = self.random_matching_bxdf(types, &outgoing, sample.one_d)?;
 
         let mut sample = bxdf.sample(&outgoing, &sample.two_d);
+        self.combine(&mut sample, bxdf.as_ref(), probability, &outgoing, types);
+        sample.incident = rotation.reversed() * sample.incident;
+
+        Some(sample)
+    }
+
+    /// # Summary
+    /// Samples a matching BxDF like `sample`, but for light of a single wavelength.
+    ///
+    /// # Arguments
+    /// * `normal` - The normal vector at the intersection
+    /// * `outgoing_world` - The outgoing light direction
+    /// * `types` - The types of BxDFs to sample
+    /// * `sample` - The sample space for randomization
+    /// * `lambda` - The wavelength in nm
+    ///
+    /// # Results
+    /// * `Option<BxDFSample<S>>` - The sample, if any BxDF matches
+    pub fn sample_wavelength(
+        &self,
+        normal: &Vec3,
+        outgoing_world: &Vec3,
+        types: BxDFType,
+        sample: &Sample,
+        lambda: f32,
+    ) -> Option<BxDFSample<S>> {
+        let rotation = world_to_bxdf(normal);
+        let outgoing = rotation * *outgoing_world;
+
+        let (bxdf, probability) = self.random_matching_bxdf(types, &outgoing, sample.one_d)?;
+
+        let mut sample = bxdf.sample_wavelength(&outgoing, &sample.two_d, lambda);
+        self.combine(&mut sample, bxdf.as_ref(), probability, &outgoing, types);
         sample.incident = rotation.reversed() * sample.incident;
 
         Some(sample)
     }
 
+    /// # Summary
+    /// Combines the sample of a single BxDF with all other matching BxDFs.
+    /// Specular samples, samples of stochastic BxDFs, and samples of fluorescent BSDFs whose
+    /// re-radiation depends on the sampled BxDF, keep their value and only account for the
+    /// probability of picking the BxDF.
+    /// All other samples are combined with the matching non-stochastic BxDFs only. Their value
+    /// and pdf are exact, such that every stochastic BxDF is only estimated by its own samples.
+    ///
+    /// # Arguments
+    /// * `sample` - The sample in the local frame
+    /// * `bxdf` - The sampled BxDF
+    /// * `probability` - The probability of picking the sampled BxDF
+    /// * `outgoing` - The outgoing light direction in the local frame
+    /// * `types` - The types of BxDFs to combine
+    fn combine(
+        &self,
+        sample: &mut BxDFSample<S>,
+        bxdf: &dyn BxDF<S>,
+        probability: f32,
+        outgoing: &Vec3,
+        types: BxDFType,
+    ) {
+        if sample.pdf <= 0.0 {
+            return;
+        }
+
+        if sample.typ.is_specular() || bxdf.is_stochastic() || self.is_fluorescent() {
+            sample.pdf *= probability;
+        } else {
+            sample.spectrum = self.evaluate_local(&sample.incident, outgoing, types, false);
+            sample.pdf = self.pdf_local(&sample.incident, outgoing, types, false);
+        }
+    }
+
+    /// # Summary
+    /// Computes the change of polarization by the BxDF of the given type, as sampled by `sample`.
+    ///
+    /// # Arguments
+    /// * `normal` - The normal vector at the intersection
+    /// * `incident_world` - The incident direction
+    /// * `outgoing_world` - The outgoing light direction
+    /// * `typ` - The type of the sampled BxDF
+    ///
+    /// # Results
+    /// * `Mueller<S>` - The normalized Mueller matrix in the s/p reference frame
+    pub fn polarization(
+        &self,
+        normal: &Vec3,
+        incident_world: &Vec3,
+        outgoing_world: &Vec3,
+        typ: BxDFType,
+    ) -> Mueller<S> {
+        let rotation = world_to_bxdf(normal);
+        let incident = rotation * *incident_world;
+        let outgoing = rotation * *outgoing_world;
+
+        self.bxdfs
+            .iter()
+            .find(|bxdf| bxdf.get_type().contains(typ))
+            .map_or_else(Mueller::depolarizer, |bxdf| {
+                bxdf.polarization(&incident, &outgoing)
+            })
+    }
+
+    /// Whether any BxDF samples directions depending on the wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        self.bxdfs.iter().any(|bxdf| bxdf.is_dispersive())
+    }
+
+    /// Whether any BxDF only estimates its value and pdf stochastically.
+    pub fn is_stochastic(&self) -> bool {
+        self.bxdfs.iter().any(|bxdf| bxdf.is_stochastic())
+    }
+
+    /// Whether any BxDF re-radiates light at other wavelengths.
+    pub fn is_fluorescent(&self) -> bool {
+        self.bxdfs.iter().any(|bxdf| bxdf.is_fluorescent())
+    }
+
+    /// # Summary
+    /// Evaluates the light scattered from the given incident light like `evaluate`, including
+    /// the light re-radiated at other wavelengths.
+    ///
+    /// # Arguments
+    /// * `normal` - The normal vector at the intersection
+    /// * `incident_world` - The incident direction
+    /// * `outgoing_world` - The outgoing light direction
+    /// * `types` - The types of BxDFs to evaluate
+    /// * `radiance` - The incident light
+    ///
+    /// # Results
+    /// * `S` - The scattered light
+    pub fn scatter(
+        &self,
+        normal: &Vec3,
+        incident_world: &Vec3,
+        outgoing_world: &Vec3,
+        types: BxDFType,
+        radiance: &S,
+    ) -> S {
+        let rotation = world_to_bxdf(normal);
+        let incident = rotation * *incident_world;
+        let outgoing = rotation * *outgoing_world;
+
+        // transmission or reflection
+        let reflection = same_hemisphere(&incident, &outgoing);
+
+        self.bxdfs
+            .iter()
+            .filter(|bxdf| Self::scatters(bxdf.as_ref(), types, reflection))
+            .map(|bxdf| bxdf.evaluate(&incident, &outgoing) * bxdf.reradiate(radiance))
+            .sum()
+    }
+
+    /// # Summary
+    /// Samples the wavelength at which light scattered by the BxDF of the given type, as sampled
+    /// by `sample`, was absorbed.
+    ///
+    /// # Arguments
+    /// * `typ` - The type of the sampled BxDF
+    /// * `emission` - The index of the scattered wavelength
+    /// * `sample` - A random sample in `[0, 1)`
+    ///
+    /// # Results
+    /// * `Option<(usize, f32)>` - The index of the absorbed wavelength and the weight relative to
+    ///   the sampled spectrum, or `None` if the wavelength does not change
+    pub fn sample_excitation(
+        &self,
+        typ: BxDFType,
+        emission: usize,
+        sample: f32,
+    ) -> Option<(usize, f32)> {
+        self.bxdfs
+            .iter()
+            .find(|bxdf| bxdf.is_fluorescent() && bxdf.get_type().contains(typ))?
+            .sample_excitation(emission, sample)
+    }
+
+    /// # Summary
+    /// Computes the pdf of sampling the incident direction with `sample`, i.e. the pdfs of all
+    /// matching non-specular BxDFs weighted by the probability of picking them.
+    ///
+    /// # Arguments
+    /// * `normal` - The normal vector at the intersection
+    /// * `incident_world` - The incident direction
+    /// * `outgoing_world` - The outgoing light direction
+    /// * `types` - The types of BxDFs to sample
+    ///
+    /// # Results
+    /// * `f32` - The pdf
     pub fn pdf(
         &self,
         normal: &Vec3,
@@ -102,22 +389,41 @@ impl BSDF {
         let incident = rotation * *incident_world;
         let outgoing = rotation * *outgoing_world;
 
-        let (pdf, num) = self
+        self.pdf_local(&incident, &outgoing, types, true)
+    }
+
+    /// # Summary
+    /// Computes the pdf of sampling the incident direction in the local frame, including the
+    /// probabilities of picking the matching BxDFs.
+    ///
+    /// # Arguments
+    /// * `incident` - The incident direction in the local frame
+    /// * `outgoing` - The outgoing light direction in the local frame
+    /// * `types` - The types of BxDFs to sample
+    /// * `stochastic` - Whether to include BxDFs only estimating their pdf stochastically
+    ///
+    /// # Results
+    /// * `f32` - The pdf
+    fn pdf_local(
+        &self,
+        incident: &Vec3,
+        outgoing: &Vec3,
+        types: BxDFType,
+        stochastic: bool,
+    ) -> f32 {
+        let total = self.total_weight(outgoing, types);
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        let pdf: f32 = self
             .bxdfs
             .iter()
-            .filter_map(|bxdf| {
-                if bxdf.is_type(types) {
-                    Some(bxdf.pdf(&incident, &outgoing))
-                } else {
-                    None
-                }
-            })
-            .fold((0.0, 0usize), |(a, num), b| (a + b, num + 1));
+            .filter(|bxdf| !bxdf.get_type().is_specular())
+            .filter(|bxdf| stochastic || !bxdf.is_stochastic())
+            .map(|bxdf| Self::weight(bxdf.as_ref(), outgoing, types) * bxdf.pdf(incident, outgoing))
+            .sum();
 
-        if num > 0 {
-            pdf / num as f32
-        } else {
-            0.0
-        }
+        pdf / total
     }
 }
@@ -0,0 +1,414 @@
+use std::f32::consts::{FRAC_1_PI, PI};
+
+use ultraviolet::{Vec2, Vec3};
+
+use crate::bxdf::bsdf::BSDF;
+use crate::bxdf::fresnel::{fresnel_dielectric, Dielectric, Fresnel};
+use crate::bxdf::microfacet::{
+    MicrofacetReflection, MicrofacetTransmission, TrowbridgeReitzDistribution,
+};
+use crate::bxdf::*;
+use crate::Spectrum;
+
+#[inline(always)]
+fn lerp<S: Spectrum>(t: f32, a: S, b: S) -> S {
+    a * (1.0 - t) + b * t
+}
+
+#[inline(always)]
+fn schlick_weight(cos_theta: f32) -> f32 {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0);
+    let m2 = m * m;
+
+    m2 * m2 * m
+}
+
+#[inline(always)]
+fn schlick_fresnel(r0: f32, cos_theta: f32) -> f32 {
+    r0 + (1.0 - r0) * schlick_weight(cos_theta)
+}
+
+/// # Summary
+/// Computes the half vector of two directions in the same hemisphere.
+///
+/// # Results
+/// * `Option<Vec3>` - The normalized half vector, or `None` if the directions are opposite
+#[inline(always)]
+fn half_vector(incident: &Vec3, outgoing: &Vec3) -> Option<Vec3> {
+    let wh = *incident + *outgoing;
+    if wh == Vec3::zero() {
+        None
+    } else {
+        Some(wh.normalized())
+    }
+}
+
+/// The diffuse lobe of the Disney BRDF, darkening at grazing angles like a Fresnel-weighted
+/// Lambertian surface.
+#[derive(Debug)]
+pub struct DisneyDiffuse<S> {
+    r: S,
+}
+
+impl<S> DisneyDiffuse<S> {
+    pub fn new(r: S) -> Self {
+        Self { r }
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for DisneyDiffuse<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::DIFFUSE | BxDFType::REFLECTION
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        let fo = schlick_weight(cos_theta(outgoing).abs());
+        let fi = schlick_weight(cos_theta(incident).abs());
+
+        self.r * (FRAC_1_PI * (1.0 - fo / 2.0) * (1.0 - fi / 2.0))
+    }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        self.r.avg()
+    }
+}
+
+/// The retro-reflection lobe of the Disney BRDF, brightening rough surfaces at grazing angles.
+#[derive(Debug)]
+pub struct DisneyRetro<S> {
+    r: S,
+    roughness: f32,
+}
+
+impl<S> DisneyRetro<S> {
+    pub fn new(r: S, roughness: f32) -> Self {
+        Self { r, roughness }
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for DisneyRetro<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::DIFFUSE | BxDFType::REFLECTION
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        let wh = match half_vector(incident, outgoing) {
+            Some(wh) => wh,
+            None => return S::black(),
+        };
+
+        let cos_theta_d = incident.dot(wh);
+        let fo = schlick_weight(cos_theta(outgoing).abs());
+        let fi = schlick_weight(cos_theta(incident).abs());
+        let rr = 2.0 * self.roughness * cos_theta_d * cos_theta_d;
+
+        self.r * (FRAC_1_PI * rr * (fo + fi + fo * fi * (rr - 1.0)))
+    }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        // the retro-reflection only adds a fraction of the diffuse lobe
+        0.1 * self.roughness * self.r.avg()
+    }
+}
+
+/// The sheen lobe of the Disney BRDF, adding a soft grazing highlight for cloth.
+#[derive(Debug)]
+pub struct DisneySheen<S> {
+    r: S,
+}
+
+impl<S> DisneySheen<S> {
+    pub fn new(r: S) -> Self {
+        Self { r }
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for DisneySheen<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::DIFFUSE | BxDFType::REFLECTION
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        let wh = match half_vector(incident, outgoing) {
+            Some(wh) => wh,
+            None => return S::black(),
+        };
+
+        self.r * schlick_weight(incident.dot(wh))
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        schlick_weight(cos_theta(outgoing).abs()) * self.r.avg()
+    }
+}
+
+/// The clear coat lobe of the Disney BRDF, a colorless specular layer with a fixed index of
+/// refraction of `1.5` and the long-tailed GTR1 distribution.
+#[derive(Debug)]
+pub struct DisneyClearcoat {
+    weight: f32,
+    alpha: f32,
+}
+
+impl DisneyClearcoat {
+    /// # Summary
+    /// Creates a new clear coat lobe.
+    ///
+    /// # Arguments
+    /// * `weight` - The strength of the lobe
+    /// * `gloss` - The glossiness in `[0, 1]`, mapped to a roughness between `0.1` and `0.001`
+    ///
+    /// # Results
+    /// * `Self` - The lobe
+    pub fn new(weight: f32, gloss: f32) -> Self {
+        let alpha = 0.1 * (1.0 - gloss) + 0.001 * gloss;
+
+        Self { weight, alpha }
+    }
+
+    fn gtr1(&self, cos_theta: f32) -> f32 {
+        let alpha2 = self.alpha * self.alpha;
+
+        (alpha2 - 1.0) / (PI * alpha2.ln() * (1.0 + (alpha2 - 1.0) * cos_theta * cos_theta))
+    }
+
+    fn smith_g_ggx(cos_theta: f32, alpha: f32) -> f32 {
+        let alpha2 = alpha * alpha;
+        let cos2_theta = cos_theta * cos_theta;
+
+        1.0 / (cos_theta + f32::sqrt(alpha2 + cos2_theta - alpha2 * cos2_theta))
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for DisneyClearcoat {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::REFLECTION | BxDFType::GLOSSY
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        let wh = match half_vector(incident, outgoing) {
+            Some(wh) => wh,
+            None => return S::black(),
+        };
+
+        let d = self.gtr1(cos_theta(&wh).abs());
+        let f = schlick_fresnel(0.04, outgoing.dot(wh));
+        // the coat's roughness is fixed to 0.25 for masking and shadowing
+        let g = Self::smith_g_ggx(cos_theta(outgoing).abs(), 0.25)
+            * Self::smith_g_ggx(cos_theta(incident).abs(), 0.25);
+
+        S::new_const(self.weight * g * f * d / 4.0)
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        if bxdf_is_parallel(outgoing) {
+            return BxDFSample::black_nan_0();
+        }
+
+        let alpha2 = self.alpha * self.alpha;
+        let cos_theta = f32::sqrt(f32::max(
+            0.0,
+            (1.0 - alpha2.powf(1.0 - sample.x)) / (1.0 - alpha2),
+        ));
+        let sin_theta = f32::sqrt(f32::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * PI * sample.y;
+
+        let mut wh = Vec3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+        if !same_hemisphere(outgoing, &wh) {
+            wh = -wh;
+        }
+
+        let incident = -outgoing.reflected(wh);
+        if !same_hemisphere(&incident, outgoing) {
+            return BxDFSample::black_nan_0();
+        }
+
+        let spectrum = self.evaluate(&incident, outgoing);
+        let pdf = BxDF::<S>::pdf(self, &incident, outgoing);
+
+        BxDFSample::new(spectrum, incident, pdf, BxDF::<S>::get_type(self))
+    }
+
+    fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        if !same_hemisphere(incident, outgoing) {
+            return 0.0;
+        }
+
+        match half_vector(incident, outgoing) {
+            Some(wh) => {
+                let cos_theta_h = cos_theta(&wh).abs();
+                self.gtr1(cos_theta_h) * cos_theta_h / (4.0 * outgoing.dot(wh).abs())
+            }
+            None => 0.0,
+        }
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        self.weight * schlick_fresnel(0.04, cos_theta(outgoing).abs())
+    }
+}
+
+/// The Fresnel term of the Disney specular lobe, blending between a dielectric and the (tinted)
+/// Schlick approximation of a metal.
+#[derive(Debug)]
+pub struct DisneyFresnel<S> {
+    r0: S,
+    metallic: f32,
+    eta: f32,
+}
+
+impl<S> DisneyFresnel<S> {
+    pub fn new(r0: S, metallic: f32, eta: f32) -> Self {
+        Self { r0, metallic, eta }
+    }
+}
+
+impl<S: Spectrum> Fresnel<S> for DisneyFresnel<S> {
+    fn evaluate(&self, cos_i: f32) -> S {
+        let dielectric = S::new_const(fresnel_dielectric(cos_i, 1.0, self.eta));
+        let metal = lerp(schlick_weight(cos_i), self.r0, S::white());
+
+        lerp(self.metallic, dielectric, metal)
+    }
+}
+
+/// A principled material after Burley, "Physically Based Shading at Disney" (2012) and
+/// "Extending the Disney BRDF to a BSDF with Integrated Subsurface Scattering" (2015).
+///
+/// All parameters but the base color are in `[0, 1]`. The material is assembled from the Disney
+/// lobes and the microfacet BxDFs, which all sample their own values.
+#[derive(Debug, Copy, Clone)]
+pub struct DisneyMaterial<S> {
+    /// The surface color, i.e. the diffuse albedo or the metallic reflectance
+    pub base_color: S,
+    /// Blends between a dielectric (`0`) and a metal (`1`)
+    pub metallic: f32,
+    /// The perceptual roughness of the specular and diffuse lobes
+    pub roughness: f32,
+    /// The specular reflectance at normal incidence, `0.5` being `4%` (an index of `1.5`)
+    pub specular: f32,
+    /// Tints the dielectric specular reflectance towards the base color
+    pub specular_tint: f32,
+    /// The aspect ratio of the specular highlight, stretched along the x-axis
+    pub anisotropic: f32,
+    /// The strength of the grazing sheen for cloth
+    pub sheen: f32,
+    /// Tints the sheen towards the base color
+    pub sheen_tint: f32,
+    /// The strength of the clear coat
+    pub clearcoat: f32,
+    /// The glossiness of the clear coat
+    pub clearcoat_gloss: f32,
+    /// Blends between an opaque (`0`) and a fully transmissive (`1`) dielectric
+    pub specular_transmission: f32,
+}
+
+impl<S: Spectrum> DisneyMaterial<S> {
+    /// # Summary
+    /// Creates a rough dielectric material of the given color, with all other lobes disabled.
+    ///
+    /// # Arguments
+    /// * `base_color` - The surface color
+    ///
+    /// # Results
+    /// * `Self` - The material
+    pub fn new(base_color: S) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            anisotropic: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            specular_transmission: 0.0,
+        }
+    }
+
+    /// Returns the index of refraction corresponding to the specular reflectance.
+    pub fn eta(&self) -> f32 {
+        let r0 = (0.08 * self.specular).clamp(0.0, 0.99).sqrt();
+
+        (1.0 + r0) / (1.0 - r0)
+    }
+
+    fn distribution(&self) -> TrowbridgeReitzDistribution {
+        let aspect = f32::sqrt(1.0 - self.anisotropic * 0.9);
+        let alpha = self.roughness * self.roughness;
+        let alpha_x = f32::max(0.001, alpha / aspect);
+        let alpha_y = f32::max(0.001, alpha * aspect);
+
+        TrowbridgeReitzDistribution::new(alpha_x, alpha_y, true)
+    }
+
+    /// # Summary
+    /// Assembles the BSDF of this material.
+    /// Lobes with a weight of zero are omitted.
+    ///
+    /// # Results
+    /// * `BSDF<S>` - The BSDF
+    pub fn bsdf(&self) -> BSDF<S> {
+        let mut bxdfs: Vec<Box<dyn BxDF<S>>> = Vec::new();
+
+        let color = self.base_color;
+        let luminance = color.luminance();
+        let tint = if luminance > 0.0 {
+            color / luminance
+        } else {
+            S::white()
+        };
+
+        let eta = self.eta();
+        let diffuse_weight = (1.0 - self.metallic) * (1.0 - self.specular_transmission);
+
+        if diffuse_weight > 0.0 {
+            bxdfs.push(Box::new(DisneyDiffuse::new(color * diffuse_weight)));
+            bxdfs.push(Box::new(DisneyRetro::new(
+                color * diffuse_weight,
+                self.roughness,
+            )));
+
+            if self.sheen > 0.0 {
+                let sheen = lerp(self.sheen_tint, S::white(), tint);
+                bxdfs.push(Box::new(DisneySheen::new(
+                    sheen * (diffuse_weight * self.sheen),
+                )));
+            }
+        }
+
+        let r0 = ((eta - 1.0) / (eta + 1.0)).powi(2);
+        let specular = lerp(
+            self.metallic,
+            lerp(self.specular_tint, S::white(), tint) * r0,
+            color,
+        );
+        let fresnel = DisneyFresnel::new(specular, self.metallic, eta);
+        bxdfs.push(Box::new(MicrofacetReflection::new(
+            S::white(),
+            Box::new(self.distribution()),
+            Box::new(fresnel),
+        )));
+
+        if self.clearcoat > 0.0 {
+            bxdfs.push(Box::new(DisneyClearcoat::new(
+                self.clearcoat,
+                self.clearcoat_gloss,
+            )));
+        }
+
+        let transmission_weight = (1.0 - self.metallic) * self.specular_transmission;
+        if transmission_weight > 0.0 {
+            let transmission = color.sqrt() * transmission_weight;
+            bxdfs.push(Box::new(MicrofacetTransmission::new(
+                transmission,
+                Box::new(self.distribution()),
+                Dielectric::new(1.0, eta),
+            )));
+        }
+
+        BSDF::new(bxdfs)
+    }
+}
@@ -0,0 +1,308 @@
+//! Fluorescent materials, absorbing light at one wavelength and re-radiating it at longer ones.
+//!
+//! The re-radiation is described by a matrix over the spectral samples (a bispectral reflectance),
+//! whose diagonal contains the elastic reflectance. Only spectral integrators switching the
+//! wavelengths of paths render it exactly; all other integrators (and colors not consisting of
+//! wavelengths) see the apparent reflectance under daylight (D65).
+
+use crate::bxdf::{BxDF, BxDFType};
+use crate::Spectrum;
+use color::illuminant::Illuminant;
+use color::{Spectral, SPECTRAL_SAMPLES};
+use std::convert::TryInto;
+use std::f32::consts::FRAC_1_PI;
+use ultraviolet::Vec3;
+use util::floats;
+
+/// # Summary
+/// Creates a spectrum of a gaussian of height `1`.
+///
+/// # Arguments
+/// * `peak` - The wavelength of the peak in nm
+/// * `deviation` - The standard deviation in nm
+///
+/// # Results
+/// * `Spectral` - The spectrum
+fn gaussian(peak: f32, deviation: f32) -> Spectral {
+    let mut spectrum = Spectral::new_const(0.0);
+    for i in 0..SPECTRAL_SAMPLES {
+        let x = (Spectral::lambda(i) - peak) / deviation;
+        spectrum[i] = (-0.5 * x * x).exp();
+    }
+
+    spectrum
+}
+
+/// Common fluorescent dyes, approximated by gaussian excitation and emission spectra.
+#[derive(Debug, Copy, Clone)]
+pub enum Fluorophore {
+    /// A stilbene optical brightener, absorbing (near) ultraviolet light and emitting blue light
+    OpticalBrightener,
+    /// Fluorescein, absorbing blue light and emitting green light
+    Fluorescein,
+    /// Rhodamine 6G, absorbing green light and emitting yellow light
+    Rhodamine,
+}
+
+impl Fluorophore {
+    /// Returns the excitation spectrum, normalized to a peak of `1`.
+    pub fn excitation(&self) -> Spectral {
+        match self {
+            Fluorophore::OpticalBrightener => gaussian(370.0, 20.0),
+            Fluorophore::Fluorescein => gaussian(490.0, 18.0),
+            Fluorophore::Rhodamine => gaussian(530.0, 16.0),
+        }
+    }
+
+    /// Returns the emission spectrum, normalized to a peak of `1`.
+    pub fn emission(&self) -> Spectral {
+        match self {
+            Fluorophore::OpticalBrightener => gaussian(435.0, 20.0),
+            Fluorophore::Fluorescein => gaussian(515.0, 18.0),
+            Fluorophore::Rhodamine => gaussian(555.0, 18.0),
+        }
+    }
+
+    /// Returns the quantum yield, the fraction of absorbed photons being re-emitted.
+    pub fn quantum_yield(&self) -> f32 {
+        match self {
+            Fluorophore::OpticalBrightener => 0.8,
+            Fluorophore::Fluorescein => 0.93,
+            Fluorophore::Rhodamine => 0.95,
+        }
+    }
+}
+
+impl TryInto<Fluorophore> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<Fluorophore, Self::Error> {
+        match self {
+            "brightener" | "Brightener" | "BRIGHTENER" => Ok(Fluorophore::OpticalBrightener),
+            "fluorescein" | "Fluorescein" | "FLUORESCEIN" => Ok(Fluorophore::Fluorescein),
+            "rhodamine" | "Rhodamine" | "RHODAMINE" => Ok(Fluorophore::Rhodamine),
+            _ => Err(self.to_string()),
+        }
+    }
+}
+
+/// A re-radiation matrix, giving the fraction of light absorbed at an excitation wavelength
+/// being re-radiated at an emission wavelength.
+#[derive(Debug, Clone)]
+pub struct Reradiation {
+    /// The rows of the matrix, i.e. the re-radiation at an emission wavelength as spectrum over
+    /// the excitation wavelengths
+    rows: Vec<Spectral>,
+    /// The sums of the rows
+    albedo: Spectral,
+}
+
+impl Reradiation {
+    /// # Summary
+    /// Creates a re-radiation matrix from its rows.
+    ///
+    /// # Arguments
+    /// * `rows` - The re-radiation at every emission wavelength as spectrum over the excitation
+    ///   wavelengths
+    ///
+    /// # Results
+    /// * `Self` - The re-radiation matrix
+    pub fn new(rows: Vec<Spectral>) -> Self {
+        assert_eq!(rows.len(), SPECTRAL_SAMPLES);
+
+        let mut albedo = Spectral::new_const(0.0);
+        for (o, row) in rows.iter().enumerate() {
+            albedo[o] = (0..SPECTRAL_SAMPLES).map(|i| row[i]).sum();
+        }
+
+        Self { rows, albedo }
+    }
+
+    /// # Summary
+    /// Creates the re-radiation matrix of a fluorescent material.
+    /// Absorbed light is re-emitted at longer wavelengths only, losing the energy difference of
+    /// the photons.
+    ///
+    /// # Arguments
+    /// * `reflectance` - The elastic reflectance of the material without the fluorophore
+    /// * `excitation` - The fraction of light absorbed by the fluorophore in `[0, 1]`
+    /// * `emission` - The (relative) emission spectrum of the fluorophore
+    /// * `quantum_yield` - The fraction of absorbed photons being re-emitted
+    ///
+    /// # Results
+    /// * `Self` - The re-radiation matrix
+    pub fn fluorescent(
+        reflectance: &Spectral,
+        excitation: &Spectral,
+        emission: &Spectral,
+        quantum_yield: f32,
+    ) -> Self {
+        let emission_sum: f32 = (0..SPECTRAL_SAMPLES).map(|o| emission[o]).sum();
+        assert!(emission_sum > 0.0, "Emission spectrum must not be black");
+
+        let mut rows = vec![Spectral::new_const(0.0); SPECTRAL_SAMPLES];
+        for i in 0..SPECTRAL_SAMPLES {
+            let absorbed = floats::fast_clamp(excitation[i], 0.0, 1.0);
+            rows[i][i] = reflectance[i] * (1.0 - absorbed);
+
+            for (o, row) in rows.iter_mut().enumerate().skip(i + 1) {
+                let photons = quantum_yield * absorbed * emission[o] / emission_sum;
+                row[i] = photons * Spectral::lambda(i) / Spectral::lambda(o);
+            }
+        }
+
+        Self::new(rows)
+    }
+
+    /// # Summary
+    /// Returns the total light re-radiated at every emission wavelength under an illumination
+    /// of `1` at all wavelengths.
+    ///
+    /// # Results
+    /// * `Spectral` - The sums of the rows
+    pub fn albedo(&self) -> Spectral {
+        self.albedo
+    }
+
+    /// # Summary
+    /// Re-radiates the given light.
+    ///
+    /// # Arguments
+    /// * `spectrum` - The light at the excitation wavelengths
+    ///
+    /// # Results
+    /// * `Spectral` - The light at the emission wavelengths
+    pub fn reradiate(&self, spectrum: &Spectral) -> Spectral {
+        let mut reradiated = Spectral::new_const(0.0);
+        for (o, row) in self.rows.iter().enumerate() {
+            reradiated[o] = (0..SPECTRAL_SAMPLES).map(|i| row[i] * spectrum[i]).sum();
+        }
+
+        reradiated
+    }
+
+    /// # Summary
+    /// Computes the apparent reflectance, i.e. the re-radiated light relative to the
+    /// illumination.
+    ///
+    /// # Arguments
+    /// * `illuminant` - The spectrum of the illumination
+    ///
+    /// # Results
+    /// * `Spectral` - The apparent reflectance
+    pub fn apparent_reflectance(&self, illuminant: &Spectral) -> Spectral {
+        let reradiated = self.reradiate(illuminant);
+
+        let mut reflectance = Spectral::new_const(0.0);
+        for o in 0..SPECTRAL_SAMPLES {
+            if illuminant[o] > 0.0 {
+                reflectance[o] = reradiated[o] / illuminant[o];
+            }
+        }
+
+        reflectance
+    }
+
+    /// # Summary
+    /// Samples the excitation wavelength of light re-radiated at the given emission wavelength,
+    /// proportional to the re-radiation.
+    ///
+    /// # Arguments
+    /// * `emission` - The index of the emission wavelength
+    /// * `sample` - A random sample in `[0, 1)`
+    ///
+    /// # Results
+    /// * `Option<(usize, f32)>` - The index of the excitation wavelength and its probability,
+    ///   or `None` if no light is re-radiated
+    pub fn sample_excitation(&self, emission: usize, sample: f32) -> Option<(usize, f32)> {
+        let total = self.albedo[emission];
+        if total <= 0.0 {
+            return None;
+        }
+
+        let row = &self.rows[emission];
+        let target = sample * total;
+
+        let mut sum = 0.0;
+        let mut last = None;
+        for i in 0..SPECTRAL_SAMPLES {
+            if row[i] <= 0.0 {
+                continue;
+            }
+
+            sum += row[i];
+            last = Some(i);
+            if target < sum {
+                break;
+            }
+        }
+
+        last.map(|i| (i, row[i] / total))
+    }
+}
+
+/// A lambertian reflection re-radiating light at other wavelengths.
+#[derive(Debug)]
+pub struct FluorescentReflection<S> {
+    reradiation: Reradiation,
+    /// The apparent reflectance under daylight
+    r: S,
+}
+
+impl<S: Spectrum> FluorescentReflection<S> {
+    pub fn new(reradiation: Reradiation) -> Self {
+        let daylight = Illuminant::D65.spectrum();
+        let r = S::from_spectral_reflectance(&reradiation.apparent_reflectance(&daylight));
+
+        Self { reradiation, r }
+    }
+
+    pub fn reradiation(&self) -> &Reradiation {
+        &self.reradiation
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for FluorescentReflection<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::REFLECTION | BxDFType::DIFFUSE
+    }
+
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
+        self.r * FRAC_1_PI
+    }
+
+    fn is_fluorescent(&self) -> bool {
+        true
+    }
+
+    fn reradiate(&self, radiance: &S) -> S {
+        let reradiated = match S::reradiate(&self.reradiation, radiance) {
+            Some(reradiated) => reradiated,
+            None => return *radiance,
+        };
+
+        let mut relative = S::black();
+        for o in 0..S::SIZE {
+            if self.r[o] > 0.0 {
+                relative[o] = reradiated[o] / self.r[o];
+            }
+        }
+
+        relative
+    }
+
+    fn sample_excitation(&self, emission: usize, sample: f32) -> Option<(usize, f32)> {
+        if self.r[emission] <= 0.0 {
+            return None;
+        }
+
+        let (excitation, _) = self.reradiation.sample_excitation(emission, sample)?;
+        let weight = self.reradiation.albedo()[emission] / self.r[emission];
+
+        Some((excitation, weight))
+    }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        self.r.avg()
+    }
+}
@@ -1,8 +1,11 @@
 use crate::Spectrum;
-use color::Color;
+use color::Spectral;
 
 use crate::bxdf;
+use crate::bxdf::metal::Metal;
+use crate::bxdf::refractive_index::{RefractiveIndex, REFERENCE_WAVELENGTH};
 use crate::bxdf::{BxDF, BxDFSample, BxDFType};
+use crate::polarization::{fresnel_amplitudes, Complex, Mueller};
 use bitflags::_core::fmt::Debug;
 use bitflags::_core::mem::swap;
 use ultraviolet::{Vec2, Vec3};
@@ -31,7 +34,7 @@ pub fn fresnel_dielectric(mut cos_i: f32, mut eta_i: f32, mut eta_t: f32) -> f32
     cos_i = floats::fast_clamp(cos_i, -1.0, 1.0);
     // potentially swap indices of refraction
     let entering = cos_i > 0.0;
-    if entering {
+    if !entering {
         swap(&mut eta_i, &mut eta_t);
         cos_i = cos_i.abs();
     }
@@ -53,12 +56,7 @@ pub fn fresnel_dielectric(mut cos_i: f32, mut eta_i: f32, mut eta_t: f32) -> f32
 }
 
 #[must_use]
-pub fn fresnel_conductor(
-    mut cos_i: f32,
-    eta_i: &Spectrum,
-    eta_t: &Spectrum,
-    k: &Spectrum,
-) -> Spectrum {
+pub fn fresnel_conductor<S: Spectrum>(mut cos_i: f32, eta_i: &S, eta_t: &S, k: &S) -> S {
     cos_i = floats::fast_clamp(cos_i, -1.0, 1.0);
     let eta = *eta_t / *eta_i;
     let etak = *k / *eta_i;
@@ -68,137 +66,294 @@ pub fn fresnel_conductor(
     let eta2 = eta * eta;
     let etak2 = etak * etak;
 
-    let t0 = eta2 - etak2 - sin_i2.into();
+    let t0 = eta2 - etak2 - S::new_const(sin_i2);
     let a2_plus_b2 = (t0 * t0 + eta2 * etak2 * 4.0).sqrt();
-    let t1 = a2_plus_b2 + cos_i2.into();
+    let t1 = a2_plus_b2 + S::new_const(cos_i2);
     let a = ((a2_plus_b2 + t0) * 0.5).sqrt();
     let t2 = a * (cos_i * 2.0);
     let r_s = (t1 - t2) / (t1 + t2);
 
-    let t3 = a2_plus_b2 * cos_i2 + (sin_i2 * sin_i2).into();
+    let t3 = a2_plus_b2 * cos_i2 + S::new_const(sin_i2 * sin_i2);
     let t4 = t2 * sin_i2;
     let r_p = r_s * ((t3 - t4) / (t3 + t4));
 
     (r_p + r_s) / 2.0
 }
 
-pub trait Fresnel: Debug + Send + Sync {
-    fn evaluate(&self, cos_i: f32) -> Spectrum;
+pub trait Fresnel<S: Spectrum>: Debug + Send + Sync {
+    fn evaluate(&self, cos_i: f32) -> S;
+
+    /// # Summary
+    /// Computes the change of polarization by the reflection, as Mueller matrix in the s/p
+    /// reference frame, normalized to an intensity of `1`.
+    /// Fresnel terms not modelling polarization do not change it.
+    ///
+    /// # Arguments
+    /// * `cos_i` - The cosine of the incident angle
+    ///
+    /// # Results
+    /// * `Mueller<S>` - The normalized Mueller matrix
+    fn polarization(&self, _cos_i: f32) -> Mueller<S> {
+        Mueller::identity()
+    }
 }
 
 #[derive(Debug)]
 pub struct Dielectric {
-    pub eta_i: f32,
-    pub eta_t: f32,
+    pub eta_i: RefractiveIndex,
+    pub eta_t: RefractiveIndex,
 }
 
 impl Dielectric {
     /// - `eta_t`: refractive index of material the light is entering.
     /// - `eta_i`: refractive index of material the light is coming from.
     pub fn new(eta_i: f32, eta_t: f32) -> Self {
+        Self::dispersive(
+            RefractiveIndex::Constant(eta_i),
+            RefractiveIndex::Constant(eta_t),
+        )
+    }
+
+    /// - `eta_t`: refractive index model of material the light is entering.
+    /// - `eta_i`: refractive index model of material the light is coming from.
+    pub fn dispersive(eta_i: RefractiveIndex, eta_t: RefractiveIndex) -> Self {
         Self { eta_i, eta_t }
     }
+
+    /// Whether the refractive indices depend on the wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        self.eta_i.is_dispersive() || self.eta_t.is_dispersive()
+    }
+
+    /// # Summary
+    /// Evaluates the refractive indices.
+    ///
+    /// # Arguments
+    /// * `lambda` - The wavelength in nm
+    ///
+    /// # Results
+    /// * `(f32, f32)` - The refractive indices `eta_i` and `eta_t`
+    pub fn etas(&self, lambda: f32) -> (f32, f32) {
+        (self.eta_i.at(lambda), self.eta_t.at(lambda))
+    }
+
+    /// # Summary
+    /// Evaluates the fresnel reflectance for light of a single wavelength.
+    ///
+    /// # Arguments
+    /// * `cos_i` - The cosine of the incident angle
+    /// * `lambda` - The wavelength in nm
+    ///
+    /// # Results
+    /// * `f32` - The reflectance
+    pub fn evaluate_wavelength(&self, cos_i: f32, lambda: f32) -> f32 {
+        let (eta_i, eta_t) = self.etas(lambda);
+
+        fresnel_dielectric(cos_i, eta_i, eta_t)
+    }
+
+    /// # Summary
+    /// Computes the Fresnel amplitudes of the s- and p-polarized parts.
+    ///
+    /// # Arguments
+    /// * `cos_i` - The cosine of the incident angle (negative if exiting)
+    ///
+    /// # Results
+    /// * `(Complex, Complex, Complex, Complex)` - The amplitudes `r_s`, `r_p`, `t_s` and `t_p`
+    fn amplitudes(&self, cos_i: f32) -> (Complex, Complex, Complex, Complex) {
+        let (eta_i, eta_t) = self.etas(REFERENCE_WAVELENGTH);
+        let eta = if cos_i > 0.0 {
+            eta_t / eta_i
+        } else {
+            eta_i / eta_t
+        };
+
+        fresnel_amplitudes(cos_i.abs(), eta.into())
+    }
+
+    /// # Summary
+    /// Computes the change of polarization by the transmission, as Mueller matrix in the s/p
+    /// reference frame, normalized to an intensity of `1`.
+    ///
+    /// # Arguments
+    /// * `cos_i` - The cosine of the incident angle (negative if exiting)
+    ///
+    /// # Results
+    /// * `Mueller<S>` - The normalized Mueller matrix
+    pub fn transmission_polarization<S: Spectrum>(&self, cos_i: f32) -> Mueller<S> {
+        let (_, _, t_s, t_p) = self.amplitudes(cos_i);
+
+        Mueller::from_amplitudes(|_| (t_s, t_p))
+    }
 }
 
-impl Fresnel for Dielectric {
-    fn evaluate(&self, cos_i: f32) -> Spectrum {
-        fresnel_dielectric(cos_i, self.eta_i, self.eta_t).into()
+impl<S: Spectrum> Fresnel<S> for Dielectric {
+    fn evaluate(&self, cos_i: f32) -> S {
+        S::new_const(self.evaluate_wavelength(cos_i, REFERENCE_WAVELENGTH))
+    }
+
+    fn polarization(&self, cos_i: f32) -> Mueller<S> {
+        let (r_s, r_p, _, _) = self.amplitudes(cos_i);
+
+        Mueller::from_amplitudes(|_| (r_s, r_p))
     }
 }
 
 #[derive(Debug)]
-pub struct Conductor {
-    pub eta_t: Spectrum,
-    pub eta_i: Spectrum,
-    pub k: Spectrum,
+pub struct Conductor<S> {
+    pub eta_t: S,
+    pub eta_i: S,
+    pub k: S,
 }
 
-impl Conductor {
+impl<S: Spectrum> Conductor<S> {
     /// - `eta_t`: refractive index of material the light is entering.
     /// - `eta_i`: refractive index of material the light is coming from.
-    pub fn new(eta_t: Spectrum, eta_i: Spectrum, absorption: Spectrum) -> Self {
+    pub fn new(eta_t: S, eta_i: S, absorption: S) -> Self {
         Self {
             eta_t,
             eta_i,
             k: absorption,
         }
     }
+
+    /// # Summary
+    /// Creates a conductor from the spectra of its complex refractive index, surrounded by air.
+    /// The spectra are projected to RGB, if not rendering spectrally.
+    ///
+    /// # Arguments
+    /// * `eta` - The refractive index
+    /// * `k` - The absorption coefficient
+    ///
+    /// # Results
+    /// * `Self` - The conductor
+    pub fn measured(eta: &Spectral, k: &Spectral) -> Self {
+        Self::new(
+            S::from_spectral_reflectance(eta),
+            S::new_const(1.0),
+            S::from_spectral_reflectance(k),
+        )
+    }
+
+    /// # Summary
+    /// Creates a conductor of a tabulated metal, surrounded by air.
+    ///
+    /// # Arguments
+    /// * `metal` - The metal
+    ///
+    /// # Results
+    /// * `Self` - The conductor
+    pub fn metal(metal: Metal) -> Self {
+        Self::measured(&metal.eta(), &metal.k())
+    }
 }
 
-impl Fresnel for Conductor {
-    fn evaluate(&self, cos_i: f32) -> Spectrum {
+impl<S: Spectrum> Fresnel<S> for Conductor<S> {
+    fn evaluate(&self, cos_i: f32) -> S {
         fresnel_conductor(cos_i.abs(), &self.eta_i, &self.eta_t, &self.k)
     }
+
+    fn polarization(&self, cos_i: f32) -> Mueller<S> {
+        Mueller::from_amplitudes(|i| {
+            let eta = Complex::new(self.eta_t[i], self.k[i]) / self.eta_i[i].into();
+            let (r_s, r_p, _, _) = fresnel_amplitudes(cos_i.abs(), eta);
+
+            (r_s, r_p)
+        })
+    }
 }
 
 #[derive(Debug)]
 pub struct FresnelNoOp;
 
-impl Fresnel for FresnelNoOp {
-    fn evaluate(&self, _: f32) -> Spectrum {
-        Spectrum::white()
+impl<S: Spectrum> Fresnel<S> for FresnelNoOp {
+    fn evaluate(&self, _: f32) -> S {
+        S::new_const(1.0)
     }
 }
 
 #[derive(Debug)]
-pub struct FresnelSpecular {
-    r: Spectrum,
-    t: Spectrum,
+pub struct FresnelSpecular<S> {
+    r: S,
+    t: S,
     fresnel: Dielectric,
 }
 
-impl FresnelSpecular {
-    pub fn new(r: Spectrum, t: Spectrum, fresnel: Dielectric) -> Self {
+impl<S> FresnelSpecular<S> {
+    pub fn new(r: S, t: S, fresnel: Dielectric) -> Self {
         Self { r, t, fresnel }
     }
 }
 
-impl BxDF for FresnelSpecular {
+impl<S: Spectrum> BxDF<S> for FresnelSpecular<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::REFLECTION | BxDFType::SPECULAR | BxDFType::TRANSMISSION
     }
 
-    fn evaluate(&self, _: &Vec3, _: &Vec3) -> Spectrum {
-        Spectrum::black()
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
+        S::black()
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        self.sample_wavelength(outgoing, sample, REFERENCE_WAVELENGTH)
     }
 
-    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample {
-        let f = fresnel_dielectric(
-            bxdf::cos_theta(outgoing),
-            self.fresnel.eta_i,
-            self.fresnel.eta_t,
-        );
+    fn sample_wavelength(&self, outgoing: &Vec3, sample: &Vec2, lambda: f32) -> BxDFSample<S> {
+        let (eta_i, eta_t) = self.fresnel.etas(lambda);
+        let f = fresnel_dielectric(bxdf::cos_theta(outgoing), eta_i, eta_t);
 
         if sample.x < f {
-            let incident = Vec3::new(-outgoing.x, -outgoing.y, outgoing.z);
+            let incident = bxdf::bxdf_incident_to(outgoing);
             let pdf = f;
-            let spectrum = self.r * (f / bxdf::cos_theta(&incident).abs());
-
-            BxDFSample::new(spectrum, incident, pdf, self.get_type())
+            let spectrum = self.r * f;
+
+            BxDFSample::new(
+                spectrum,
+                incident,
+                pdf,
+                self.get_type() & !BxDFType::TRANSMISSION,
+            )
         } else {
             let entering = bxdf::cos_theta(outgoing) > 0.0;
 
-            let (eta_i, eta_t, n, typ) = if entering {
-                (
-                    self.fresnel.eta_i,
-                    self.fresnel.eta_t,
-                    Vec3::unit_y(),
-                    self.get_type() & !BxDFType::REFLECTION,
-                )
+            let (eta_i, eta_t, n) = if entering {
+                (eta_i, eta_t, bxdf::bxdf_normal())
             } else {
-                (
-                    self.fresnel.eta_t,
-                    self.fresnel.eta_i,
-                    -Vec3::unit_y(),
-                    self.get_type() & !BxDFType::TRANSMISSION,
-                )
+                (eta_t, eta_i, -bxdf::bxdf_normal())
             };
 
-            let incident = outgoing.refracted(n, eta_i / eta_t);
+            let incident = (-*outgoing).refracted(n, eta_i / eta_t);
             let pdf = 1.0 - f;
             let spectrum = self.t * pdf;
 
-            BxDFSample::new(spectrum, incident, pdf, typ)
+            BxDFSample::new(
+                spectrum,
+                incident,
+                pdf,
+                self.get_type() & !BxDFType::REFLECTION,
+            )
         }
     }
+
+    fn is_dispersive(&self) -> bool {
+        self.fresnel.is_dispersive()
+    }
+
+    fn polarization(&self, incident: &Vec3, outgoing: &Vec3) -> Mueller<S> {
+        let cos_i = bxdf::cos_theta(incident);
+
+        if bxdf::same_hemisphere(incident, outgoing) {
+            self.fresnel.polarization(cos_i)
+        } else {
+            self.fresnel.transmission_polarization(cos_i)
+        }
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        let f = self
+            .fresnel
+            .evaluate_wavelength(bxdf::cos_theta(outgoing), REFERENCE_WAVELENGTH);
+
+        f * self.r.avg() + (1.0 - f) * self.t.avg()
+    }
 }
@@ -4,43 +4,51 @@ use std::f32::consts::FRAC_1_PI;
 use ultraviolet::Vec3;
 
 #[derive(Debug)]
-pub struct LambertianReflection {
-    r: Spectrum,
+pub struct LambertianReflection<S> {
+    r: S,
 }
 
-impl LambertianReflection {
-    pub fn new(r: Spectrum) -> Self {
+impl<S> LambertianReflection<S> {
+    pub fn new(r: S) -> Self {
         Self { r }
     }
 }
 
-impl BxDF for LambertianReflection {
+impl<S: Spectrum> BxDF<S> for LambertianReflection<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::REFLECTION | BxDFType::DIFFUSE
     }
 
-    fn evaluate(&self, _: &Vec3, _: &Vec3) -> Spectrum {
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
         self.r * FRAC_1_PI
     }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        self.r.avg()
+    }
 }
 
 #[derive(Debug)]
-pub struct LambertianTransmission {
-    t: Spectrum,
+pub struct LambertianTransmission<S> {
+    t: S,
 }
 
-impl LambertianTransmission {
-    pub fn new(t: Spectrum) -> Self {
+impl<S> LambertianTransmission<S> {
+    pub fn new(t: S) -> Self {
         Self { t }
     }
 }
 
-impl BxDF for LambertianTransmission {
+impl<S: Spectrum> BxDF<S> for LambertianTransmission<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::DIFFUSE | BxDFType::TRANSMISSION
     }
 
-    fn evaluate(&self, _: &Vec3, _: &Vec3) -> Spectrum {
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
         self.t * FRAC_1_PI
     }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        self.t.avg()
+    }
 }
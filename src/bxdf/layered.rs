@@ -0,0 +1,544 @@
+use std::collections::hash_map::DefaultHasher;
+use std::f32::consts::PI;
+use std::hash::{Hash, Hasher};
+
+use fastrand::Rng;
+use ultraviolet::{Vec2, Vec3};
+
+use crate::bxdf::bsdf::BSDF;
+use crate::bxdf::fresnel::{Dielectric, FresnelSpecular};
+use crate::bxdf::microfacet::{MicrofacetDistribution, RoughDielectric};
+use crate::bxdf::refractive_index::REFERENCE_WAVELENGTH;
+use crate::bxdf::*;
+use crate::mc::power_heuristic;
+use crate::sampler::Sample;
+use crate::Spectrum;
+
+/// The maximum number of bounces inside the layer
+const MAX_DEPTH: u32 = 10;
+/// The number of random walks per evaluation
+const SAMPLES: u32 = 1;
+
+/// The interfaces enclosing the layer.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Layer {
+    Top,
+    Bottom,
+}
+
+impl Layer {
+    fn other(self) -> Self {
+        match self {
+            Layer::Top => Layer::Bottom,
+            Layer::Bottom => Layer::Top,
+        }
+    }
+}
+
+/// # Summary
+/// Seeds a random number generator from the given directions, such that evaluating the same
+/// pair of directions gives the same estimate.
+///
+/// # Arguments
+/// * `a` - The first direction
+/// * `b` - The second direction
+///
+/// # Results
+/// * `Rng` - The seeded generator
+fn seeded_rng(a: &Vec3, b: &Vec3) -> Rng {
+    let mut hasher = DefaultHasher::new();
+    for f in [a.x, a.y, a.z, b.x, b.y, b.z].iter() {
+        f.to_bits().hash(&mut hasher);
+    }
+
+    Rng::with_seed(hasher.finish())
+}
+
+fn max_value<S: Spectrum>(s: &S) -> f32 {
+    (0..S::SIZE).map(|i| s[i]).fold(0.0, f32::max)
+}
+
+/// # Summary
+/// Computes the throughput of a sample, i.e. its value weighted by the cosine and divided by its
+/// pdf, with the conventions of specular samples.
+///
+/// # Arguments
+/// * `sample` - The sample
+///
+/// # Results
+/// * `S` - The throughput
+fn throughput<S: Spectrum>(sample: &BxDFSample<S>) -> S {
+    let cos = if sample.typ.is_specular() {
+        1.0
+    } else {
+        cos_theta(&sample.incident).abs()
+    };
+
+    sample.spectrum * (cos / sample.pdf)
+}
+
+/// A coated surface, placing a dielectric interface over an arbitrary base BSDF, e.g. for car
+/// paint, varnished wood or lacquered plastic.
+///
+/// The layer between both may absorb light. Light bouncing inside the layer is simulated by
+/// random walks (Guo et al., "Position-Free Monte Carlo Simulation for Arbitrary Layered BSDFs",
+/// 2018), such that `evaluate` and `pdf` are stochastic estimates. Both sides of the surface are
+/// coated.
+///
+/// The pdf only approximates the density of the samples, which are weighted by their own ratio of
+/// value and pdf. Integrators may only use it to weight samples against other strategies.
+#[derive(Debug)]
+pub struct CoatedBxDF<S: Spectrum> {
+    interface: Box<dyn BxDF<S>>,
+    base: BSDF<S>,
+    /// The refractive index of the layer relative to the outside
+    eta: f32,
+    /// The optical depth of the layer at normal incidence
+    absorption: S,
+}
+
+impl<S: Spectrum> CoatedBxDF<S> {
+    /// # Summary
+    /// Creates a new coating with a clear layer.
+    ///
+    /// # Arguments
+    /// * `base` - The coated BSDF
+    /// * `fresnel` - The dielectric interface, from the outside into the layer
+    /// * `distribution` - The microfacet distribution of a rough interface, or `None` for a smooth
+    ///   one
+    ///
+    /// # Results
+    /// * `Self` - The coated BxDF
+    pub fn new(
+        base: BSDF<S>,
+        fresnel: Dielectric,
+        distribution: Option<Box<dyn MicrofacetDistribution>>,
+    ) -> Self {
+        let (eta_i, eta_t) = fresnel.etas(REFERENCE_WAVELENGTH);
+
+        let white = S::new_const(1.0);
+        let interface: Box<dyn BxDF<S>> = match distribution {
+            Some(distribution) => {
+                Box::new(RoughDielectric::new(white, white, distribution, fresnel))
+            }
+            None => Box::new(FresnelSpecular::new(white, white, fresnel)),
+        };
+
+        Self {
+            interface,
+            base,
+            eta: eta_t / eta_i,
+            absorption: S::black(),
+        }
+    }
+
+    /// # Summary
+    /// Creates a new coating with an absorbing layer.
+    ///
+    /// # Arguments
+    /// * `base` - The coated BSDF
+    /// * `fresnel` - The dielectric interface, from the outside into the layer
+    /// * `distribution` - The microfacet distribution of a rough interface, or `None` for a smooth
+    ///   one
+    /// * `color` - The color of the layer, i.e. its transmittance at normal incidence for a
+    ///   thickness of `1`
+    /// * `thickness` - The thickness of the layer
+    ///
+    /// # Results
+    /// * `Self` - The coated BxDF
+    pub fn absorbing(
+        base: BSDF<S>,
+        fresnel: Dielectric,
+        distribution: Option<Box<dyn MicrofacetDistribution>>,
+        color: S,
+        thickness: f32,
+    ) -> Self {
+        let mut coated = Self::new(base, fresnel, distribution);
+        for i in 0..S::SIZE {
+            coated.absorption[i] = -color[i].clamp(f32::EPSILON, 1.0).ln() * thickness;
+        }
+
+        coated
+    }
+
+    /// Computes the transmittance of the layer along the direction.
+    fn transmittance(&self, w: &Vec3) -> S {
+        (self.absorption * (-1.0 / cos_theta(w).abs())).exp()
+    }
+
+    /// # Summary
+    /// Computes the scale of a path refracted into the layer by the top interface, which is
+    /// sampled against the flow of light from the incident direction.
+    ///
+    /// Such a path needs the adjoint BTDF, which is the radiance BTDF scaled by `eta^2` (Veach,
+    /// 1997, section 5.2). Rough interfaces scale the radiance by `1 / eta^2` when refracted, such
+    /// that their samples are scaled by `eta^2`. Smooth interfaces do not scale the radiance at
+    /// all, such that their samples already follow the adjoint BTDF. Instead, the refraction out
+    /// of the layer towards the outgoing direction misses its scale of `1 / eta^2`, which is
+    /// applied here once per path.
+    ///
+    /// # Results
+    /// * `f32` - The scale
+    fn reversed_refraction_scale(&self) -> f32 {
+        if self.is_specular(Layer::Top) {
+            1.0 / (self.eta * self.eta)
+        } else {
+            self.eta * self.eta
+        }
+    }
+
+    fn is_specular(&self, layer: Layer) -> bool {
+        match layer {
+            Layer::Top => self.interface.get_type().is_specular(),
+            Layer::Bottom => !self.base.is_type(BxDFType::ALL & !BxDFType::SPECULAR),
+        }
+    }
+
+    fn evaluate_layer(&self, layer: Layer, incident: &Vec3, outgoing: &Vec3) -> S {
+        match layer {
+            Layer::Top => self.interface.evaluate(incident, outgoing),
+            Layer::Bottom => self
+                .base
+                .evaluate(&bxdf_normal(), incident, outgoing, BxDFType::ALL),
+        }
+    }
+
+    fn pdf_layer(&self, layer: Layer, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        match layer {
+            Layer::Top => self.interface.pdf(incident, outgoing),
+            Layer::Bottom => self
+                .base
+                .pdf(&bxdf_normal(), incident, outgoing, BxDFType::ALL),
+        }
+    }
+
+    /// # Summary
+    /// Samples an interface of the layer.
+    ///
+    /// # Arguments
+    /// * `layer` - The interface to sample
+    /// * `outgoing` - The outgoing direction
+    /// * `rng` - The random number generator
+    /// * `reflection` - Whether to only accept reflections, transmissions or both
+    ///
+    /// # Results
+    /// * `Option<BxDFSample<S>>` - The sample, if it scatters light as requested
+    fn sample_layer(
+        &self,
+        layer: Layer,
+        outgoing: &Vec3,
+        rng: &Rng,
+        reflection: Option<bool>,
+    ) -> Option<BxDFSample<S>> {
+        let sample = match layer {
+            Layer::Top => self
+                .interface
+                .sample(outgoing, &Vec2::new(rng.f32(), rng.f32())),
+            Layer::Bottom => {
+                let sample = Sample::new(rng.f32(), Vec2::new(rng.f32(), rng.f32()));
+                self.base
+                    .sample(&bxdf_normal(), outgoing, BxDFType::ALL, &sample)?
+            }
+        };
+
+        if sample.pdf <= 0.0
+            || sample.spectrum.is_black()
+            || bxdf_is_parallel(&sample.incident)
+            || matches!(reflection, Some(r) if r != same_hemisphere(&sample.incident, outgoing))
+        {
+            None
+        } else {
+            Some(sample)
+        }
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for CoatedBxDF<S> {
+    fn get_type(&self) -> BxDFType {
+        let typ = BxDFType::REFLECTION | BxDFType::TRANSMISSION;
+
+        if self.is_specular(Layer::Top) && self.is_specular(Layer::Bottom) {
+            typ | BxDFType::SPECULAR
+        } else {
+            typ | BxDFType::GLOSSY
+        }
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        // light always enters through the top
+        let (incident, outgoing) = if is_neg(outgoing) {
+            (-*incident, -*outgoing)
+        } else {
+            (*incident, *outgoing)
+        };
+
+        if bxdf_is_parallel(&incident) || bxdf_is_parallel(&outgoing) {
+            return S::black();
+        }
+
+        let reflection = same_hemisphere(&incident, &outgoing);
+        let exit = if reflection {
+            Layer::Top
+        } else {
+            Layer::Bottom
+        };
+        let non_exit = exit.other();
+
+        let mut f = if reflection {
+            self.interface.evaluate(&incident, &outgoing) * SAMPLES as f32
+        } else {
+            S::black()
+        };
+
+        let rng = seeded_rng(&incident, &outgoing);
+        for _ in 0..SAMPLES {
+            let entered = match self.sample_layer(Layer::Top, &outgoing, &rng, Some(false)) {
+                Some(s) => s,
+                None => continue,
+            };
+            // the path leaving towards the incident direction, sampled backwards
+            let exited = match self.sample_layer(exit, &incident, &rng, Some(false)) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            let mut exited_throughput = throughput(&exited) * self.transmittance(&exited.incident);
+            if exit == Layer::Top {
+                exited_throughput *= self.reversed_refraction_scale();
+            }
+
+            let mut beta = throughput(&entered);
+            let mut w = entered.incident;
+            let mut layer = Layer::Top;
+
+            for depth in 0..MAX_DEPTH {
+                if depth > 3 {
+                    let max = max_value(&beta);
+                    if max < 0.25 {
+                        let q = f32::max(0.0, 1.0 - max);
+                        if rng.f32() < q {
+                            break;
+                        }
+
+                        beta /= 1.0 - q;
+                    }
+                }
+
+                // cross the layer
+                beta *= self.transmittance(&w);
+                layer = layer.other();
+
+                if layer == exit {
+                    // bounce back into the layer
+                    let sample = match self.sample_layer(exit, &-w, &rng, Some(true)) {
+                        Some(s) => s,
+                        None => break,
+                    };
+
+                    beta *= throughput(&sample);
+                    w = sample.incident;
+                    continue;
+                }
+
+                // connect to the exiting path
+                if !self.is_specular(non_exit) {
+                    let weight = if self.is_specular(exit) {
+                        1.0
+                    } else {
+                        let pdf = self.pdf_layer(non_exit, &-exited.incident, &-w);
+                        power_heuristic(1, exited.pdf, 1, pdf)
+                    };
+
+                    let f_non_exit = self.evaluate_layer(non_exit, &-exited.incident, &-w);
+                    f += beta * f_non_exit * exited_throughput * weight;
+                }
+
+                let sample = match self.sample_layer(non_exit, &-w, &rng, Some(true)) {
+                    Some(s) => s,
+                    None => break,
+                };
+
+                beta *= throughput(&sample);
+                w = sample.incident;
+
+                // leave through the exit interface
+                if !self.is_specular(exit) {
+                    let f_exit = self.evaluate_layer(exit, &incident, &-w);
+
+                    if !f_exit.is_black() {
+                        let weight = if self.is_specular(non_exit) {
+                            1.0
+                        } else {
+                            let pdf = self.pdf_layer(exit, &-w, &incident);
+                            power_heuristic(1, sample.pdf, 1, pdf)
+                        };
+
+                        f += beta * self.transmittance(&w) * f_exit * weight;
+                    }
+                }
+            }
+        }
+
+        f / SAMPLES as f32
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        // light always enters through the top
+        let flip = is_neg(outgoing);
+        let outgoing = if flip { -*outgoing } else { *outgoing };
+
+        let mut top = self.interface.sample(&outgoing, sample);
+        if top.pdf <= 0.0 || top.spectrum.is_black() || bxdf_is_parallel(&top.incident) {
+            return BxDFSample::black_nan_0();
+        }
+
+        // reflected by the interface
+        if same_hemisphere(&top.incident, &outgoing) {
+            if flip {
+                top.incident = -top.incident;
+            }
+
+            return top;
+        }
+
+        let rng = seeded_rng(&outgoing, &Vec3::new(sample.x, sample.y, 0.0));
+
+        let mut beta = throughput(&top);
+        let mut pdf = top.pdf;
+        let mut specular = top.typ.is_specular();
+        let mut w = top.incident;
+        let mut layer = Layer::Top;
+
+        for depth in 0..MAX_DEPTH {
+            if depth > 3 {
+                let max = max_value(&beta);
+                if max < 0.25 {
+                    let q = f32::max(0.0, 1.0 - max);
+                    if rng.f32() < q {
+                        break;
+                    }
+
+                    beta /= 1.0 - q;
+                }
+            }
+
+            // cross the layer and scatter at the other interface
+            beta *= self.transmittance(&w);
+            layer = layer.other();
+
+            let sample = match self.sample_layer(layer, &-w, &rng, None) {
+                Some(s) => s,
+                None => break,
+            };
+
+            beta *= throughput(&sample);
+            pdf *= sample.pdf;
+            specular &= sample.typ.is_specular();
+
+            let left = !same_hemisphere(&sample.incident, &-w);
+            w = sample.incident;
+
+            if left {
+                let typ = if same_hemisphere(&w, &outgoing) {
+                    BxDFType::REFLECTION
+                } else {
+                    BxDFType::TRANSMISSION
+                };
+
+                // only the ratio of the value and the pdf matters
+                let (spectrum, typ) = if specular {
+                    (beta * pdf, typ | BxDFType::SPECULAR)
+                } else {
+                    (beta * (pdf / cos_theta(&w).abs()), typ | BxDFType::GLOSSY)
+                };
+
+                let incident = if flip { -w } else { w };
+
+                return BxDFSample::new(spectrum, incident, pdf, typ);
+            }
+        }
+
+        BxDFSample::black_nan_0()
+    }
+
+    fn is_stochastic(&self) -> bool {
+        true
+    }
+
+    fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        // light always enters through the top
+        let (incident, outgoing) = if is_neg(outgoing) {
+            (-*incident, -*outgoing)
+        } else {
+            (*incident, *outgoing)
+        };
+
+        let reflection = same_hemisphere(&incident, &outgoing);
+
+        let mut pdf = if reflection && !self.is_specular(Layer::Top) {
+            self.interface.pdf(&incident, &outgoing) * SAMPLES as f32
+        } else {
+            0.0
+        };
+
+        let rng = seeded_rng(&outgoing, &incident);
+        for _ in 0..SAMPLES {
+            if reflection {
+                // transmitted through the top, reflected by the base and transmitted again
+                let entered = self.sample_layer(Layer::Top, &outgoing, &rng, Some(false));
+                let exited = self.sample_layer(Layer::Top, &incident, &rng, Some(false));
+
+                let (entered, exited) = match (entered, exited) {
+                    (Some(entered), Some(exited)) => (entered, exited),
+                    _ => continue,
+                };
+
+                if self.is_specular(Layer::Top) {
+                    pdf += self.pdf_layer(Layer::Bottom, &-exited.incident, &-entered.incident);
+                    continue;
+                }
+
+                let reflected =
+                    match self.sample_layer(Layer::Bottom, &-entered.incident, &rng, Some(true)) {
+                        Some(s) => s,
+                        None => continue,
+                    };
+
+                if self.is_specular(Layer::Bottom) {
+                    pdf += self.pdf_layer(Layer::Top, &incident, &-reflected.incident);
+                } else {
+                    let pdf_base =
+                        self.pdf_layer(Layer::Bottom, &-exited.incident, &-entered.incident);
+                    pdf += power_heuristic(1, exited.pdf, 1, pdf_base) * pdf_base;
+
+                    let pdf_top = self.pdf_layer(Layer::Top, &incident, &-reflected.incident);
+                    pdf += power_heuristic(1, reflected.pdf, 1, pdf_top) * pdf_top;
+                }
+            } else {
+                // transmitted through the top and the base
+                let entered = self.sample_layer(Layer::Top, &outgoing, &rng, Some(false));
+                let exited = self.sample_layer(Layer::Bottom, &incident, &rng, Some(false));
+
+                let (entered, exited) = match (entered, exited) {
+                    (Some(entered), Some(exited)) => (entered, exited),
+                    _ => continue,
+                };
+
+                pdf += if self.is_specular(Layer::Top) {
+                    self.pdf_layer(Layer::Bottom, &incident, &-entered.incident)
+                } else if self.is_specular(Layer::Bottom) {
+                    self.pdf_layer(Layer::Top, &-exited.incident, &outgoing)
+                } else {
+                    (self.pdf_layer(Layer::Top, &-exited.incident, &outgoing)
+                        + self.pdf_layer(Layer::Bottom, &incident, &-entered.incident))
+                        / 2.0
+                };
+            }
+        }
+
+        // mix with a uniform pdf to account for paths not estimated above
+        let uniform = 1.0 / (4.0 * PI);
+
+        0.1 * uniform + 0.9 * pdf / SAMPLES as f32
+    }
+}
@@ -0,0 +1,91 @@
+//! Complex refractive indices of common metals.
+//!
+//! The tables are coarse approximations (every 50 nm) of the measured optical constants of
+//! Johnson and Christy (1972) for gold, silver and copper, of Rakić (1995) for aluminium, and of
+//! Johnson and Christy (1974) for chromium and titanium.
+//! For accurate renderings, load the full measured data with `Spectral::load` and create the
+//! conductor with `Conductor::measured`.
+
+use color::Spectral;
+use std::convert::TryInto;
+
+const METAL_SAMPLES: usize = 7;
+/// The wavelengths in nm of the samples of the tables.
+const METAL_LAMBDA: [f32; METAL_SAMPLES] = [400.0, 450.0, 500.0, 550.0, 600.0, 650.0, 700.0];
+
+const GOLD_ETA: [f32; METAL_SAMPLES] = [1.47, 1.38, 0.97, 0.42, 0.25, 0.17, 0.16];
+const GOLD_K: [f32; METAL_SAMPLES] = [1.95, 1.92, 1.87, 2.35, 2.98, 3.50, 3.95];
+
+const SILVER_ETA: [f32; METAL_SAMPLES] = [0.05, 0.04, 0.05, 0.06, 0.06, 0.05, 0.04];
+const SILVER_K: [f32; METAL_SAMPLES] = [2.07, 2.65, 3.09, 3.59, 4.00, 4.48, 4.84];
+
+const COPPER_ETA: [f32; METAL_SAMPLES] = [1.18, 1.24, 1.12, 1.02, 0.27, 0.21, 0.21];
+const COPPER_K: [f32; METAL_SAMPLES] = [2.21, 2.40, 2.56, 2.58, 3.41, 3.67, 4.20];
+
+const ALUMINIUM_ETA: [f32; METAL_SAMPLES] = [0.49, 0.62, 0.77, 0.96, 1.20, 1.49, 1.83];
+const ALUMINIUM_K: [f32; METAL_SAMPLES] = [4.86, 5.47, 6.08, 6.69, 7.26, 7.79, 8.31];
+
+const CHROMIUM_ETA: [f32; METAL_SAMPLES] = [2.01, 2.41, 2.75, 3.02, 3.18, 3.23, 3.26];
+const CHROMIUM_K: [f32; METAL_SAMPLES] = [3.17, 3.31, 3.33, 3.33, 3.33, 3.32, 3.34];
+
+const TITANIUM_ETA: [f32; METAL_SAMPLES] = [1.86, 2.03, 2.21, 2.45, 2.67, 2.86, 3.03];
+const TITANIUM_K: [f32; METAL_SAMPLES] = [2.59, 2.80, 3.03, 3.22, 3.38, 3.50, 3.60];
+
+/// A metal of tabulated optical constants.
+#[derive(Debug, Copy, Clone)]
+pub enum Metal {
+    Gold,
+    Silver,
+    Copper,
+    Aluminium,
+    Chromium,
+    Titanium,
+}
+
+impl Metal {
+    fn tables(&self) -> (&[f32; METAL_SAMPLES], &[f32; METAL_SAMPLES]) {
+        match self {
+            Metal::Gold => (&GOLD_ETA, &GOLD_K),
+            Metal::Silver => (&SILVER_ETA, &SILVER_K),
+            Metal::Copper => (&COPPER_ETA, &COPPER_K),
+            Metal::Aluminium => (&ALUMINIUM_ETA, &ALUMINIUM_K),
+            Metal::Chromium => (&CHROMIUM_ETA, &CHROMIUM_K),
+            Metal::Titanium => (&TITANIUM_ETA, &TITANIUM_K),
+        }
+    }
+
+    /// # Summary
+    /// Creates the spectrum of the refractive index (the real part).
+    ///
+    /// # Results
+    /// * `Spectral` - The refractive index
+    pub fn eta(&self) -> Spectral {
+        Spectral::from_sampled(&METAL_LAMBDA, self.tables().0, METAL_SAMPLES)
+    }
+
+    /// # Summary
+    /// Creates the spectrum of the absorption coefficient (the imaginary part).
+    ///
+    /// # Results
+    /// * `Spectral` - The absorption coefficient
+    pub fn k(&self) -> Spectral {
+        Spectral::from_sampled(&METAL_LAMBDA, self.tables().1, METAL_SAMPLES)
+    }
+}
+
+impl TryInto<Metal> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<Metal, Self::Error> {
+        match self {
+            "gold" | "Gold" | "GOLD" | "au" | "Au" | "AU" => Ok(Metal::Gold),
+            "silver" | "Silver" | "SILVER" | "ag" | "Ag" | "AG" => Ok(Metal::Silver),
+            "copper" | "Copper" | "COPPER" | "cu" | "Cu" | "CU" => Ok(Metal::Copper),
+            "aluminium" | "Aluminium" | "ALUMINIUM" | "aluminum" | "Aluminum" | "ALUMINUM"
+            | "al" | "Al" | "AL" => Ok(Metal::Aluminium),
+            "chromium" | "Chromium" | "CHROMIUM" | "cr" | "Cr" | "CR" => Ok(Metal::Chromium),
+            "titanium" | "Titanium" | "TITANIUM" | "ti" | "Ti" | "TI" => Ok(Metal::Titanium),
+            _ => Err(self.to_string()),
+        }
+    }
+}
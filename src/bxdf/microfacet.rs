@@ -2,11 +2,10 @@ use std::f32::consts::{FRAC_PI_2, PI};
 
 use ultraviolet::{Vec2, Vec3};
 
-use color::Color;
-use geometry::spherical_direction;
 use util::{floats, math};
 
-use crate::bxdf::fresnel::Fresnel;
+use crate::bxdf::fresnel::{Dielectric, Fresnel};
+use crate::bxdf::refractive_index::REFERENCE_WAVELENGTH;
 use crate::bxdf::*;
 use crate::Spectrum;
 
@@ -36,7 +35,14 @@ pub trait MicrofacetDistribution: Debug + Send + Sync {
 
     fn pdf(&self, wo: &Vec3, wh: &Vec3) -> f32 {
         if self.is_sample_visible_area() {
-            self.d(wh) * self.g1(wo) * wo.dot(*wh).abs() / cos_theta(wo).abs()
+            // only normals facing wo are visible, on whichever side wh is given
+            let cos_o = if same_hemisphere(wo, wh) {
+                wo.dot(*wh)
+            } else {
+                -wo.dot(*wh)
+            };
+
+            self.d(wh) * self.g1(wo) * cos_o.max(0.0) / cos_theta(wo).abs()
         } else {
             self.d(wh) * cos_theta(wh).abs()
         }
@@ -144,8 +150,8 @@ impl BeckmannDistribution {
     }
 
     fn beckmann_sample(wi: &Vec3, alpha_x: f32, alpha_y: f32, sample: &Vec2) -> Vec3 {
-        // 1. stretch wi
-        let wi_stretched = Vec3::new(alpha_x * wi.x, alpha_y * wi.y, wi.z).normalized();
+        // 1. stretch wi (our normal is the y-axis)
+        let wi_stretched = Vec3::new(alpha_x * wi.x, wi.y, alpha_y * wi.z).normalized();
 
         // 2. simulate P22_{wi}(x_slope, y_slope, 1, 1)
         let cos_theta = cos_theta(&wi_stretched);
@@ -163,7 +169,7 @@ impl BeckmannDistribution {
         slope.y *= alpha_y;
 
         // 5. compute normal
-        Vec3::new(-slope.x, -slope.y, 1.0).normalized()
+        Vec3::new(-slope.x, 1.0, -slope.y).normalized()
     }
 }
 
@@ -176,12 +182,10 @@ impl MicrofacetDistribution for BeckmannDistribution {
             let cos2_theta = cos2_theta(wh);
             let cos4_theta = cos2_theta * cos2_theta;
 
-            let alpha_x2 = self.alpha_x * self.alpha_x;
+            let cos2 = cos2_phi(wh) / (self.alpha_x * self.alpha_x);
+            let sin2 = sin2_phi(wh) / (self.alpha_y * self.alpha_y);
 
-            let cos2 = cos2_phi(wh) / alpha_x2;
-            let sin2 = sin2_phi(wh) / self.alpha_y * self.alpha_y;
-
-            f32::exp(-tan2_theta * (cos2 + sin2)) / (PI * alpha_x2 * cos4_theta)
+            f32::exp(-tan2_theta * (cos2 + sin2)) / (PI * self.alpha_x * self.alpha_y * cos4_theta)
         }
     }
 
@@ -197,7 +201,7 @@ impl MicrofacetDistribution for BeckmannDistribution {
 
         let alpha = f32::sqrt(cos2 + sin2);
         let a = 1.0 / (alpha * tan_theta.abs());
-        if a >= floats::BIG_EPSILON {
+        if a >= 1.6 {
             0.0
         } else {
             (1.0 - 1.259 * a + 0.396 * a * a) / (3.535 * a + 2.181 * a * a)
@@ -226,13 +230,13 @@ impl MicrofacetDistribution for BeckmannDistribution {
 
         let tan2_theta: f32;
         let phi: f32;
-        if sample.x == sample.y {
+        if self.alpha_x == self.alpha_y {
             tan2_theta = -alpha_x2 * log_sample;
             phi = floats::PI_2 * sample.y;
         } else {
             // Compute _tan2Theta_ and _phi_ for anisotropic Beckmann
             // distribution
-            let tan = f32::tan(floats::PI_2 * sample.y * FRAC_PI_2);
+            let tan = f32::tan(floats::PI_2 * sample.y + FRAC_PI_2);
             let mut phi_new = f32::atan(self.alpha_y * tan / self.alpha_x);
             if sample.y > 0.5 {
                 phi_new += PI;
@@ -247,11 +251,11 @@ impl MicrofacetDistribution for BeckmannDistribution {
                     + sin_phi * sin_phi / (self.alpha_y * self.alpha_y));
         }
 
-        // Map sampled Beckmann angles to normal direction _wh_
-        let cos_theta = 1.0 / f32::sqrt(1.0 - tan2_theta);
+        // Map sampled Beckmann angles to normal direction _wh_ around the y-axis
+        let cos_theta = 1.0 / f32::sqrt(1.0 + tan2_theta);
         let sin_theta = 0f32.max(1.0 - cos_theta * cos_theta).sqrt();
 
-        let mut wh = spherical_direction(sin_theta, cos_theta, phi);
+        let mut wh = Vec3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
         if !same_hemisphere(wo, &wh) {
             wh = -wh;
         }
@@ -264,60 +268,167 @@ impl MicrofacetDistribution for BeckmannDistribution {
     }
 }
 
-// pub struct TrowbridgeReitzDistribution {
-//     alpha_x: f32,
-//     alpha_y: f32,
-//     sample_visible_area: bool
-// }
-//
-// impl TrowbridgeReitzDistribution {
-//     pub fn new(alpha_x: f32, alpha_y: f32, sample_visible_area: bool) -> Self {
-//         Self { alpha_x, alpha_y, sample_visible_area }
-//     }
-// }
-//
-// impl MicrofacetDistribution for TrowbridgeReitzDistribution {
-//     fn d(&self, wh: &Vec3) -> f32 {
-//         unimplemented!()
-//     }
-//
-//     fn lambda(&self, w: &Vec3) -> f32 {
-//         let tan_theta = tan_theta(w);
-//         if tan_theta.is_infinite() {
-//             return 0.0;
-//         }
-//
-//         let tan2 = tan_theta * tan_theta;
-//         let cos2 = cos2_phi(w) * self.alpha_x * self.alpha_x;
-//         let sin2 = sin2_phi(w) * self.alpha_y * self.alpha_y;
-//
-//         let alpha = f32::sqrt(cos2 + sin2);
-//         let alpha2_tan2 = tan2 * alpha * alpha;
-//
-//         (-1.0 + f32::sqrt(1.0 + alpha2_tan2)) / 2.0
-//     }
-//
-//     fn sample_wh(&self, wo: &Vec3, sample: &Vec2) -> Vec3 {
-//         unimplemented!()
-//     }
-//
-//     fn pdf(&self, wo: &Vec3, wh: &Vec3) -> f32 {
-//         unimplemented!()
-//     }
-// }
+/// The anisotropic Trowbridge-Reitz (GGX) distribution of microfacet normals.
+///
+/// Compared to Beckmann, its tails are longer, giving highlights a softer falloff.
+#[derive(Debug)]
+pub struct TrowbridgeReitzDistribution {
+    alpha_x: f32,
+    alpha_y: f32,
+    sample_visible_area: bool,
+}
+
+impl TrowbridgeReitzDistribution {
+    pub fn new(alpha_x: f32, alpha_y: f32, sample_visible_area: bool) -> Self {
+        Self {
+            alpha_x,
+            alpha_y,
+            sample_visible_area,
+        }
+    }
+
+    /// # Summary
+    /// Samples a normal visible from `wo` (Heitz, "Sampling the GGX Distribution of Visible
+    /// Normals", 2018).
+    ///
+    /// # Arguments
+    /// * `wo` - The outgoing direction in the upper hemisphere
+    /// * `sample` - The sample space
+    ///
+    /// # Results
+    /// * `Vec3` - The sampled normal
+    fn sample_visible(&self, wo: &Vec3, sample: &Vec2) -> Vec3 {
+        // the paper samples around the z-axis, our normal is the y-axis
+        let wo = Vec3::new(wo.x, wo.z, wo.y);
+
+        // 1. stretch wo into the configuration of alpha = 1
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normalized();
+
+        // 2. orthonormal basis around vh
+        let len2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt()
+        } else {
+            Vec3::unit_x()
+        };
+        let t2 = vh.cross(t1);
+
+        // 3. sample the projected hemisphere, a disk warped by the visibility of vh
+        let r = sample.x.sqrt();
+        let phi = floats::PI_2 * sample.y;
+        let p1 = r * phi.cos();
+        let p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * f32::sqrt(1.0 - p1 * p1) + s * p2;
+
+        // 4. reproject onto the hemisphere
+        let nh = t1 * p1 + t2 * p2 + vh * f32::sqrt(0f32.max(1.0 - p1 * p1 - p2 * p2));
+
+        // 5. unstretch
+        let wh = Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).normalized();
+
+        Vec3::new(wh.x, wh.z, wh.y)
+    }
+}
+
+impl MicrofacetDistribution for TrowbridgeReitzDistribution {
+    fn d(&self, wh: &Vec3) -> f32 {
+        let tan2_theta = tan2_theta(wh);
+        if tan2_theta.is_infinite() {
+            return 0.0;
+        }
+
+        let cos2_theta = cos2_theta(wh);
+        let cos4_theta = cos2_theta * cos2_theta;
+
+        let cos2 = cos2_phi(wh) / (self.alpha_x * self.alpha_x);
+        let sin2 = sin2_phi(wh) / (self.alpha_y * self.alpha_y);
+
+        let e = 1.0 + tan2_theta * (cos2 + sin2);
+
+        1.0 / (PI * self.alpha_x * self.alpha_y * cos4_theta * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f32 {
+        let tan_theta = tan_theta(w);
+        if tan_theta.is_infinite() {
+            return 0.0;
+        }
+
+        // Compute _alpha_ for direction _w_
+        let cos2 = cos2_phi(w) * self.alpha_x * self.alpha_x;
+        let sin2 = sin2_phi(w) * self.alpha_y * self.alpha_y;
+
+        let alpha2_tan2 = (cos2 + sin2) * tan_theta * tan_theta;
+
+        (-1.0 + f32::sqrt(1.0 + alpha2_tan2)) / 2.0
+    }
+
+    fn sample_wh(&self, wo: &Vec3, sample: &Vec2) -> Vec3 {
+        if self.sample_visible_area {
+            let is_neg = is_neg(wo);
+            let wo_new = if is_neg { -*wo } else { *wo };
+
+            let mut wh = self.sample_visible(&wo_new, sample);
+            if is_neg {
+                wh = -wh;
+            }
+
+            return wh;
+        }
+
+        // Sample full distribution of normals for Trowbridge-Reitz distribution
+        let tan2_theta: f32;
+        let phi: f32;
+        if self.alpha_x == self.alpha_y {
+            tan2_theta = self.alpha_x * self.alpha_x * sample.x / (1.0 - sample.x);
+            phi = floats::PI_2 * sample.y;
+        } else {
+            let tan = f32::tan(floats::PI_2 * sample.y + FRAC_PI_2);
+            let mut phi_new = f32::atan(self.alpha_y / self.alpha_x * tan);
+            if sample.y > 0.5 {
+                phi_new += PI;
+            }
+            phi = phi_new;
+
+            let sin_phi = phi.sin();
+            let cos_phi = phi.cos();
+            let alpha2 = 1.0
+                / (cos_phi * cos_phi / (self.alpha_x * self.alpha_x)
+                    + sin_phi * sin_phi / (self.alpha_y * self.alpha_y));
+
+            tan2_theta = alpha2 * sample.x / (1.0 - sample.x);
+        }
+
+        // Map sampled Trowbridge-Reitz angles to normal direction _wh_ around the y-axis
+        let cos_theta = 1.0 / f32::sqrt(1.0 + tan2_theta);
+        let sin_theta = 0f32.max(1.0 - cos_theta * cos_theta).sqrt();
+
+        let mut wh = Vec3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+        if !same_hemisphere(wo, &wh) {
+            wh = -wh;
+        }
+
+        wh
+    }
+
+    fn is_sample_visible_area(&self) -> bool {
+        self.sample_visible_area
+    }
+}
 
 #[derive(Debug)]
-pub struct MicrofacetReflection {
-    r: Spectrum,
+pub struct MicrofacetReflection<S: Spectrum> {
+    r: S,
     distribution: Box<dyn MicrofacetDistribution>,
-    fresnel: Box<dyn Fresnel>,
+    fresnel: Box<dyn Fresnel<S>>,
 }
 
-impl MicrofacetReflection {
+impl<S: Spectrum> MicrofacetReflection<S> {
     pub fn new(
-        r: Spectrum,
+        r: S,
         distribution: Box<dyn MicrofacetDistribution>,
-        fresnel: Box<dyn Fresnel>,
+        fresnel: Box<dyn Fresnel<S>>,
     ) -> Self {
         Self {
             r,
@@ -327,21 +438,21 @@ impl MicrofacetReflection {
     }
 }
 
-impl BxDF for MicrofacetReflection {
+impl<S: Spectrum> BxDF<S> for MicrofacetReflection<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::REFLECTION | BxDFType::GLOSSY
     }
 
-    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> Spectrum {
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
         let cos_theta_i = cos_theta(incident).abs();
         let cos_theta_o = cos_theta(outgoing).abs();
         if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
-            Spectrum::black();
+            return S::black();
         }
 
         let wh = *incident + *outgoing;
         if wh == Vec3::zero() {
-            Spectrum::black();
+            return S::black();
         }
 
         let wh = wh.normalized();
@@ -352,7 +463,7 @@ impl BxDF for MicrofacetReflection {
         dist * f / (4.0 * cos_theta_i * cos_theta_o)
     }
 
-    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample {
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
         // Sample microfacet orientation $\wh$ and reflected direction $\wi$
         if bxdf_is_parallel(outgoing) {
             return BxDFSample::black_nan_0();
@@ -365,7 +476,7 @@ impl BxDF for MicrofacetReflection {
             return BxDFSample::black_nan_0();
         }
 
-        let incident = outgoing.reflected(wh);
+        let incident = -outgoing.reflected(wh);
         if !same_hemisphere(&incident, outgoing) {
             return BxDFSample::black_nan_0();
         }
@@ -385,122 +496,385 @@ impl BxDF for MicrofacetReflection {
             self.distribution.pdf(outgoing, &wh) / (4.0 * outgoing.dot(wh))
         }
     }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        (self.fresnel.evaluate(cos_theta(outgoing).abs()) * self.r).avg()
+    }
+}
+
+/// # Summary
+/// Computes the refractive index of the side of the incident direction relative to the side of
+/// the outgoing direction.
+///
+/// # Arguments
+/// * `fresnel` - The dielectric interface
+/// * `outgoing` - The outgoing direction
+///
+/// # Results
+/// * `f32` - The relative refractive index
+fn relative_eta(fresnel: &Dielectric, outgoing: &Vec3) -> f32 {
+    let (eta_i, eta_t) = fresnel.etas(REFERENCE_WAVELENGTH);
+
+    if cos_theta(outgoing) > 0.0 {
+        eta_t / eta_i
+    } else {
+        eta_i / eta_t
+    }
+}
+
+/// # Summary
+/// Computes the microfacet normal refracting `outgoing` into `incident`, oriented towards the
+/// upper hemisphere.
+///
+/// # Arguments
+/// * `incident` - The incident direction
+/// * `outgoing` - The outgoing direction
+/// * `eta` - The relative refractive index
+///
+/// # Results
+/// * `Option<Vec3>` - The microfacet normal or `None` if the directions cannot be refracted
+///   into each other
+fn refraction_half_vector(incident: &Vec3, outgoing: &Vec3, eta: f32) -> Option<Vec3> {
+    let wh = *outgoing + *incident * eta;
+    if wh == Vec3::zero() {
+        return None;
+    }
+
+    let wh = wh.normalized();
+    let wh = if is_neg(&wh) { -wh } else { wh };
+
+    // the microfacet must face both directions from their sides of the surface, which puts
+    // them on different sides of the microfacet
+    if outgoing.dot(wh) * cos_theta(outgoing) <= 0.0
+        || incident.dot(wh) * cos_theta(incident) <= 0.0
+    {
+        None
+    } else {
+        Some(wh)
+    }
+}
+
+/// # Summary
+/// Evaluates the transmission through microfacets between directions on different sides.
+///
+/// # Arguments
+/// * `distribution` - The microfacet distribution
+/// * `fresnel` - The dielectric interface
+/// * `incident` - The incident direction
+/// * `outgoing` - The outgoing direction
+///
+/// # Results
+/// * `f32` - The transmitted fraction
+fn evaluate_transmission(
+    distribution: &dyn MicrofacetDistribution,
+    fresnel: &Dielectric,
+    incident: &Vec3,
+    outgoing: &Vec3,
+) -> f32 {
+    let cos_theta_i = cos_theta(incident);
+    let cos_theta_o = cos_theta(outgoing);
+    if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
+        return 0.0;
+    }
+
+    let eta = relative_eta(fresnel, outgoing);
+    let wh = match refraction_half_vector(incident, outgoing, eta) {
+        Some(wh) => wh,
+        None => return 0.0,
+    };
+
+    let cos_o = outgoing.dot(wh);
+    let cos_i = incident.dot(wh);
+    let f = fresnel.evaluate_wavelength(cos_o, REFERENCE_WAVELENGTH);
+
+    // the radiance is scaled by 1 / eta^2 when refracted, cancelling the eta^2 of the jacobian
+    let sqrt_denom = cos_o + eta * cos_i;
+    let dist = distribution.d(&wh) * distribution.g(incident, outgoing);
+    let factor = cos_i * cos_o / (cos_theta_i * cos_theta_o * sqrt_denom * sqrt_denom);
+
+    (1.0 - f) * (dist * factor).abs()
+}
+
+/// # Summary
+/// Computes the pdf of sampling the transmission through microfacets.
+///
+/// # Arguments
+/// * `distribution` - The microfacet distribution
+/// * `fresnel` - The dielectric interface
+/// * `incident` - The incident direction
+/// * `outgoing` - The outgoing direction
+///
+/// # Results
+/// * `f32` - The pdf
+fn pdf_transmission(
+    distribution: &dyn MicrofacetDistribution,
+    fresnel: &Dielectric,
+    incident: &Vec3,
+    outgoing: &Vec3,
+) -> f32 {
+    let eta = relative_eta(fresnel, outgoing);
+    let wh = match refraction_half_vector(incident, outgoing, eta) {
+        Some(wh) => wh,
+        None => return 0.0,
+    };
+
+    let cos_i = incident.dot(wh);
+    let sqrt_denom = outgoing.dot(wh) + eta * cos_i;
+    let dwh_dwi = eta * eta * cos_i.abs() / (sqrt_denom * sqrt_denom);
+
+    distribution.pdf(outgoing, &wh) * dwh_dwi
+}
+
+/// # Summary
+/// Samples a direction refracted through a microfacet.
+///
+/// # Arguments
+/// * `distribution` - The microfacet distribution
+/// * `fresnel` - The dielectric interface
+/// * `outgoing` - The outgoing direction
+/// * `sample` - The sample space
+///
+/// # Results
+/// * `Option<Vec3>` - The incident direction or `None` if not refracted
+fn sample_transmission(
+    distribution: &dyn MicrofacetDistribution,
+    fresnel: &Dielectric,
+    outgoing: &Vec3,
+    sample: &Vec2,
+) -> Option<Vec3> {
+    let wh = distribution.sample_wh(outgoing, sample);
+    // Should be rare
+    if outgoing.dot(wh) < 0.0 {
+        return None;
+    }
+
+    // total internal reflection
+    let incident = (-*outgoing).refracted(wh, 1.0 / relative_eta(fresnel, outgoing));
+    if incident == Vec3::zero() || same_hemisphere(&incident, outgoing) {
+        None
+    } else {
+        Some(incident)
+    }
+}
+
+/// A BTDF of rough dielectric surfaces like frosted glass, transmitting light through
+/// microfacets of the given distribution.
+#[derive(Debug)]
+pub struct MicrofacetTransmission<S> {
+    t: S,
+    distribution: Box<dyn MicrofacetDistribution>,
+    fresnel: Dielectric,
+}
+
+impl<S: Spectrum> MicrofacetTransmission<S> {
+    pub fn new(t: S, distribution: Box<dyn MicrofacetDistribution>, fresnel: Dielectric) -> Self {
+        Self {
+            t,
+            distribution,
+            fresnel,
+        }
+    }
+}
+
+impl<S: Spectrum> BxDF<S> for MicrofacetTransmission<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::TRANSMISSION | BxDFType::GLOSSY
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        if same_hemisphere(incident, outgoing) {
+            return S::black();
+        }
+
+        let distribution = self.distribution.as_ref();
+
+        self.t * evaluate_transmission(distribution, &self.fresnel, incident, outgoing)
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        if bxdf_is_parallel(outgoing) {
+            return BxDFSample::black_nan_0();
+        }
+
+        let distribution = self.distribution.as_ref();
+        let incident = match sample_transmission(distribution, &self.fresnel, outgoing, sample) {
+            Some(incident) => incident,
+            None => return BxDFSample::black_nan_0(),
+        };
+
+        let spectrum = self.evaluate(&incident, outgoing);
+        let pdf = self.pdf(&incident, outgoing);
+
+        BxDFSample::new(spectrum, incident, pdf, self.get_type())
+    }
+
+    fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        if same_hemisphere(incident, outgoing) {
+            return 0.0;
+        }
+
+        let distribution = self.distribution.as_ref();
+
+        pdf_transmission(distribution, &self.fresnel, incident, outgoing)
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        let f = self
+            .fresnel
+            .evaluate_wavelength(cos_theta(outgoing), REFERENCE_WAVELENGTH);
+
+        (1.0 - f) * self.t.avg()
+    }
+}
+
+/// A rough dielectric surface, reflecting and transmitting light through microfacets of the
+/// given distribution.
+///
+/// Like `FresnelSpecular`, either reflection or transmission is sampled, chosen by the Fresnel
+/// reflectance of the macro surface.
+#[derive(Debug)]
+pub struct RoughDielectric<S> {
+    r: S,
+    t: S,
+    distribution: Box<dyn MicrofacetDistribution>,
+    fresnel: Dielectric,
+}
+
+impl<S: Spectrum> RoughDielectric<S> {
+    pub fn new(
+        r: S,
+        t: S,
+        distribution: Box<dyn MicrofacetDistribution>,
+        fresnel: Dielectric,
+    ) -> Self {
+        Self {
+            r,
+            t,
+            distribution,
+            fresnel,
+        }
+    }
+
+    /// # Summary
+    /// Computes the probability of sampling the reflection.
+    /// It is clamped, as microfacets may still transmit light where the macro surface reflects
+    /// totally and vice versa.
+    ///
+    /// # Arguments
+    /// * `outgoing` - The outgoing direction
+    ///
+    /// # Results
+    /// * `f32` - The probability
+    fn reflection_probability(&self, outgoing: &Vec3) -> f32 {
+        self.fresnel
+            .evaluate_wavelength(cos_theta(outgoing), REFERENCE_WAVELENGTH)
+            .clamp(0.05, 0.95)
+    }
+
+    fn evaluate_reflection(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        let cos_theta_i = cos_theta(incident).abs();
+        let cos_theta_o = cos_theta(outgoing).abs();
+        if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
+            return S::black();
+        }
+
+        let wh = *incident + *outgoing;
+        if wh == Vec3::zero() {
+            return S::black();
+        }
+
+        // oriented towards the upper hemisphere, such that the fresnel term knows the side
+        let wh = wh.normalized();
+        let wh = if is_neg(&wh) { -wh } else { wh };
+
+        let f = self
+            .fresnel
+            .evaluate_wavelength(outgoing.dot(wh), REFERENCE_WAVELENGTH);
+        let dist = self.distribution.d(&wh) * self.distribution.g(incident, outgoing);
+
+        self.r * (f * dist / (4.0 * cos_theta_i * cos_theta_o))
+    }
+
+    fn pdf_reflection(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        let wh = *incident + *outgoing;
+        if wh == Vec3::zero() {
+            return 0.0;
+        }
+
+        let wh = wh.normalized();
+
+        self.distribution.pdf(outgoing, &wh) / (4.0 * outgoing.dot(wh).abs())
+    }
 }
 
-// #[derive(Debug)]
-// pub struct MicrofacetTransmission {
-//     t: Spectrum,
-//     distribution: Box<dyn MicrofacetDistribution>,
-//     fresnel: Dielectric,
-// }
-//
-// impl MicrofacetTransmission {
-//     pub fn new(
-//         t: Spectrum,
-//         distribution: Box<dyn MicrofacetDistribution>,
-//         fresnel: Dielectric,
-//     ) -> Self {
-//         Self {
-//             t,
-//             distribution,
-//             fresnel,
-//         }
-//     }
-// }
-//
-// impl BxDF for MicrofacetTransmission {
-//     fn get_type(&self) -> BxDFType {
-//         BxDFType::TRANSMISSION | BxDFType::GLOSSY
-//     }
-//
-//     fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> Spectrum {
-//         if same_hemisphere(incident, outgoing) {
-//             return Spectrum::black();
-//         }
-//
-//         let cos_theta_i = cos_theta(incident);
-//         let cos_theta_o = cos_theta(outgoing);
-//         if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
-//             return Spectrum::black();
-//         }
-//
-//         // Compute $\wh$ from $\wo$ and $\wi$ for microfacet transmission
-//         let (eta, wh) = {
-//             let eta = if cos_theta_o > 0.0 {
-//                 self.fresnel.eta_t / self.fresnel.eta_i
-//             } else {
-//                 self.fresnel.eta_i / self.fresnel.eta_t
-//             };
-//             let wh = flip_if_neg(*outgoing + *incident * eta);
-//             (eta, wh)
-//         };
-//
-//         // Same side?
-//         let cos_i = outgoing.dot(wh);
-//         let cos_t = incident.dot(wh);
-//         if cos_i * cos_t > 0.0 {
-//             return Spectrum::black();
-//         }
-//
-//         let f = self.fresnel.evaluate(cos_i);
-//
-//         let sqrt_denom = cos_i + eta * cos_t;
-//
-//         let t = (Spectrum::new_const(1.0) - f) * self.t;
-//         let dist = self.distribution.d(&wh) * self.distribution.g(incident, outgoing);
-//         let factor =
-//             cos_i.abs() * cos_t.abs() / (cos_theta_i * cos_theta_i * sqrt_denom * sqrt_denom);
-//
-//         t * (dist * factor).abs()
-//     }
-//
-//     fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample {
-//         if bxdf_is_parallel(outgoing) {
-//             return BxDFSample::black_nan_0();
-//         }
-//
-//         let wh = self.distribution.sample_wh(outgoing, sample);
-//         // Should be rare
-//         if outgoing.dot(wh) < 0.0 {
-//             return BxDFSample::black_nan_0();
-//         }
-//
-//         let eta = if cos_theta(outgoing) > 0.0 {
-//             self.fresnel.eta_i / self.fresnel.eta_t
-//         } else {
-//             self.fresnel.eta_t / self.fresnel.eta_i
-//         };
-//
-//         let incident = outgoing.refracted(wh, eta);
-//         let spectrum = self.evaluate(&incident, outgoing);
-//         let pdf = self.pdf(&incident, outgoing);
-//
-//         BxDFSample::new(spectrum, incident, pdf, self.get_type())
-//     }
-//
-//     fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
-//         if same_hemisphere(incident, outgoing) {
-//             return 0.0;
-//         }
-//
-//         // Compute $\wh$ from $\wo$ and $\wi$ for microfacet transmission
-//         let eta = if cos_theta(outgoing) > 0.0 {
-//             self.fresnel.eta_i / self.fresnel.eta_t
-//         } else {
-//             self.fresnel.eta_t / self.fresnel.eta_i
-//         };
-//
-//         let wh = (*outgoing + *incident * eta).normalized();
-//
-//         let cos_i = incident.dot(wh);
-//         let cos_o = outgoing.dot(wh);
-//         if cos_i * cos_o > 0.0 {
-//             return 0.0;
-//         }
-//
-//         let sqrt_denom = cos_o + eta * cos_i;
-//         let dwh_dwi = eta * eta * cos_i.abs() / (sqrt_denom * sqrt_denom);
-//
-//         self.distribution.pdf(outgoing, &wh) * dwh_dwi
-//     }
-// }
+impl<S: Spectrum> BxDF<S> for RoughDielectric<S> {
+    fn get_type(&self) -> BxDFType {
+        BxDFType::REFLECTION | BxDFType::TRANSMISSION | BxDFType::GLOSSY
+    }
+
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        if same_hemisphere(incident, outgoing) {
+            self.evaluate_reflection(incident, outgoing)
+        } else {
+            let distribution = self.distribution.as_ref();
+
+            self.t * evaluate_transmission(distribution, &self.fresnel, incident, outgoing)
+        }
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        if bxdf_is_parallel(outgoing) {
+            return BxDFSample::black_nan_0();
+        }
+
+        // choose the lobe and stretch the used part of the sample back to [0, 1)
+        let p = self.reflection_probability(outgoing);
+        let (incident, typ) = if sample.x < p {
+            let sample = Vec2::new(sample.x / p, sample.y);
+
+            let wh = self.distribution.sample_wh(outgoing, &sample);
+            // Should be rare
+            if outgoing.dot(wh) < 0.0 {
+                return BxDFSample::black_nan_0();
+            }
+
+            let incident = -outgoing.reflected(wh);
+            if !same_hemisphere(&incident, outgoing) {
+                return BxDFSample::black_nan_0();
+            }
+
+            (incident, self.get_type() & !BxDFType::TRANSMISSION)
+        } else {
+            let sample = Vec2::new((sample.x - p) / (1.0 - p), sample.y);
+
+            let distribution = self.distribution.as_ref();
+            match sample_transmission(distribution, &self.fresnel, outgoing, &sample) {
+                Some(incident) => (incident, self.get_type() & !BxDFType::REFLECTION),
+                None => return BxDFSample::black_nan_0(),
+            }
+        };
+
+        let spectrum = self.evaluate(&incident, outgoing);
+        let pdf = self.pdf(&incident, outgoing);
+
+        BxDFSample::new(spectrum, incident, pdf, typ)
+    }
+
+    fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        let p = self.reflection_probability(outgoing);
+
+        if same_hemisphere(incident, outgoing) {
+            p * self.pdf_reflection(incident, outgoing)
+        } else {
+            let distribution = self.distribution.as_ref();
+
+            (1.0 - p) * pdf_transmission(distribution, &self.fresnel, incident, outgoing)
+        }
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        let p = self.reflection_probability(outgoing);
+
+        p * self.r.avg() + (1.0 - p) * self.t.avg()
+    }
+}
@@ -1,13 +1,19 @@
 pub mod bsdf;
+pub mod disney;
+pub mod fluorescence;
 pub mod fresnel;
 pub mod lambertian;
+pub mod layered;
+pub mod metal;
 pub mod microfacet;
 pub mod oren_nayar;
+pub mod refractive_index;
 pub mod specular;
 
 use util::floats;
 
 use crate::mc::cos_sample_hemisphere;
+use crate::polarization::Mueller;
 use crate::Spectrum;
 use bitflags::_core::fmt::Debug;
 use std::f32::consts::{FRAC_1_PI, PI};
@@ -166,15 +172,15 @@ impl BxDFType {
 /// * `spectrum` - An evaluated scaling spectrum
 /// * `incident` - An evaluated incident direction
 /// * `pdf` - An evaluated pdf
-pub struct BxDFSample {
-    pub spectrum: Spectrum,
+pub struct BxDFSample<S> {
+    pub spectrum: S,
     pub incident: Vec3,
     pub pdf: f32,
     pub typ: BxDFType,
 }
 
-impl BxDFSample {
-    pub fn new(spectrum: Spectrum, incident: Vec3, pdf: f32, typ: BxDFType) -> Self {
+impl<S: Spectrum> BxDFSample<S> {
+    pub fn new(spectrum: S, incident: Vec3, pdf: f32, typ: BxDFType) -> Self {
         Self {
             spectrum,
             incident,
@@ -184,7 +190,7 @@ impl BxDFSample {
     }
 
     pub fn black_nan_0() -> Self {
-        Self::new(0.0.into(), Vec3::broadcast(f32::NAN), 0.0, BxDFType::NONE)
+        Self::new(S::black(), Vec3::broadcast(f32::NAN), 0.0, BxDFType::NONE)
     }
 }
 
@@ -192,7 +198,7 @@ impl BxDFSample {
 /// The common base shared between BRDFs and BTDFs.
 /// Provides methods for evaluating and sampling the distribution function for pairs of directions
 /// at an intersection
-pub trait BxDF: Debug + Send + Sync {
+pub trait BxDF<S: Spectrum>: Debug + Send + Sync {
     /// # Summary
     /// Some light transport algorithms need to distinguish different BxDFTypes.
     ///
@@ -220,8 +226,8 @@ pub trait BxDF: Debug + Send + Sync {
     /// * `outgoing` - The outgoing light direction
     ///
     /// # Results
-    /// * `Spectrum` - The scaling spectrum at the intersection
-    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> Spectrum;
+    /// * `S` - The scaling spectrum at the intersection
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S;
 
     /// # Summary
     /// Samples an incident light direction for an outgoing light direction from the given sample
@@ -234,9 +240,13 @@ pub trait BxDF: Debug + Send + Sync {
     ///
     /// # Results
     /// * `BxDFSample` - The spectrum, incident and pdf at the intersection
-    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample {
-        let incident = cos_sample_hemisphere(sample);
-        let incident = flip_if_neg(incident);
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        // the hemisphere is sampled around the z-axis, our normal is the y-axis
+        let hemisphere = cos_sample_hemisphere(sample);
+        let mut incident = Vec3::new(hemisphere.x, hemisphere.z, hemisphere.y);
+        if is_neg(outgoing) {
+            incident.y = -incident.y;
+        }
 
         let spectrum = self.evaluate(&incident, outgoing);
         let pdf = self.pdf(&incident, outgoing);
@@ -244,6 +254,83 @@ pub trait BxDF: Debug + Send + Sync {
         BxDFSample::new(spectrum, incident, pdf, self.get_type())
     }
 
+    /// # Summary
+    /// Samples an incident light direction for light of a single wavelength.
+    /// Only dispersive BxDFs, whose sampled directions depend on the wavelength, need to override
+    /// this.
+    ///
+    /// # Arguments
+    /// * `outgoing` - The outgoing light direction
+    /// * `sample` - The sample space for randomization
+    /// * `lambda` - The wavelength in nm
+    ///
+    /// # Results
+    /// * `BxDFSample` - The spectrum, incident and pdf at the intersection
+    fn sample_wavelength(&self, outgoing: &Vec3, sample: &Vec2, _lambda: f32) -> BxDFSample<S> {
+        self.sample(outgoing, sample)
+    }
+
+    /// Whether the sampled directions depend on the wavelength.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// Whether light is re-radiated at other wavelengths.
+    fn is_fluorescent(&self) -> bool {
+        false
+    }
+
+    /// Whether `evaluate` and `pdf` are stochastic estimates, such that only the ratio of the
+    /// value and the pdf of a sample is meaningful.
+    fn is_stochastic(&self) -> bool {
+        false
+    }
+
+    /// # Summary
+    /// Computes the light re-radiated from the given incident light, relative to the spectrum
+    /// of this BxDF, such that scattering it with `evaluate` yields the re-radiated light.
+    /// Only fluorescent BxDFs need to override this.
+    ///
+    /// # Arguments
+    /// * `radiance` - The incident light
+    ///
+    /// # Results
+    /// * `S` - The light to scatter
+    fn reradiate(&self, radiance: &S) -> S {
+        *radiance
+    }
+
+    /// # Summary
+    /// Samples the wavelength at which light scattered at the given wavelength was absorbed.
+    /// Only fluorescent BxDFs need to override this.
+    ///
+    /// # Arguments
+    /// * `emission` - The index of the scattered wavelength
+    /// * `sample` - A random sample in `[0, 1)`
+    ///
+    /// # Results
+    /// * `Option<(usize, f32)>` - The index of the absorbed wavelength and the weight relative to
+    ///   the spectrum of this BxDF, or `None` if the wavelength does not change
+    fn sample_excitation(&self, _emission: usize, _sample: f32) -> Option<(usize, f32)> {
+        None
+    }
+
+    /// # Summary
+    /// Computes the change of polarization for the pair of directions, as Mueller matrix in the
+    /// s/p reference frame (the s-axis being perpendicular to the plane of incidence), normalized
+    /// to an intensity of `1`.
+    /// By default, BxDFs depolarize, which suits diffuse scattering.
+    ///
+    /// # Arguments
+    /// * `incident` - The incident direction onto the intersection we evaluate
+    /// * `outgoing` - The outgoing light direction
+    ///
+    /// # Results
+    /// * `Mueller<S>` - The normalized Mueller matrix
+    fn polarization(&self, _incident: &Vec3, _outgoing: &Vec3) -> Mueller<S> {
+        Mueller::depolarizer()
+    }
+
     /// # Summary
     /// Computes the probability density function (_pdf_) for the pair of directions.
     ///
@@ -262,38 +349,86 @@ pub trait BxDF: Debug + Send + Sync {
             0.0
         }
     }
+
+    /// # Summary
+    /// Estimates the fraction of light scattered into all directions, which the BSDF uses to
+    /// pick BxDFs proportionally to their contribution.
+    /// The estimate only needs to be roughly proportional, by default all BxDFs are weighted
+    /// equally.
+    ///
+    /// # Arguments
+    /// * `outgoing` - The outgoing light direction
+    ///
+    /// # Results
+    /// * `f32` - The estimated albedo
+    fn albedo(&self, _outgoing: &Vec3) -> f32 {
+        1.0
+    }
 }
 
 #[derive(Debug)]
-pub struct ScaledBxDF {
-    bxdf: Box<dyn BxDF>,
-    scale: Spectrum,
+pub struct ScaledBxDF<S: Spectrum> {
+    bxdf: Box<dyn BxDF<S>>,
+    scale: S,
 }
 
 /// A scaled BxDF
 ///
-impl ScaledBxDF {
-    pub fn new(bxdf: Box<dyn BxDF>, scale: Spectrum) -> Self {
+impl<S: Spectrum> ScaledBxDF<S> {
+    pub fn new(bxdf: Box<dyn BxDF<S>>, scale: S) -> Self {
         Self { bxdf, scale }
     }
 }
 
-impl BxDF for ScaledBxDF {
+impl<S: Spectrum> BxDF<S> for ScaledBxDF<S> {
     fn get_type(&self) -> BxDFType {
         self.bxdf.get_type()
     }
 
-    fn evaluate(&self, view: &Vec3, from: &Vec3) -> Spectrum {
+    fn evaluate(&self, view: &Vec3, from: &Vec3) -> S {
         self.scale * self.bxdf.evaluate(view, from)
     }
 
-    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample {
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
         let mut sample = self.bxdf.sample(outgoing, sample);
         sample.spectrum *= self.scale;
         sample
     }
 
+    fn sample_wavelength(&self, outgoing: &Vec3, sample: &Vec2, lambda: f32) -> BxDFSample<S> {
+        let mut sample = self.bxdf.sample_wavelength(outgoing, sample, lambda);
+        sample.spectrum *= self.scale;
+        sample
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.bxdf.is_dispersive()
+    }
+
+    fn is_fluorescent(&self) -> bool {
+        self.bxdf.is_fluorescent()
+    }
+
+    fn is_stochastic(&self) -> bool {
+        self.bxdf.is_stochastic()
+    }
+
+    fn reradiate(&self, radiance: &S) -> S {
+        self.bxdf.reradiate(radiance)
+    }
+
+    fn sample_excitation(&self, emission: usize, sample: f32) -> Option<(usize, f32)> {
+        self.bxdf.sample_excitation(emission, sample)
+    }
+
+    fn polarization(&self, incident: &Vec3, outgoing: &Vec3) -> Mueller<S> {
+        self.bxdf.polarization(incident, outgoing)
+    }
+
     fn pdf(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
         self.bxdf.pdf(incident, outgoing)
     }
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        self.scale.avg() * self.bxdf.albedo(outgoing)
+    }
 }
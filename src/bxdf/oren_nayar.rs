@@ -6,14 +6,14 @@ use ultraviolet::Vec3;
 use util::floats;
 
 #[derive(Debug)]
-pub struct OrenNayar {
-    r: Spectrum,
+pub struct OrenNayar<S> {
+    r: S,
     a: f32,
     b: f32,
 }
 
-impl OrenNayar {
-    pub fn new(r: Spectrum, sigma: f32) -> Self {
+impl<S> OrenNayar<S> {
+    pub fn new(r: S, sigma: f32) -> Self {
         let sigma = sigma.to_radians();
         let sigma2 = sigma * sigma;
         let a = 1.0 - (sigma2 / (2.0 * (sigma2 + 0.33)));
@@ -23,12 +23,12 @@ impl OrenNayar {
     }
 }
 
-impl BxDF for OrenNayar {
+impl<S: Spectrum> BxDF<S> for OrenNayar<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::DIFFUSE | BxDFType::REFLECTION
     }
 
-    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> Spectrum {
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> S {
         let sin_theta_i = bxdf::sin_theta(incident);
         let sin_theta_o = bxdf::sin_theta(outgoing);
 
@@ -58,4 +58,8 @@ impl BxDF for OrenNayar {
 
         self.r * (FRAC_1_PI * (self.a + self.b * max_cos * sin_alpha * tan_beta))
     }
+
+    fn albedo(&self, _: &Vec3) -> f32 {
+        self.r.avg()
+    }
 }
@@ -0,0 +1,74 @@
+/// The wavelength in nm at which wavelength-dependent refractive indices are evaluated, if no
+/// wavelength is known (the sodium D line).
+pub const REFERENCE_WAVELENGTH: f32 = 589.3;
+
+/// A model of the refractive index of a material as function of the wavelength.
+///
+/// The empirical formulas expect the wavelength in µm, while `at` takes it in nm.
+#[derive(Debug, Copy, Clone)]
+pub enum RefractiveIndex {
+    /// A refractive index independent of the wavelength
+    Constant(f32),
+    /// Cauchy's equation `n(λ) = a + b / λ²`
+    Cauchy { a: f32, b: f32 },
+    /// The Sellmeier equation `n²(λ) = 1 + Σ b_i λ² / (λ² - c_i)`
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl RefractiveIndex {
+    /// The borosilicate crown glass BK7 (Schott).
+    pub fn bk7() -> Self {
+        Self::Sellmeier {
+            b: [1.039_612, 0.231_792_34, 1.010_469_4],
+            c: [0.006_000_699, 0.020_017_914, 103.560_65],
+        }
+    }
+
+    /// Fused silica (Malitson, 1965).
+    pub fn fused_silica() -> Self {
+        Self::Sellmeier {
+            b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+            c: [0.004_679_148, 0.013_512_063, 97.934_003],
+        }
+    }
+
+    /// Diamond (Peter, 1923).
+    pub fn diamond() -> Self {
+        Self::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030_625, 0.011_236, 0.0],
+        }
+    }
+
+    /// # Summary
+    /// Evaluates the refractive index.
+    ///
+    /// # Arguments
+    /// * `lambda` - The wavelength in nm
+    ///
+    /// # Results
+    /// * `f32` - The refractive index
+    pub fn at(&self, lambda: f32) -> f32 {
+        let lambda = lambda / 1000.0;
+        let lambda2 = lambda * lambda;
+
+        match self {
+            RefractiveIndex::Constant(eta) => *eta,
+            RefractiveIndex::Cauchy { a, b } => a + b / lambda2,
+            RefractiveIndex::Sellmeier { b, c } => {
+                let sum: f32 = b
+                    .iter()
+                    .zip(c.iter())
+                    .map(|(b, c)| b * lambda2 / (lambda2 - c))
+                    .sum();
+
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    /// Whether the refractive index depends on the wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, RefractiveIndex::Constant(_))
+    }
+}
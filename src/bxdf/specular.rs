@@ -1,32 +1,33 @@
 use crate::bxdf::fresnel::{Dielectric, Fresnel};
+use crate::bxdf::refractive_index::REFERENCE_WAVELENGTH;
 use crate::bxdf::*;
+use crate::polarization::Mueller;
 use crate::Spectrum;
-use color::Color;
 use std::sync::Arc;
 use ultraviolet::{Vec2, Vec3};
 
 #[derive(Debug)]
-pub struct SpecularReflection {
-    r: Spectrum,
-    fresnel: Arc<dyn Fresnel>,
+pub struct SpecularReflection<S: Spectrum> {
+    r: S,
+    fresnel: Arc<dyn Fresnel<S>>,
 }
 
-impl SpecularReflection {
-    pub fn new(r: Spectrum, fresnel: Arc<dyn Fresnel>) -> Self {
+impl<S: Spectrum> SpecularReflection<S> {
+    pub fn new(r: S, fresnel: Arc<dyn Fresnel<S>>) -> Self {
         Self { r, fresnel }
     }
 }
 
-impl BxDF for SpecularReflection {
+impl<S: Spectrum> BxDF<S> for SpecularReflection<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::REFLECTION | BxDFType::SPECULAR
     }
 
-    fn evaluate(&self, _: &Vec3, _: &Vec3) -> Spectrum {
-        Spectrum::black()
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
+        S::black()
     }
 
-    fn sample(&self, outgoing: &Vec3, _: &Vec2) -> BxDFSample {
+    fn sample(&self, outgoing: &Vec3, _: &Vec2) -> BxDFSample<S> {
         let incident = bxdf_incident_to(outgoing);
 
         let cos_i = cos_theta(&incident);
@@ -36,44 +37,79 @@ impl BxDF for SpecularReflection {
 
         BxDFSample::new(spectrum, incident, pdf, self.get_type())
     }
+
+    fn polarization(&self, incident: &Vec3, _: &Vec3) -> Mueller<S> {
+        self.fresnel.polarization(cos_theta(incident))
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        (self.fresnel.evaluate(cos_theta(outgoing)) * self.r).avg()
+    }
 }
 
 #[derive(Debug)]
-pub struct SpecularTransmission {
-    t: Spectrum,
+pub struct SpecularTransmission<S> {
+    t: S,
     fresnel: Arc<Dielectric>,
 }
 
-impl SpecularTransmission {
-    pub fn new(t: Spectrum, fresnel: Arc<Dielectric>) -> Self {
+impl<S> SpecularTransmission<S> {
+    pub fn new(t: S, fresnel: Arc<Dielectric>) -> Self {
         Self { t, fresnel }
     }
 }
 
-impl BxDF for SpecularTransmission {
+impl<S: Spectrum> BxDF<S> for SpecularTransmission<S> {
     fn get_type(&self) -> BxDFType {
         BxDFType::SPECULAR | BxDFType::TRANSMISSION
     }
 
-    fn evaluate(&self, _: &Vec3, _: &Vec3) -> Spectrum {
-        Spectrum::black()
+    fn evaluate(&self, _: &Vec3, _: &Vec3) -> S {
+        S::black()
     }
 
-    fn sample(&self, outgoing: &Vec3, _: &Vec2) -> BxDFSample {
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> BxDFSample<S> {
+        self.sample_wavelength(outgoing, sample, REFERENCE_WAVELENGTH)
+    }
+
+    fn sample_wavelength(&self, outgoing: &Vec3, _: &Vec2, lambda: f32) -> BxDFSample<S> {
         let entering = cos_theta(outgoing) > 0.0;
 
+        let (eta_i, eta_t) = self.fresnel.etas(lambda);
         let (eta_i, eta_t, n) = if entering {
-            (self.fresnel.eta_i, self.fresnel.eta_t, bxdf_normal())
+            (eta_i, eta_t, bxdf_normal())
         } else {
-            (self.fresnel.eta_t, self.fresnel.eta_i, -bxdf_normal())
+            (eta_t, eta_i, -bxdf_normal())
         };
 
-        let incident = outgoing.refracted(n, eta_i / eta_t);
-        let cos_i = cos_theta(&incident).abs();
+        // total internal reflection
+        let incident = (-*outgoing).refracted(n, eta_i / eta_t);
+        if incident == Vec3::zero() {
+            return BxDFSample::black_nan_0();
+        }
 
-        let f = Spectrum::new_const(1.0) - self.fresnel.evaluate(cos_i);
-        let spectrum = f * self.t;
+        let f = 1.0
+            - self
+                .fresnel
+                .evaluate_wavelength(cos_theta(&incident), lambda);
+        let spectrum = self.t * f;
 
         BxDFSample::new(spectrum, incident, 1.0, self.get_type())
     }
+
+    fn is_dispersive(&self) -> bool {
+        self.fresnel.is_dispersive()
+    }
+
+    fn polarization(&self, incident: &Vec3, _: &Vec3) -> Mueller<S> {
+        self.fresnel.transmission_polarization(cos_theta(incident))
+    }
+
+    fn albedo(&self, outgoing: &Vec3) -> f32 {
+        let f = self
+            .fresnel
+            .evaluate_wavelength(cos_theta(outgoing), REFERENCE_WAVELENGTH);
+
+        (1.0 - f) * self.t.avg()
+    }
 }
@@ -1,5 +1,9 @@
 #[allow(dead_code)]
 pub const CIE_SAMPLES: usize = 471;
+/// The wavelength in nm of the first sample, with the samples being spaced `1` nm apart.
+pub const CIE_LAMBDA_START: f32 = 360.0;
+/// The integral of `CIE_Y_2`, normalizing the luminance of spectra to `Y = 1`.
+pub const CIE_Y_INTEGRAL: f32 = 106.856895;
 
 //noinspection RsApproxConstant
 #[allow(dead_code)]
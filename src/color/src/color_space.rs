@@ -0,0 +1,286 @@
+//! RGB color spaces, defined by their primaries, white point and transfer function, and the
+//! chromatic adaptation between different white points.
+
+use crate::*;
+use std::convert::TryInto;
+
+/// The chromaticity of the CIE standard illuminant D65.
+const D65: (f32, f32) = (0.3127, 0.3290);
+/// The chromaticity of the ACES white point (about D60).
+const ACES: (f32, f32) = (0.32168, 0.33767);
+
+/// # Summary
+/// Converts chromaticity coordinates to XYZ of luminance `1`.
+///
+/// # Arguments
+/// * `xy` - The chromaticity coordinates
+///
+/// # Results
+/// * `Vec3` - The XYZ values
+fn xy_to_xyz(xy: (f32, f32)) -> Vec3 {
+    let (x, y) = xy;
+    Vec3::new(x / y, 1.0, (1.0 - x - y) / y)
+}
+
+/// Returns the diagonal matrix of the given vector.
+fn diagonal(v: Vec3) -> Mat3 {
+    Mat3::new(
+        Vec3::new(v.x, 0.0, 0.0),
+        Vec3::new(0.0, v.y, 0.0),
+        Vec3::new(0.0, 0.0, v.z),
+    )
+}
+
+/// A von Kries-like transform adapting colors between white points.
+#[derive(Debug, Copy, Clone)]
+pub enum ChromaticAdaptation {
+    /// The Bradford transform (Lam, 1985), as used by ICC profiles
+    Bradford,
+    /// The transform of the CIECAM02 color appearance model
+    Cat02,
+}
+
+impl ChromaticAdaptation {
+    /// Returns the matrix transforming XYZ into the cone response domain.
+    fn cone_mat(&self) -> Mat3 {
+        match self {
+            ChromaticAdaptation::Bradford => Mat3::new(
+                Vec3::new(0.8951, -0.7502, 0.0389),
+                Vec3::new(0.2664, 1.7135, -0.0685),
+                Vec3::new(-0.1614, 0.0367, 1.0296),
+            ),
+            ChromaticAdaptation::Cat02 => Mat3::new(
+                Vec3::new(0.7328, -0.7036, 0.0030),
+                Vec3::new(0.4296, 1.6975, 0.0136),
+                Vec3::new(-0.1624, 0.0061, 0.9834),
+            ),
+        }
+    }
+
+    /// # Summary
+    /// Computes the matrix adapting XYZ colors seen under one white point to another.
+    ///
+    /// # Arguments
+    /// * `from` - The XYZ values of the source white point
+    /// * `to` - The XYZ values of the destination white point
+    ///
+    /// # Results
+    /// * `Mat3` - The adaptation matrix
+    pub fn matrix(&self, from: Vec3, to: Vec3) -> Mat3 {
+        let cone = self.cone_mat();
+        let scale = (cone * to) / (cone * from);
+
+        cone.inversed() * diagonal(scale) * cone
+    }
+}
+
+impl TryInto<ChromaticAdaptation> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<ChromaticAdaptation, Self::Error> {
+        match self {
+            "bradford" | "Bradford" | "BRADFORD" => Ok(ChromaticAdaptation::Bradford),
+            "cat02" | "Cat02" | "CAT02" => Ok(ChromaticAdaptation::Cat02),
+            _ => Err(self.to_string()),
+        }
+    }
+}
+
+/// An RGB color space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorSpace {
+    /// sRGB (IEC 61966-2-1)
+    Srgb,
+    /// ITU-R BT.709 (HDTV), the primaries of sRGB with a different transfer function
+    Rec709,
+    /// ITU-R BT.2020 (UHDTV)
+    Rec2020,
+    /// Display P3, the DCI-P3 primaries with the D65 white point and the sRGB transfer function
+    DisplayP3,
+    /// Adobe RGB (1998)
+    AdobeRgb,
+    /// ACEScg, the linear working space of the Academy Color Encoding System (AP1 primaries)
+    AcesCg,
+}
+
+impl ColorSpace {
+    /// # Summary
+    /// Returns the chromaticity coordinates of the red, green and blue primaries.
+    ///
+    /// # Results
+    /// * `[(f32, f32); 3]` - The primaries
+    pub fn primaries(&self) -> [(f32, f32); 3] {
+        match self {
+            ColorSpace::Srgb | ColorSpace::Rec709 => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)],
+            ColorSpace::Rec2020 => [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)],
+            ColorSpace::DisplayP3 => [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            ColorSpace::AdobeRgb => [(0.64, 0.33), (0.21, 0.71), (0.15, 0.06)],
+            ColorSpace::AcesCg => [(0.713, 0.293), (0.165, 0.830), (0.128, 0.044)],
+        }
+    }
+
+    /// # Summary
+    /// Returns the chromaticity coordinates of the white point.
+    ///
+    /// # Results
+    /// * `(f32, f32)` - The white point
+    pub fn white_point(&self) -> (f32, f32) {
+        match self {
+            ColorSpace::AcesCg => ACES,
+            _ => D65,
+        }
+    }
+
+    /// # Summary
+    /// Returns the XYZ values of the white point, of luminance `1`.
+    ///
+    /// # Results
+    /// * `Vec3` - The white point
+    pub fn white(&self) -> Vec3 {
+        xy_to_xyz(self.white_point())
+    }
+
+    /// # Summary
+    /// Computes the matrix converting linear RGB of this color space to XYZ.
+    ///
+    /// # Results
+    /// * `Mat3` - The conversion matrix
+    pub fn to_xyz_mat(&self) -> Mat3 {
+        let [r, g, b] = self.primaries();
+        let primaries = Mat3::new(xy_to_xyz(r), xy_to_xyz(g), xy_to_xyz(b));
+
+        // scale the primaries such that RGB white maps to the white point
+        let scale = primaries.inversed() * self.white();
+
+        primaries * diagonal(scale)
+    }
+
+    /// # Summary
+    /// Computes the matrix converting XYZ to linear RGB of this color space.
+    ///
+    /// # Results
+    /// * `Mat3` - The conversion matrix
+    pub fn from_xyz_mat(&self) -> Mat3 {
+        self.to_xyz_mat().inversed()
+    }
+
+    /// # Summary
+    /// Computes the matrix converting linear RGB of this color space to another one, adapting
+    /// the white point if they differ.
+    ///
+    /// # Arguments
+    /// * `target` - The color space to convert to
+    /// * `adaptation` - The chromatic adaptation to use
+    ///
+    /// # Results
+    /// * `Mat3` - The conversion matrix
+    pub fn conversion_mat(&self, target: ColorSpace, adaptation: ChromaticAdaptation) -> Mat3 {
+        let adapt = if self.white_point() == target.white_point() {
+            Mat3::identity()
+        } else {
+            adaptation.matrix(self.white(), target.white())
+        };
+
+        target.from_xyz_mat() * adapt * self.to_xyz_mat()
+    }
+
+    /// # Summary
+    /// Computes the matrix white balancing linear RGB of this color space, such that colors of
+    /// the given white point appear neutral.
+    ///
+    /// # Arguments
+    /// * `white` - The XYZ values of the white point to balance
+    /// * `adaptation` - The chromatic adaptation to use
+    ///
+    /// # Results
+    /// * `Mat3` - The white balance matrix
+    pub fn white_balance_mat(&self, white: Vec3, adaptation: ChromaticAdaptation) -> Mat3 {
+        let adapt = adaptation.matrix(white / white.y, self.white());
+
+        self.from_xyz_mat() * adapt * self.to_xyz_mat()
+    }
+
+    /// # Summary
+    /// Applies the transfer function of this color space (the opto-electronic transfer
+    /// function), clamping the value to `[0, 1]`.
+    ///
+    /// # Arguments
+    /// * `linear` - The linear value
+    ///
+    /// # Results
+    /// * `f32` - The encoded value
+    pub fn encode(&self, linear: f32) -> f32 {
+        let v = floats::fast_clamp(linear, 0.0, 1.0);
+
+        match self {
+            ColorSpace::Srgb | ColorSpace::DisplayP3 => linear_to_srgb(v),
+            ColorSpace::Rec709 | ColorSpace::Rec2020 => {
+                if v < 0.018 {
+                    4.5 * v
+                } else {
+                    1.099 * v.powf(0.45) - 0.099
+                }
+            }
+            ColorSpace::AdobeRgb => v.powf(256.0 / 563.0),
+            ColorSpace::AcesCg => v,
+        }
+    }
+
+    /// # Summary
+    /// Inverts the transfer function of this color space, clamping the value to `[0, 1]`.
+    ///
+    /// # Arguments
+    /// * `encoded` - The encoded value
+    ///
+    /// # Results
+    /// * `f32` - The linear value
+    pub fn decode(&self, encoded: f32) -> f32 {
+        let v = floats::fast_clamp(encoded, 0.0, 1.0);
+
+        match self {
+            ColorSpace::Srgb | ColorSpace::DisplayP3 => srgb_to_linear(v),
+            ColorSpace::Rec709 | ColorSpace::Rec2020 => {
+                if v < 0.081 {
+                    v / 4.5
+                } else {
+                    ((v + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            }
+            ColorSpace::AdobeRgb => v.powf(563.0 / 256.0),
+            ColorSpace::AcesCg => v,
+        }
+    }
+
+    /// # Summary
+    /// Returns the exponent of a pure power law approximating the transfer function, as stored
+    /// in image metadata.
+    ///
+    /// # Results
+    /// * `f32` - The encoding exponent
+    pub fn gamma(&self) -> f32 {
+        match self {
+            ColorSpace::Srgb | ColorSpace::DisplayP3 => 1.0 / 2.2,
+            ColorSpace::Rec709 | ColorSpace::Rec2020 => 0.45,
+            ColorSpace::AdobeRgb => 256.0 / 563.0,
+            ColorSpace::AcesCg => 1.0,
+        }
+    }
+}
+
+impl TryInto<ColorSpace> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<ColorSpace, Self::Error> {
+        match self {
+            "srgb" | "sRGB" | "SRGB" => Ok(ColorSpace::Srgb),
+            "rec709" | "Rec709" | "REC709" => Ok(ColorSpace::Rec709),
+            "rec2020" | "Rec2020" | "REC2020" => Ok(ColorSpace::Rec2020),
+            "p3" | "P3" | "display-p3" | "Display-P3" | "DISPLAY-P3" => Ok(ColorSpace::DisplayP3),
+            "adobe" | "Adobe" | "ADOBE" | "adobe-rgb" | "Adobe-RGB" | "ADOBE-RGB" => {
+                Ok(ColorSpace::AdobeRgb)
+            }
+            "acescg" | "ACEScg" | "ACESCG" => Ok(ColorSpace::AcesCg),
+            _ => Err(self.to_string()),
+        }
+    }
+}
@@ -7,8 +7,12 @@ use std::fmt::Debug;
 use ultraviolet::{Mat3, Vec3};
 
 pub mod cie;
+pub mod color_space;
+pub mod illuminant;
+pub mod response;
 pub mod spectral;
 pub mod srgb;
+pub mod upsampling;
 pub mod xyz;
 
 pub use spectral::*;
@@ -45,6 +49,18 @@ macro_rules! colors {
                     Self::new(data)
                 }
 
+                pub fn exp(&self) -> Self {
+                    let mut data = self.data;
+                    data.iter_mut().for_each(|f| *f = f.exp());
+
+                    Self::new(data)
+                }
+
+                /// Returns the average of the color values.
+                pub fn avg(&self) -> f32 {
+                    self.data.iter().sum::<f32>() / self.data.len() as f32
+                }
+
                 pub fn lerp(&self, other: &Self, t: f32) -> Self {
                     *self * (1.0 - t) + *other * t
                 }
@@ -229,26 +245,33 @@ macro_rules! colors {
 }
 
 pub trait Color:
-Add
+Add<Output = Self>
 + AddAssign
-+ Sub
++ Sub<Output = Self>
 + SubAssign
-+ Mul
++ Mul<Output = Self>
 + MulAssign
-+ Mul<f32>
++ Mul<f32, Output = Self>
 + MulAssign<f32>
-+ Div
++ Div<Output = Self>
 + DivAssign
-+ Div<f32>
++ Div<f32, Output = Self>
 + DivAssign<f32>
 + PartialEq
-+ Index<usize>
++ Index<usize, Output = f32>
 + IndexMut<usize>
++ Copy
 + Debug
 + Into<Rgb<u8>>
 + Into<Rgb<u16>>
 + Sum
 {
+    /// The number of values of this color.
+    const SIZE: usize;
+
+    /// Creates a color of the given value in all components.
+    fn new_const(value: f32) -> Self;
+
     /// Whether this color is black. Some computations can be omitted, if the color is black.
     fn is_black(&self) -> bool;
 
@@ -260,12 +283,43 @@ Add
 
     fn sqrt(&self) -> Self;
 
-    /// Converts this color to sRGB.
+    /// Computes the exponential of the color values.
+    fn exp(&self) -> Self;
+
+    /// Computes the average of the color values.
+    fn avg(&self) -> f32;
+
+    /// Converts sRGB to this color.
+    fn from_rgb(rgb: Srgb) -> Self;
+
+    /// Converts a spectrum to this color.
+    fn from_spectral(spectral: &Spectral) -> Self;
+
+    /// Converts sRGB to this color, for colors of light sources.
+    fn from_rgb_illuminant(rgb: Srgb) -> Self {
+        Self::from_rgb(rgb)
+    }
+
+    /// Converts a spectrum of a unitless quantity (like reflectances or refractive indices) to
+    /// this color. Contrary to `from_spectral`, the spectrum is weighted by the sRGB white point
+    /// (D65), such that constant spectra convert to gray.
+    fn from_spectral_reflectance(spectral: &Spectral) -> Self {
+        Self::from_spectral(&(*spectral * illuminant::Illuminant::D65.spectrum()))
+    }
+
+    /// Converts this color to linear sRGB.
     fn to_rgb(&self) -> Srgb;
 
     /// Converts this color to XYZ.
     fn to_xyz(&self) -> Xyz;
 
+    /// Computes the relative luminance of this color from its (linear) sRGB values.
+    fn luminance(&self) -> f32 {
+        let rgb = self.to_rgb();
+
+        0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+    }
+
     fn black() -> Self;
 
     fn white() -> Self;
@@ -294,7 +348,7 @@ pub fn srgb_to_xyz_mat() -> Mat3 {
     // https://en.wikipedia.org/wiki/SRGB#The_reverse_transformation
     Mat3::new(
         Vec3::new(0.41239080, 0.21263901, 0.01933082),
-        Vec3::new(0.35758434, 0.71516868, 0.07219232),
+        Vec3::new(0.35758434, 0.71516868, 0.11919478),
         Vec3::new(0.18048079, 0.07219232, 0.95053215),
     )
 }
@@ -0,0 +1,71 @@
+//! Response curves of films and sensors, compressing the linear scene radiance into the displayable
+//! range.
+
+use std::convert::TryInto;
+
+/// A response curve applied per channel to linear values.
+#[derive(Debug, Copy, Clone)]
+pub enum ResponseCurve {
+    /// The global operator of Reinhard et al. (2002), `x / (1 + x)`
+    Reinhard,
+    /// The filmic curve of Hable (2010), with a linear white point of `11.2`
+    Filmic,
+    /// An approximation of the ACES reference rendering transform by Narkowicz (2015)
+    Aces,
+}
+
+impl ResponseCurve {
+    /// The filmic curve without normalization.
+    fn hable(x: f32) -> f32 {
+        const A: f32 = 0.15; // shoulder strength
+        const B: f32 = 0.50; // linear strength
+        const C: f32 = 0.10; // linear angle
+        const D: f32 = 0.20; // toe strength
+        const E: f32 = 0.02; // toe numerator
+        const F: f32 = 0.30; // toe denominator
+
+        (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+    }
+
+    /// # Summary
+    /// Applies the response curve.
+    ///
+    /// # Arguments
+    /// * `value` - The linear value
+    ///
+    /// # Results
+    /// * `f32` - The response in `[0, 1]`
+    pub fn apply(&self, value: f32) -> f32 {
+        let x = value.max(0.0);
+
+        let response = match self {
+            ResponseCurve::Reinhard => x / (1.0 + x),
+            ResponseCurve::Filmic => {
+                const EXPOSURE_BIAS: f32 = 2.0;
+                const WHITE: f32 = 11.2;
+
+                Self::hable(EXPOSURE_BIAS * x) / Self::hable(WHITE)
+            }
+            ResponseCurve::Aces => {
+                // the fit expects the exposure of the reference transform
+                let x = x * 0.6;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }
+        };
+
+        response.min(1.0)
+    }
+}
+
+impl TryInto<ResponseCurve> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<ResponseCurve, Self::Error> {
+        match self {
+            "reinhard" | "Reinhard" | "REINHARD" => Ok(ResponseCurve::Reinhard),
+            "filmic" | "Filmic" | "FILMIC" => Ok(ResponseCurve::Filmic),
+            "aces" | "Aces" | "ACES" => Ok(ResponseCurve::Aces),
+            _ => Err(self.to_string()),
+        }
+    }
+}
@@ -1,13 +1,28 @@
+use crate::cie::{CIE_LAMBDA_START, CIE_SAMPLES, CIE_X_2, CIE_Y_2, CIE_Y_INTEGRAL, CIE_Z_2};
 use crate::*;
+use image::Rgb;
+use lazy_static::lazy_static;
+use util::floats;
 
 pub const LAMBDA_START: f32 = 400.0;
 pub const LAMBDA_END: f32 = 700.0;
 pub const SPECTRAL_SAMPLES: usize = 60;
+/// The number of wavelengths sampled per path in hero wavelength sampling.
+pub const HERO_SAMPLES: usize = 4;
 
 colors!(
     Spectral => f32, f32, SPECTRAL_SAMPLES
 );
 
+lazy_static! {
+    static ref CIE_LAMBDA: Vec<f32> = (0..CIE_SAMPLES)
+        .map(|i| CIE_LAMBDA_START + i as f32)
+        .collect();
+    static ref X: Spectral = Spectral::from_sampled(&CIE_LAMBDA, &CIE_X_2, CIE_SAMPLES);
+    static ref Y: Spectral = Spectral::from_sampled(&CIE_LAMBDA, &CIE_Y_2, CIE_SAMPLES);
+    static ref Z: Spectral = Spectral::from_sampled(&CIE_LAMBDA, &CIE_Z_2, CIE_SAMPLES);
+}
+
 fn is_sorted(lambda: &[f32]) -> bool {
     let mut iter = lambda.iter();
     let mut prev = iter.next().unwrap();
@@ -22,17 +37,415 @@ fn is_sorted(lambda: &[f32]) -> bool {
     true
 }
 
+/// # Summary
+/// Averages the piecewise linear function given by the sorted samples over a wavelength range.
+///
+/// # Arguments
+/// * `lambda` - The sorted wavelengths of the samples
+/// * `v` - The values of the samples
+/// * `lambda_start` - The start of the range
+/// * `lambda_end` - The end of the range
+///
+/// # Results
+/// * `f32` - The average value
+fn average_samples(lambda: &[f32], v: &[f32], lambda_start: f32, lambda_end: f32) -> f32 {
+    let n = lambda.len();
+
+    // constant extrapolation outside the samples
+    if lambda_end <= lambda[0] {
+        return v[0];
+    }
+    if lambda_start >= lambda[n - 1] {
+        return v[n - 1];
+    }
+    if n == 1 {
+        return v[0];
+    }
+
+    let mut sum = 0.0;
+    if lambda_start < lambda[0] {
+        sum += v[0] * (lambda[0] - lambda_start);
+    }
+    if lambda_end > lambda[n - 1] {
+        sum += v[n - 1] * (lambda_end - lambda[n - 1]);
+    }
+
+    let mut i = 0;
+    while lambda_start > lambda[i + 1] {
+        i += 1;
+    }
+
+    let interpolate = |w: f32, i: usize| {
+        let t = (w - lambda[i]) / (lambda[i + 1] - lambda[i]);
+        v[i] + t * (v[i + 1] - v[i])
+    };
+
+    while i + 1 < n && lambda_end >= lambda[i] {
+        let segment_start = f32::max(lambda_start, lambda[i]);
+        let segment_end = f32::min(lambda_end, lambda[i + 1]);
+
+        if segment_end > segment_start {
+            sum += 0.5
+                * (interpolate(segment_start, i) + interpolate(segment_end, i))
+                * (segment_end - segment_start);
+        }
+
+        i += 1;
+    }
+
+    sum / (lambda_end - lambda_start)
+}
+
 impl Spectral {
+    /// # Summary
+    /// Creates a spectrum from (possibly unsorted) samples of a piecewise linear function, by
+    /// averaging it over the wavelength range of every spectral sample.
+    ///
+    /// # Arguments
+    /// * `lambda` - The wavelengths of the samples in nm
+    /// * `v` - The values of the samples
+    /// * `n` - The number of samples
+    ///
+    /// # Results
+    /// * `Self` - The spectrum
     pub fn from_sampled(lambda: &[f32], v: &[f32], n: usize) -> Self {
         assert_eq!(lambda.len(), n);
         assert_eq!(v.len(), n);
+        assert!(n > 0);
 
         if !is_sorted(lambda) {
-            //     let permutation = permutation::sort_by(lambda, |a, b| floats::fast_cmp(*a, *b));
-            //     let lambda_sorted = permutation.apply_slice(lambda).as_slice();
-            //     let v_sorted = permutation.apply_slice(v).as_slice();
+            let permutation = permutation::sort_by(lambda, |a, b| floats::fast_cmp(*a, *b));
+            let lambda_sorted = permutation.apply_slice(lambda);
+            let v_sorted = permutation.apply_slice(v);
+
+            return Self::from_sampled(&lambda_sorted, &v_sorted, n);
+        }
+
+        let mut data = [0.0; SPECTRAL_SAMPLES];
+        data.iter_mut().enumerate().for_each(|(i, d)| {
+            let (lambda_start, lambda_end) = Self::range(i);
+            *d = average_samples(lambda, v, lambda_start, lambda_end);
+        });
+
+        Self::new(data)
+    }
+
+    /// # Summary
+    /// Loads a spectrum from a text file of samples, e.g. measured illuminants like the CIE
+    /// fluorescent series.
+    /// Every line contains a wavelength in nm and its value, separated by a comma or whitespace.
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `file_name` - The path of the file
+    ///
+    /// # Results
+    /// * `Result<Self, String>` - The spectrum or the reason it could not be loaded
+    pub fn load(file_name: &str) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+
+        let mut lambda = Vec::new();
+        let mut v = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values: Vec<f32> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("{}:{}: {}", file_name, i + 1, e))?;
+
+            if values.len() != 2 {
+                return Err(format!("{}:{}: Expected 2 values", file_name, i + 1));
+            }
+
+            lambda.push(values[0]);
+            v.push(values[1]);
+        }
+
+        if lambda.is_empty() {
+            return Err(format!("{}: No samples", file_name));
+        }
+
+        Ok(Self::from_sampled(&lambda, &v, lambda.len()))
+    }
+
+    /// # Summary
+    /// Returns the wavelength range covered by the spectral sample at the given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the spectral sample
+    ///
+    /// # Results
+    /// * `(f32, f32)` - The start and end of the range in nm
+    pub fn range(index: usize) -> (f32, f32) {
+        debug_assert!(index < SPECTRAL_SAMPLES);
+        let width = (LAMBDA_END - LAMBDA_START) / SPECTRAL_SAMPLES as f32;
+        let start = LAMBDA_START + index as f32 * width;
+
+        (start, start + width)
+    }
+
+    /// Returns the (center) wavelength in nm of the spectral sample at the given index.
+    pub fn lambda(index: usize) -> f32 {
+        let (start, end) = Self::range(index);
+
+        (start + end) / 2.0
+    }
+}
+
+/// The wavelengths of a path sampled by hero wavelength sampling (Wilkie et al. 2014).
+///
+/// A hero wavelength is sampled uniformly, the remaining `HERO_SAMPLES - 1` wavelengths are
+/// rotated equidistantly through the spectral range, such that the spectrum is sampled stratified.
+/// Paths only carry the sampled wavelengths, i.e. their spectra are `0` everywhere else.
+/// Fluorescence may switch the hero wavelength along the path, while its light is still measured
+/// at the wavelength sampled by the sensor.
+#[derive(Debug, Copy, Clone)]
+pub struct WavelengthSample {
+    pub indices: [usize; HERO_SAMPLES],
+    /// The index of the hero wavelength measured by the sensor
+    sensor: usize,
+    secondary_terminated: bool,
+}
+
+impl WavelengthSample {
+    /// # Summary
+    /// Samples the wavelengths of a path.
+    ///
+    /// # Arguments
+    /// * `sample` - A random sample in `[0, 1)`
+    ///
+    /// # Results
+    /// * `Self` - The sampled wavelengths, the hero wavelength first
+    pub fn new(sample: f32) -> Self {
+        let hero = usize::min(
+            (sample * SPECTRAL_SAMPLES as f32) as usize,
+            SPECTRAL_SAMPLES - 1,
+        );
+
+        let mut indices = [0; HERO_SAMPLES];
+        indices.iter_mut().enumerate().for_each(|(i, index)| {
+            *index = (hero + i * SPECTRAL_SAMPLES / HERO_SAMPLES) % SPECTRAL_SAMPLES;
+        });
+
+        Self {
+            indices,
+            sensor: hero,
+            secondary_terminated: false,
+        }
+    }
+
+    /// Returns the index of the hero wavelength.
+    pub fn hero(&self) -> usize {
+        self.indices[0]
+    }
+
+    /// Returns the hero wavelength in nm.
+    pub fn hero_lambda(&self) -> f32 {
+        Spectral::lambda(self.hero())
+    }
+
+    /// # Summary
+    /// Terminates all but the hero wavelength, e.g. if the path is continued in a direction only
+    /// the hero wavelength can travel (dispersion).
+    ///
+    /// With the balance heuristic over the sampled wavelengths, the secondary wavelengths have a
+    /// pdf of `0` for such directions, giving the hero wavelength the full weight.
+    pub fn terminate_secondary(&mut self) {
+        self.secondary_terminated = true;
+    }
+
+    /// # Summary
+    /// Switches the hero wavelength, e.g. if the light of the path was absorbed at a different
+    /// wavelength than it was re-radiated at (fluorescence).
+    /// The secondary wavelengths must be terminated before.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the new hero wavelength
+    pub fn switch_hero(&mut self, index: usize) {
+        debug_assert!(self.secondary_terminated);
+        self.indices[0] = index;
+    }
+
+    /// # Summary
+    /// Moves a spectrum carried at the (switched) hero wavelength to the wavelength measured by
+    /// the sensor.
+    ///
+    /// # Arguments
+    /// * `spectrum` - The spectrum at the sampled wavelengths
+    ///
+    /// # Results
+    /// * `Spectral` - The spectrum as measured by the sensor
+    pub fn to_sensor(&self, spectrum: &Spectral) -> Spectral {
+        if self.indices[0] == self.sensor {
+            return *spectrum;
+        }
+
+        let mut measured = Spectral::new_const(0.0);
+        measured[self.sensor] = spectrum[self.indices[0]];
+
+        measured
+    }
+
+    /// Whether only the hero wavelength is left.
+    pub fn is_secondary_terminated(&self) -> bool {
+        self.secondary_terminated
+    }
+
+    /// Returns the number of wavelengths still carried by the path.
+    pub fn active(&self) -> usize {
+        if self.secondary_terminated {
+            1
+        } else {
+            HERO_SAMPLES
+        }
+    }
+
+    /// Returns a spectrum of `1` at the (active) sampled wavelengths and `0` elsewhere.
+    pub fn mask(&self) -> Spectral {
+        let mut mask = Spectral::new_const(0.0);
+        self.indices
+            .iter()
+            .take(self.active())
+            .for_each(|index| mask[*index] = 1.0);
+
+        mask
+    }
+
+    /// # Summary
+    /// Returns the maximum value of a spectrum at the (active) sampled wavelengths.
+    ///
+    /// # Arguments
+    /// * `spectrum` - The spectrum
+    ///
+    /// # Results
+    /// * `f32` - The maximum value
+    pub fn max_value(&self, spectrum: &Spectral) -> f32 {
+        self.indices
+            .iter()
+            .take(self.active())
+            .map(|index| spectrum[*index])
+            .fold(0.0, f32::max)
+    }
+
+    /// # Summary
+    /// Returns the weight turning a path estimate at the sampled wavelengths into an estimate of
+    /// the full spectrum, i.e. the inverse probability of a wavelength being sampled.
+    ///
+    /// # Results
+    /// * `f32` - The weight
+    pub fn weight(&self) -> f32 {
+        SPECTRAL_SAMPLES as f32 / self.active() as f32
+    }
+}
+
+impl Color for Spectral {
+    const SIZE: usize = SPECTRAL_SAMPLES;
+
+    fn new_const(value: f32) -> Self {
+        Self::new_const(value)
+    }
+
+    fn is_black(&self) -> bool {
+        floats::approx_zero_ar(&self.data)
+    }
+
+    fn clamp(&self, min: f32, max: f32) -> Self {
+        self.clamp(min, max)
+    }
+
+    fn has_nans(&self) -> bool {
+        self.data.iter().all(|value| !value.is_nan())
+    }
+
+    fn sqrt(&self) -> Self {
+        Self::sqrt(self)
+    }
+
+    fn exp(&self) -> Self {
+        Self::exp(self)
+    }
+
+    fn avg(&self) -> f32 {
+        Self::avg(self)
+    }
+
+    /// Converts a (linear) sRGB reflectance to a smooth spectrum.
+    fn from_rgb(rgb: Srgb) -> Self {
+        upsampling::reflectance(&rgb)
+    }
+
+    fn from_spectral(spectral: &Spectral) -> Self {
+        *spectral
+    }
+
+    fn from_spectral_reflectance(spectral: &Spectral) -> Self {
+        *spectral
+    }
+
+    /// Converts a (linear) sRGB emission to a smooth spectrum.
+    fn from_rgb_illuminant(rgb: Srgb) -> Self {
+        upsampling::illuminant(&rgb)
+    }
+
+    /// Converts this spectrum to linear sRGB.
+    fn to_rgb(&self) -> Srgb {
+        Srgb::from(xyz_to_srgb_mat() * self.to_xyz().to_vec3())
+    }
+
+    /// Integrates this spectrum against the CIE 1931 2° color matching functions.
+    fn to_xyz(&self) -> Xyz {
+        let mut xyz = [0.0; 3];
+        for i in 0..SPECTRAL_SAMPLES {
+            xyz[0] += X[i] * self[i];
+            xyz[1] += Y[i] * self[i];
+            xyz[2] += Z[i] * self[i];
         }
 
-        unimplemented!()
+        let scale = (LAMBDA_END - LAMBDA_START) / (CIE_Y_INTEGRAL * SPECTRAL_SAMPLES as f32);
+
+        Xyz::new(xyz) * scale
+    }
+
+    fn luminance(&self) -> f32 {
+        self.to_xyz()[1]
+    }
+
+    fn black() -> Self {
+        Self::new_const(0.0)
+    }
+
+    fn white() -> Self {
+        Self::new_const(1.0)
+    }
+
+    fn red() -> Self {
+        Self::from_rgb(Srgb::red())
+    }
+
+    fn green() -> Self {
+        Self::from_rgb(Srgb::green())
+    }
+
+    fn blue() -> Self {
+        Self::from_rgb(Srgb::blue())
+    }
+}
+
+impl Into<Rgb<u8>> for Spectral {
+    fn into(self) -> Rgb<u8> {
+        self.to_rgb().into()
+    }
+}
+
+impl Into<Rgb<u16>> for Spectral {
+    fn into(self) -> Rgb<u16> {
+        self.to_rgb().into()
     }
 }
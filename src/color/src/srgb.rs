@@ -14,6 +14,12 @@ impl Srgb {
 }
 
 impl Color for Srgb {
+    const SIZE: usize = 3;
+
+    fn new_const(value: f32) -> Self {
+        Self::new_const(value)
+    }
+
     fn is_black(&self) -> bool {
         floats::approx_zero_ar(&self.data)
     }
@@ -30,12 +36,28 @@ impl Color for Srgb {
         Self::sqrt(self)
     }
 
+    fn exp(&self) -> Self {
+        Self::exp(self)
+    }
+
+    fn avg(&self) -> f32 {
+        Self::avg(self)
+    }
+
+    fn from_rgb(rgb: Srgb) -> Self {
+        rgb
+    }
+
+    fn from_spectral(spectral: &Spectral) -> Self {
+        spectral.to_rgb()
+    }
+
     fn to_rgb(&self) -> Srgb {
         *self
     }
 
     fn to_xyz(&self) -> Xyz {
-        Xyz::from(srgb_to_xyz_mat() * srgbs_to_linear(self.to_vec3()))
+        Xyz::from(srgb_to_xyz_mat() * self.to_vec3())
     }
 
     fn black() -> Self {
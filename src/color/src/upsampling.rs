@@ -0,0 +1,140 @@
+//! Conversions of RGB colors to smooth spectra, following Smits (1999), "An RGB-to-Spectrum
+//! Conversion for Reflectances".
+//!
+//! A color is decomposed into white and either cyan, magenta or yellow, and red, green or blue,
+//! whose spectra were optimized to be smooth and bounded.
+
+use crate::*;
+use lazy_static::lazy_static;
+
+/// The number of samples of the basis spectra.
+const SMITS_SAMPLES: usize = 10;
+/// The wavelengths in nm of the samples of the basis spectra.
+const SMITS_LAMBDA: [f32; SMITS_SAMPLES] = [
+    380.0, 417.7, 455.5, 493.3, 531.1, 568.8, 606.6, 644.4, 682.2, 720.0,
+];
+
+const SMITS_WHITE: [f32; SMITS_SAMPLES] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f32; SMITS_SAMPLES] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f32; SMITS_SAMPLES] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f32; SMITS_SAMPLES] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f32; SMITS_SAMPLES] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f32; SMITS_SAMPLES] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f32; SMITS_SAMPLES] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// The basis spectra resampled to our spectral samples.
+struct Basis {
+    white: Spectral,
+    cyan: Spectral,
+    magenta: Spectral,
+    yellow: Spectral,
+    red: Spectral,
+    green: Spectral,
+    blue: Spectral,
+    /// A smooth spectrum with the chromaticity of the sRGB white point (D65)
+    illuminant: Spectral,
+}
+
+impl Basis {
+    fn new() -> Self {
+        let resample = |v: &[f32]| Spectral::from_sampled(&SMITS_LAMBDA, v, SMITS_SAMPLES);
+
+        let red = resample(&SMITS_RED);
+        let green = resample(&SMITS_GREEN);
+        let blue = resample(&SMITS_BLUE);
+
+        // find the combination of red, green and blue matching the white point
+        let primaries = Mat3::new(
+            red.to_xyz().to_vec3(),
+            green.to_xyz().to_vec3(),
+            blue.to_xyz().to_vec3(),
+        );
+        let white = srgb_to_xyz_mat() * Vec3::one();
+        let weights = primaries.inversed() * white;
+        let illuminant =
+            (red * weights.x + green * weights.y + blue * weights.z).clamp(0.0, f32::MAX);
+
+        Self {
+            white: resample(&SMITS_WHITE),
+            cyan: resample(&SMITS_CYAN),
+            magenta: resample(&SMITS_MAGENTA),
+            yellow: resample(&SMITS_YELLOW),
+            red,
+            green,
+            blue,
+            illuminant,
+        }
+    }
+
+    /// Decomposes the color into the basis spectra.
+    fn decompose(&self, rgb: &Srgb) -> Spectral {
+        let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+
+        if r <= g && r <= b {
+            let spectrum = self.white * r;
+            if g <= b {
+                spectrum + self.cyan * (g - r) + self.blue * (b - g)
+            } else {
+                spectrum + self.cyan * (b - r) + self.green * (g - b)
+            }
+        } else if g <= r && g <= b {
+            let spectrum = self.white * g;
+            if r <= b {
+                spectrum + self.magenta * (r - g) + self.blue * (b - r)
+            } else {
+                spectrum + self.magenta * (b - g) + self.red * (r - b)
+            }
+        } else {
+            let spectrum = self.white * b;
+            if r <= g {
+                spectrum + self.yellow * (r - b) + self.green * (g - r)
+            } else {
+                spectrum + self.yellow * (g - b) + self.red * (r - g)
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref BASIS: Basis = Basis::new();
+}
+
+/// # Summary
+/// Converts a (linear) sRGB reflectance to a smooth spectrum bounded by `[0, 1]`.
+///
+/// # Arguments
+/// * `rgb` - The reflectance
+///
+/// # Results
+/// * `Spectral` - The reflectance spectrum
+pub fn reflectance(rgb: &Srgb) -> Spectral {
+    BASIS.decompose(rgb).clamp(0.0, 1.0)
+}
+
+/// # Summary
+/// Converts a (linear) sRGB emission to a smooth spectrum.
+/// Contrary to reflectances, white is converted to the sRGB white point (D65) instead of a
+/// constant spectrum, such that white lights appear white.
+///
+/// # Arguments
+/// * `rgb` - The emission
+///
+/// # Results
+/// * `Spectral` - The emission spectrum
+pub fn illuminant(rgb: &Srgb) -> Spectral {
+    BASIS.decompose(rgb).clamp(0.0, f32::MAX) * BASIS.illuminant
+}
@@ -14,6 +14,12 @@ impl Xyz {
 }
 
 impl Color for Xyz {
+    const SIZE: usize = 3;
+
+    fn new_const(value: f32) -> Self {
+        Self::new_const(value)
+    }
+
     fn is_black(&self) -> bool {
         self.data.iter().all(|value| floats::approx_zero(*value))
     }
@@ -33,8 +39,24 @@ impl Color for Xyz {
         Self::sqrt(self)
     }
 
+    fn exp(&self) -> Self {
+        Self::exp(self)
+    }
+
+    fn avg(&self) -> f32 {
+        Self::avg(self)
+    }
+
+    fn from_rgb(rgb: Srgb) -> Self {
+        rgb.to_xyz()
+    }
+
+    fn from_spectral(spectral: &Spectral) -> Self {
+        spectral.to_xyz()
+    }
+
     fn to_rgb(&self) -> Srgb {
-        Srgb::from(linears_to_srgb(xyz_to_srgb_mat() * self.to_vec3()))
+        Srgb::from(xyz_to_srgb_mat() * self.to_vec3())
     }
 
     fn to_xyz(&self) -> Xyz {
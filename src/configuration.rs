@@ -1,14 +1,35 @@
 use crate::demo_scenes::debug::DebugScene;
 use crate::demo_scenes::*;
+use crate::integrator::ambient_occlusion::AmbientOcclusion;
+use crate::integrator::bdpt::Bdpt;
 use crate::integrator::debug_normals::DebugNormals;
+use crate::integrator::direct_lighting::{DirectLighting, LightStrategy};
+use crate::integrator::light_tracing::LightTracing;
+use crate::integrator::mlt::{Mlt, MltCore};
 use crate::integrator::path::Path;
+use crate::integrator::photon_mapping::PhotonMapping;
+use crate::integrator::polarized_path::PolarizedPath;
+use crate::integrator::spectral_path::SpectralPath;
+use crate::integrator::sppm::Sppm;
+use crate::integrator::volumetric_path::VolumetricPath;
 use crate::integrator::whitted::Whitted;
 use crate::integrator::Integrator;
+use crate::polarization::{StokesFilm, StokesImage};
 #[cfg(feature = "live-window")]
 use crate::render::fast_window::FastWindow;
+use crate::render::film::Film;
+use crate::render::png;
 use crate::render::renderer::Renderer;
 use crate::sampler::{NoopSampler, RandomSampler, Sampler};
+use crate::{Spectrum, LIGHT_SAMPLES_1D};
+use color::color_space::{ChromaticAdaptation, ColorSpace};
+use color::illuminant::Illuminant;
+use color::response::ResponseCurve;
+use color::{Spectral, Srgb, Xyz};
+use image::ColorType;
+use std::any::Any;
 use std::convert::TryInto;
+use std::fs;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -24,25 +45,118 @@ pub struct Configuration {
     pub threads: u32,
     pub output: Option<String>,
     pub pixel_type: PixelType,
+    /// The colors light is transported in
+    pub spectrum_type: SpectrumType,
+    /// The color space to encode the output in, or `None` to save the linear values untagged
+    pub output_space: Option<ColorSpace>,
+    pub adaptation: ChromaticAdaptation,
+    /// The white point to balance, or `None` to keep the white point of sRGB (D65)
+    pub white_balance: Option<Illuminant>,
+    /// The film or sensor response curve to develop the image with
+    pub response: Option<ResponseCurve>,
     pub integrator_type: IntegratorType,
+    /// The number of photons to emit, or `None` for the default of the photon integrator
+    pub photons: Option<usize>,
+    /// The radius to gather photons in, or `None` for the default of the photon integrator
+    pub radius: Option<f32>,
+    /// The maximum occluder distance, or `None` for the default of the ambient occlusion integrator
+    pub ao_distance: Option<f32>,
+    /// The number of occlusion rays, or `None` for the default of the ambient occlusion integrator
+    pub ao_samples: Option<u32>,
     pub demo_type: DemoType,
 }
 
 impl Configuration {
-    /// Creates a renderer instance from this configuration file.
-    pub fn create_renderer(&self) -> Renderer {
+    /// # Summary
+    /// Creates a renderer instance from this configuration file, transporting light in the given
+    /// colors.
+    ///
+    /// # Results
+    /// * `Result<Renderer<S>, String>` - The renderer or an error message if the integrator does
+    ///   not support the colors
+    pub fn create_renderer<S: Spectrum>(&self) -> Result<Renderer<S>, String> {
         let (scene, camera) = match self.demo_type {
             DemoType::SphereScene => SphereScene::create(self.width, self.height),
-            DemoType::CornellScene => CornellScene::create(self.width, self.height),
+            DemoType::CornellScene(sphere) => {
+                CornellScene::create_with_sphere(sphere, self.width, self.height)
+            }
+            DemoType::FoggyCornellScene => FoggyCornellScene::create(self.width, self.height),
+            DemoType::SmokyCornellScene => SmokyCornellScene::create(self.width, self.height),
+            DemoType::WaxCornellScene => WaxCornellScene::create(self.width, self.height),
             DemoType::DebugScene => DebugScene::create(self.width, self.height),
         };
         let scene = Arc::new(scene);
         let camera = Arc::new(camera);
+        let film = Arc::new(Film::new(self.width, self.height));
+        let stokes = match self.integrator_type {
+            IntegratorType::PolarizedPath => {
+                Some(Arc::new(StokesFilm::new(self.width, self.height)))
+            }
+            _ => None,
+        };
 
-        let integrator: Arc<dyn Integrator> = match self.integrator_type {
+        let integrator: Arc<dyn Integrator<S>> = match self.integrator_type {
             IntegratorType::Debug => Arc::new(DebugNormals),
+            IntegratorType::AmbientOcclusion => Arc::new(AmbientOcclusion::new(
+                self.ao_distance.unwrap_or(1.0),
+                self.ao_samples.unwrap_or(4),
+            )),
             IntegratorType::Whitted => Arc::new(Whitted::new(self.depth)),
+            IntegratorType::DirectLighting(strategy) => Arc::new(DirectLighting::new(
+                self.depth,
+                strategy,
+                LIGHT_SAMPLES_1D,
+                &scene,
+            )),
             IntegratorType::Path => Arc::new(Path::new(3, self.depth)),
+            IntegratorType::VolumetricPath => Arc::new(VolumetricPath::new(3, self.depth)),
+            IntegratorType::SpectralPath => spectral_path(3, self.depth).ok_or_else(|| {
+                format!(
+                    "Integrator {:?} requires spectral rendering, not {:?}",
+                    self.integrator_type, self.spectrum_type
+                )
+            })?,
+            IntegratorType::PolarizedPath => Arc::new(PolarizedPath::new(
+                3,
+                self.depth,
+                camera.clone(),
+                stokes.clone().expect("Stokes film is missing"),
+            )),
+            IntegratorType::Bdpt => Arc::new(Bdpt::new(self.depth, camera.clone(), film.clone())),
+            IntegratorType::LightTracing => {
+                Arc::new(LightTracing::new(self.depth, camera.clone(), film.clone()))
+            }
+            IntegratorType::PhotonMapping => Arc::new(PhotonMapping::new(
+                self.depth,
+                self.photons.unwrap_or(1_000_000),
+                self.radius.unwrap_or(0.05),
+            )),
+            IntegratorType::Sppm => Arc::new(Sppm::new(
+                self.depth,
+                self.photons.unwrap_or(100_000),
+                self.radius.unwrap_or(0.1),
+                2.0 / 3.0,
+            )),
+            IntegratorType::Mlt | IntegratorType::MltBdpt => {
+                let core = match self.integrator_type {
+                    IntegratorType::MltBdpt => {
+                        MltCore::Bdpt(Bdpt::new(self.depth, camera.clone(), film.clone()))
+                    }
+                    _ => MltCore::Path(Path::new(3, self.depth)),
+                };
+
+                let num_chains = 64 * self.threads as usize;
+                Arc::new(Mlt::new(
+                    core,
+                    camera.clone(),
+                    film.clone(),
+                    self.threads,
+                    100_000,
+                    num_chains,
+                    0.01,
+                    0.3,
+                ))
+            }
         };
 
         let sampler: Arc<dyn Sampler> = match self.integrator_type {
@@ -52,11 +166,13 @@ impl Configuration {
 
         let config = Arc::new(self.clone());
 
-        Renderer::new(scene, camera, sampler, integrator, config)
+        Ok(Renderer::new(
+            scene, camera, film, stokes, sampler, integrator, config,
+        ))
     }
 
     #[cfg(feature = "hpc-signals")]
-    fn signal_watcher(&'static self, renderer: Renderer) {
+    fn signal_watcher<S: Spectrum>(&'static self, renderer: Renderer<S>) {
         unsafe {
             signal_hook::register(signal_hook::SIGTERM, move || {
                 println!("Received SIGTERM. Saving current image...");
@@ -73,33 +189,128 @@ impl Configuration {
         }
     }
 
-    fn save_image(&self, renderer: &Renderer) -> Result<(), String> {
+    fn save_image<S: Spectrum>(&self, renderer: &Renderer<S>) -> Result<(), String> {
         if let Some(output) = &self.output {
             if self.verbose {
                 println!("Saving image");
             }
-            match self.pixel_type {
-                PixelType::U8 => renderer
-                    .get_image_u8()
-                    .save(output)
-                    .map_err(|e| format!("Unable to save image: {}", e))?,
-                PixelType::U16 => renderer
-                    .get_image_u16()
-                    .save(output)
-                    .map_err(|e| format!("Unable to save image: {}", e))?,
+            match self.output_space {
+                Some(space) => self.save_encoded_image(renderer, output, space)?,
+                None => match self.pixel_type {
+                    PixelType::U8 => renderer
+                        .get_image_u8()
+                        .save(output)
+                        .map_err(|e| format!("Unable to save image: {}", e))?,
+                    PixelType::U16 => renderer
+                        .get_image_u16()
+                        .save(output)
+                        .map_err(|e| format!("Unable to save image: {}", e))?,
+                },
             };
+            self.save_stokes_images(renderer, output)?;
             println!("Successfully saved image");
         }
 
         Ok(())
     }
 
+    /// # Summary
+    /// Saves the image encoded in the given color space.
+    /// PNG images are tagged with the color space, other formats are saved without metadata.
+    ///
+    /// # Arguments
+    /// * `renderer` - The renderer
+    /// * `output` - The output path
+    /// * `space` - The color space to encode in
+    ///
+    /// # Results
+    /// * `Result<(), String>` - An error message if the image could not be saved
+    fn save_encoded_image<S: Spectrum>(
+        &self,
+        renderer: &Renderer<S>,
+        output: &str,
+        space: ColorSpace,
+    ) -> Result<(), String> {
+        let is_png = output.to_lowercase().ends_with(".png");
+
+        match self.pixel_type {
+            PixelType::U8 => {
+                let image = renderer.get_encoded_image_u8(space);
+                if is_png {
+                    let png = png::encode(&image, self.width, self.height, ColorType::Rgb8, space)?;
+                    fs::write(output, png).map_err(|e| format!("Unable to save image: {}", e))
+                } else {
+                    image
+                        .save(output)
+                        .map_err(|e| format!("Unable to save image: {}", e))
+                }
+            }
+            PixelType::U16 => {
+                let image = renderer.get_encoded_image_u16(space);
+                if is_png {
+                    let data: Vec<u8> = image
+                        .iter()
+                        .flat_map(|v| v.to_be_bytes().to_vec())
+                        .collect();
+                    let png = png::encode(&data, self.width, self.height, ColorType::Rgb16, space)?;
+                    fs::write(output, png).map_err(|e| format!("Unable to save image: {}", e))
+                } else {
+                    image
+                        .save(output)
+                        .map_err(|e| format!("Unable to save image: {}", e))
+                }
+            }
+        }
+    }
+
+    /// # Summary
+    /// Saves the images derived from the Stokes vectors next to the output, if the rendering
+    /// carries polarization. The files are named after the output, e.g. `out_dop.png` for
+    /// `out.png`.
+    ///
+    /// # Arguments
+    /// * `renderer` - The renderer
+    /// * `output` - The output path
+    ///
+    /// # Results
+    /// * `Result<(), String>` - An error message if an image could not be saved
+    fn save_stokes_images<S: Spectrum>(
+        &self,
+        renderer: &Renderer<S>,
+        output: &str,
+    ) -> Result<(), String> {
+        let (stem, extension) = match output.rfind('.') {
+            Some(index) => output.split_at(index),
+            None => (output, ""),
+        };
+
+        for image in StokesImage::ALL.iter() {
+            if let Some(buffer) = renderer.get_stokes_image_u16(*image) {
+                let path = format!("{}_{}{}", stem, image.suffix(), extension);
+                buffer
+                    .save(path)
+                    .map_err(|e| format!("Unable to save image: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn start_rendering(&'static self) -> Result<(), String> {
         if self.verbose {
             println!("{:#?}", self);
         }
 
-        let mut renderer = self.create_renderer();
+        match self.spectrum_type {
+            SpectrumType::Srgb => self.render::<Srgb>(),
+            SpectrumType::Xyz => self.render::<Xyz>(),
+            SpectrumType::Spectral => self.render::<Spectral>(),
+        }
+    }
+
+    /// Renders and saves the image, transporting light in the given colors.
+    fn render<S: Spectrum>(&'static self) -> Result<(), String> {
+        let mut renderer = self.create_renderer::<S>()?;
         #[cfg(feature = "hpc-signals")]
         {
             self.signal_watcher(renderer.clone());
@@ -133,6 +344,26 @@ impl Configuration {
     }
 }
 
+/// # Summary
+/// Creates a path tracer using hero wavelength sampling, if light is transported in spectra.
+///
+/// # Arguments
+/// * `min_depth` - The depth after which paths are terminated by russian roulette
+/// * `max_depth` - The maximum depth
+///
+/// # Results
+/// * `Option<Arc<dyn Integrator<S>>>` - The integrator or `None` if `S` is not `Spectral`
+fn spectral_path<S: Spectrum>(min_depth: u32, max_depth: u32) -> Option<Arc<dyn Integrator<S>>> {
+    let integrator: Arc<dyn Integrator<Spectral>> =
+        Arc::new(SpectralPath::new(min_depth, max_depth));
+    let integrator: Box<dyn Any> = Box::new(integrator);
+
+    integrator
+        .downcast::<Arc<dyn Integrator<S>>>()
+        .ok()
+        .map(|integrator| *integrator)
+}
+
 #[derive(Debug, Clone)]
 pub enum PixelType {
     U8,
@@ -151,11 +382,63 @@ impl TryInto<PixelType> for &str {
     }
 }
 
+#[derive(Debug, Copy, Clone)]
+pub enum SpectrumType {
+    /// RGB colors of the working space
+    Srgb,
+    /// CIE XYZ colors
+    Xyz,
+    /// Spectra sampled over the visible wavelengths
+    Spectral,
+}
+
+impl SpectrumType {
+    /// # Summary
+    /// Returns the colors to transport light in, if none are configured.
+    /// Integrators switching wavelengths render spectra, all others sRGB colors.
+    ///
+    /// # Arguments
+    /// * `integrator_type` - The configured integrator
+    ///
+    /// # Results
+    /// * `Self` - The default colors of the integrator
+    pub fn default_for(integrator_type: &IntegratorType) -> Self {
+        match integrator_type {
+            IntegratorType::SpectralPath => SpectrumType::Spectral,
+            _ => SpectrumType::Srgb,
+        }
+    }
+}
+
+impl TryInto<SpectrumType> for &str {
+    type Error = String;
+
+    fn try_into(self) -> Result<SpectrumType, Self::Error> {
+        match self {
+            "srgb" | "sRGB" | "SRGB" => Ok(SpectrumType::Srgb),
+            "xyz" | "Xyz" | "XYZ" => Ok(SpectrumType::Xyz),
+            "spectral" | "Spectral" | "SPECTRAL" => Ok(SpectrumType::Spectral),
+            _ => Err(self.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum IntegratorType {
     Debug,
+    AmbientOcclusion,
     Whitted,
+    DirectLighting(LightStrategy),
     Path,
+    VolumetricPath,
+    SpectralPath,
+    PolarizedPath,
+    Bdpt,
+    LightTracing,
+    PhotonMapping,
+    Sppm,
+    Mlt,
+    MltBdpt,
 }
 
 impl TryInto<IntegratorType> for &str {
@@ -164,8 +447,27 @@ impl TryInto<IntegratorType> for &str {
     fn try_into(self) -> Result<IntegratorType, Self::Error> {
         match self {
             "debug" | "Debug" | "DEBUG" => Ok(IntegratorType::Debug),
+            "ao" | "Ao" | "AO" => Ok(IntegratorType::AmbientOcclusion),
             "whitted" | "Whitted" | "WHITTED" => Ok(IntegratorType::Whitted),
+            "direct" | "Direct" | "DIRECT" => {
+                Ok(IntegratorType::DirectLighting(LightStrategy::All))
+            }
+            "direct-uniform" | "Direct-Uniform" | "DIRECT-UNIFORM" => {
+                Ok(IntegratorType::DirectLighting(LightStrategy::Uniform))
+            }
+            "direct-power" | "Direct-Power" | "DIRECT-POWER" => {
+                Ok(IntegratorType::DirectLighting(LightStrategy::Power))
+            }
             "path" | "Path" | "PATH" => Ok(IntegratorType::Path),
+            "volpath" | "VolPath" | "VOLPATH" => Ok(IntegratorType::VolumetricPath),
+            "hero" | "Hero" | "HERO" => Ok(IntegratorType::SpectralPath),
+            "polarized" | "Polarized" | "POLARIZED" => Ok(IntegratorType::PolarizedPath),
+            "bdpt" | "Bdpt" | "BDPT" => Ok(IntegratorType::Bdpt),
+            "lt" | "Lt" | "LT" => Ok(IntegratorType::LightTracing),
+            "pm" | "Pm" | "PM" => Ok(IntegratorType::PhotonMapping),
+            "sppm" | "Sppm" | "SPPM" => Ok(IntegratorType::Sppm),
+            "mlt" | "Mlt" | "MLT" => Ok(IntegratorType::Mlt),
+            "mlt-bdpt" | "Mlt-Bdpt" | "MLT-BDPT" => Ok(IntegratorType::MltBdpt),
             _ => Err(self.to_string()),
         }
     }
@@ -174,7 +476,14 @@ impl TryInto<IntegratorType> for &str {
 #[derive(Debug, Clone)]
 pub enum DemoType {
     SphereScene,
-    CornellScene,
+    /// The cornell box with a sphere of the given material
+    CornellScene(SphereMaterial),
+    /// The cornell box in fog, rendering participating media with `VolPath`
+    FoggyCornellScene,
+    /// The cornell box with smoke loaded from a density grid, rendered with `VolPath`
+    SmokyCornellScene,
+    /// The cornell box with a subsurface scattering bunny, rendered with `VolPath`
+    WaxCornellScene,
     DebugScene,
 }
 
@@ -184,7 +493,10 @@ impl TryInto<DemoType> for &str {
     fn try_into(self) -> Result<DemoType, Self::Error> {
         match self {
             "spheres" | "Spheres" | "SPHERES" => Ok(DemoType::SphereScene),
-            "cornell" | "Cornell" | "CORNELL" => Ok(DemoType::CornellScene),
+            "cornell" | "Cornell" | "CORNELL" => Ok(DemoType::CornellScene(SphereMaterial::Glass)),
+            "foggy-cornell" | "Foggy-Cornell" | "FOGGY-CORNELL" => Ok(DemoType::FoggyCornellScene),
+            "smoky-cornell" | "Smoky-Cornell" | "SMOKY-CORNELL" => Ok(DemoType::SmokyCornellScene),
+            "wax-cornell" | "Wax-Cornell" | "WAX-CORNELL" => Ok(DemoType::WaxCornellScene),
             "debug" | "Debug" | "DEBUG" => Ok(DemoType::DebugScene),
             _ => Err(self.to_string()),
         }
@@ -2,11 +2,23 @@
 #![allow(unused_imports)]
 
 use crate::bxdf::bsdf::BSDF;
-use crate::bxdf::fresnel::{Dielectric, FresnelNoOp};
+use crate::bxdf::disney::DisneyMaterial;
+use crate::bxdf::fluorescence::{FluorescentReflection, Fluorophore, Reradiation};
+use crate::bxdf::fresnel::{Conductor, Dielectric, FresnelNoOp, FresnelSpecular};
 use crate::bxdf::lambertian::LambertianReflection;
+use crate::bxdf::layered::CoatedBxDF;
+use crate::bxdf::metal::Metal;
+use crate::bxdf::microfacet::{
+    BeckmannDistribution, MicrofacetReflection, RoughDielectric, TrowbridgeReitzDistribution,
+};
 use crate::bxdf::oren_nayar::OrenNayar;
+use crate::bxdf::refractive_index::RefractiveIndex;
 use crate::bxdf::specular::{SpecularReflection, SpecularTransmission};
 use crate::demo_scenes::{DemoScene, FOVY, SIGMA};
+use crate::medium::grid::{DensityGrid, GridMedium};
+use crate::medium::homogeneous::HomogeneousMedium;
+use crate::medium::subsurface::SubsurfaceMaterial;
+use crate::medium::Medium;
 use crate::render::camera::Camera;
 use crate::render::objects::emitter::EmitterObj;
 use crate::render::objects::receiver::ReceiverObj;
@@ -14,15 +26,18 @@ use crate::render::objects::Instance;
 use crate::render::objects::Instance::{Emitter, Receiver};
 use crate::render::scene::Scene;
 use crate::Spectrum;
-use color::Color;
+use color::illuminant::Illuminant;
+use color::{Color, Spectral, Srgb};
 use geometry::aabb::Aabb;
 use geometry::capsule::Capsule;
 use geometry::cylinder::Cylinder;
+use geometry::lens::BiconvexLens;
 use geometry::mesh::Mesh;
 use geometry::point::Point;
 use geometry::sphere::Sphere;
 use geometry::tube::Tube;
 use geometry::Geometry;
+use std::convert::TryInto;
 use std::sync::Arc;
 use ultraviolet::{Bivec3, Rotor3, Vec3};
 
@@ -41,10 +56,86 @@ pub const X_CENTER: f32 = (RIGHT_WALL + LEFT_WALL) / 2.0;
 pub const Y_CENTER: f32 = (CEILING + FLOOR) / 2.0;
 pub const Z_CENTER: f32 = (BACK_WALL + FRONT) / 2.0;
 
+/// The material of the sphere on the floor of the cornell box.
+#[derive(Debug, Copy, Clone)]
+pub enum SphereMaterial {
+    Glass,
+    /// Rough glass
+    Frosted,
+    /// A polished metal
+    Metal(Metal),
+    /// Gold brushed along one direction, i.e. of anisotropic roughness
+    Brushed,
+    /// A diffuse white dyed with rhodamine, re-radiating green light as yellow light (rendered
+    /// exactly by `Hero` only)
+    Fluorescent,
+    /// A lacquered red plastic of the principled Disney material
+    Principled,
+    /// Rough gold under a clear varnish
+    Coated,
+}
+
+impl TryInto<SphereMaterial> for &str {
+    type Error = String;
+
+    /// Parses the name of a material or a metal given by its name or chemical symbol, like `gold`
+    /// or `Au`.
+    fn try_into(self) -> Result<SphereMaterial, Self::Error> {
+        match self {
+            "glass" | "Glass" | "GLASS" => Ok(SphereMaterial::Glass),
+            "frosted" | "Frosted" | "FROSTED" => Ok(SphereMaterial::Frosted),
+            "brushed" | "Brushed" | "BRUSHED" => Ok(SphereMaterial::Brushed),
+            "fluorescent" | "Fluorescent" | "FLUORESCENT" => Ok(SphereMaterial::Fluorescent),
+            "principled" | "Principled" | "PRINCIPLED" => Ok(SphereMaterial::Principled),
+            "coated" | "Coated" | "COATED" => Ok(SphereMaterial::Coated),
+            _ => self.try_into().map(SphereMaterial::Metal),
+        }
+    }
+}
+
 pub struct CornellScene;
 
 impl CornellScene {
-    fn create_box() -> Scene {
+    /// # Summary
+    /// Creates the cornell box with a sphere of the given material on its floor.
+    ///
+    /// # Arguments
+    /// * `sphere` - The material of the sphere
+    /// * `width` - The width of the rendering in pixels
+    /// * `height` - The height of the rendering in pixels
+    ///
+    /// # Results
+    /// * `(Scene<S>, Camera)` - The scene and its camera
+    pub fn create_with_sphere<S: Spectrum>(
+        sphere: SphereMaterial,
+        width: u32,
+        height: u32,
+    ) -> (Scene<S>, Camera) {
+        (Self::create_box(sphere), Self::create_camera(width, height))
+    }
+
+    fn create_box<S: Spectrum>(sphere: SphereMaterial) -> Scene<S> {
+        let mut scene = Self::create_empty_box();
+
+        // objects
+        let sphere = match sphere {
+            SphereMaterial::Glass => Self::sphere(),
+            SphereMaterial::Frosted => Self::frosted_sphere(),
+            SphereMaterial::Metal(metal) => Self::metal_sphere(metal),
+            SphereMaterial::Brushed => Self::brushed_sphere(),
+            SphereMaterial::Fluorescent => Self::fluorescent_sphere(),
+            SphereMaterial::Principled => Self::principled_sphere(),
+            SphereMaterial::Coated => Self::coated_sphere(),
+        };
+        scene.add(sphere).add(Self::capsule()).add(Self::tube());
+
+        scene.build_bvh();
+
+        scene
+    }
+
+    /// Creates the walls and the light of the box, leaving building the bvh to the caller.
+    fn create_empty_box<S: Spectrum>() -> Scene<S> {
         let mut scene = Scene::default();
 
         // walls
@@ -54,15 +145,9 @@ impl CornellScene {
             .add(Self::back_wall())
             .add(Self::floor())
             .add(Self::ceiling())
-            // objects
-            .add(Self::sphere())
-            .add(Self::capsule())
-            .add(Self::tube())
             // lights
             .add(Self::emitter());
 
-        scene.build_bvh();
-
         scene
     }
 
@@ -86,34 +171,40 @@ impl CornellScene {
         Camera::new(position, center, up, FOVY, width, height)
     }
 
-    fn emitter() -> Instance {
+    fn emitter<S: Spectrum>() -> Instance<S> {
         let position = Vec3::new(X_CENTER, CEILING - RADIUS, Z_CENTER);
         let point = Point::new(position);
 
         // let center = Vec3::new(X_CENTER, CEILING, Z_CENTER);
         // let sphere = Sphere::new(center, RADIUS);
 
-        let color = Spectrum::white();
+        let color = S::white();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
-        Emitter(Arc::new(EmitterObj::new(
+        Emitter(Arc::new(EmitterObj::with_illuminant(
             point,
             Arc::new(bsdf),
-            color * 2.0,
+            Illuminant::D65,
+            2.0,
         )))
     }
 
-    fn bunny() -> Instance {
+    /// Loads the bunny mesh, standing in the center of the floor.
+    fn bunny_mesh() -> Mesh {
         let file_name = "./resources/meshes/bunny.obj";
         let (model, _) = tobj::load_obj(file_name, true).expect("Could not load bunny file");
         let scale = Vec3::one() * 25.0;
         let center_floor = Vec3::new(X_CENTER, FLOOR, Z_CENTER);
         let rotation = Rotor3::default();
 
-        let bunny = Mesh::load_scale_floor_rot((&model[0].mesh, scale, center_floor, rotation));
+        Mesh::load_scale_floor_rot((&model[0].mesh, scale, center_floor, rotation))
+    }
+
+    fn bunny<S: Spectrum>() -> Instance<S> {
+        let bunny = Self::bunny_mesh();
 
-        let color = Spectrum::white();
+        let color = S::white();
         let dielectric = Arc::new(Dielectric::new(1.0, 1.3));
         let transmission = Box::new(SpecularTransmission::new(color, dielectric));
         // let reflection = Box::new(SpecularReflection::new(color, dielectric));
@@ -122,7 +213,21 @@ impl CornellScene {
         Receiver(Arc::new(ReceiverObj::new(bunny, Arc::new(bsdf))))
     }
 
-    fn dragon() -> Instance {
+    fn wax_bunny<S: Spectrum>() -> Instance<S> {
+        let bunny = Self::bunny_mesh();
+
+        let albedo = S::from_rgb(Srgb::new([0.9, 0.75, 0.55]));
+        let mean_free_path = S::from_rgb(Srgb::new([0.3, 0.2, 0.1]));
+        let material = SubsurfaceMaterial::new(albedo, mean_free_path, 0.0);
+
+        Receiver(Arc::new(ReceiverObj::with_medium(
+            bunny,
+            Arc::new(BSDF::empty()),
+            Arc::new(material.medium()),
+        )))
+    }
+
+    fn dragon<S: Spectrum>() -> Instance<S> {
         let file_name = "./resources/meshes/dragon_4.obj";
         let (model, _) = tobj::load_obj(file_name, true).expect("Could not load dragon file");
         let scale = Vec3::one() * 25.0;
@@ -131,7 +236,7 @@ impl CornellScene {
 
         let dragon = Mesh::load_scale_floor_rot((&model[0].mesh, scale, floor, rotation));
 
-        let color = Spectrum::white();
+        let color = S::white();
         let dielectric = Arc::new(Dielectric::new(1.0, 1.3));
         let transmission = Box::new(SpecularTransmission::new(color, dielectric.clone()));
         let reflection = Box::new(SpecularReflection::new(color * 0.25, dielectric));
@@ -140,7 +245,7 @@ impl CornellScene {
         Receiver(Arc::new(ReceiverObj::new(dragon, Arc::new(bsdf))))
     }
 
-    fn sphere() -> Instance {
+    fn sphere<S: Spectrum>() -> Instance<S> {
         // center on ground
         let center = Vec3::new(
             LEFT_WALL + RIGHT_WALL * 1.5,
@@ -150,7 +255,7 @@ impl CornellScene {
 
         let sphere = Sphere::new(center, RADIUS);
 
-        let color = Spectrum::white();
+        let color = S::white();
         let fresnel = Arc::new(Dielectric::new(1.0, 1.1));
         let spec_trans = SpecularTransmission::new(color, fresnel);
         let bsdf = BSDF::new(vec![Box::new(spec_trans)]);
@@ -158,7 +263,146 @@ impl CornellScene {
         Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
     }
 
-    fn capsule() -> Instance {
+    fn frosted_sphere<S: Spectrum>() -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        let color = S::white();
+        let distribution = BeckmannDistribution::new(0.2, 0.2, true);
+        let dielectric = Dielectric::new(1.0, 1.5);
+        let rough = RoughDielectric::new(color, color, Box::new(distribution), dielectric);
+        let bsdf = BSDF::new(vec![Box::new(rough)]);
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn metal_sphere<S: Spectrum>(metal: Metal) -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        let fresnel = Arc::new(Conductor::metal(metal));
+        let specular = SpecularReflection::new(S::white(), fresnel);
+        let bsdf = BSDF::new(vec![Box::new(specular)]);
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn brushed_sphere<S: Spectrum>() -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        // brushed along the x-axis
+        let distribution = TrowbridgeReitzDistribution::new(0.1, 0.4, true);
+        let fresnel = Conductor::metal(Metal::Gold);
+        let microfacet =
+            MicrofacetReflection::new(S::white(), Box::new(distribution), Box::new(fresnel));
+        let bsdf = BSDF::new(vec![Box::new(microfacet)]);
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn principled_sphere<S: Spectrum>() -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        // lacquered red plastic
+        let mut material = DisneyMaterial::new(S::red());
+        material.roughness = 0.4;
+        material.clearcoat = 1.0;
+        material.clearcoat_gloss = 0.9;
+        let bsdf = material.bsdf();
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn coated_sphere<S: Spectrum>() -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        // rough gold under a clear varnish
+        let distribution = TrowbridgeReitzDistribution::new(0.3, 0.3, true);
+        let fresnel = Conductor::metal(Metal::Gold);
+        let microfacet =
+            MicrofacetReflection::new(S::white(), Box::new(distribution), Box::new(fresnel));
+        let base = BSDF::new(vec![Box::new(microfacet)]);
+
+        let coated = CoatedBxDF::new(base, Dielectric::new(1.0, 1.5), None);
+        let bsdf = BSDF::new(vec![Box::new(coated)]);
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn fluorescent_sphere<S: Spectrum>() -> Instance<S> {
+        // center on ground
+        let center = Vec3::new(
+            LEFT_WALL + RIGHT_WALL * 1.5,
+            FLOOR + RADIUS,
+            FRONT + (BACK_WALL - FRONT) * 0.75,
+        );
+
+        let sphere = Sphere::new(center, RADIUS);
+
+        let dye = Fluorophore::Rhodamine;
+        let reradiation = Reradiation::fluorescent(
+            &Spectral::new_const(0.8),
+            &(dye.excitation() * 0.9),
+            &dye.emission(),
+            dye.quantum_yield(),
+        );
+        let fluorescent = FluorescentReflection::new(reradiation);
+        let bsdf = BSDF::new(vec![Box::new(fluorescent)]);
+
+        Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf))))
+    }
+
+    fn lens<S: Spectrum>() -> Instance<S> {
+        let center = Vec3::new(X_CENTER, Y_CENTER, FRONT - 1.0);
+        let offset = Vec3::unit_z() * 0.9;
+
+        let sphere0 = Sphere::new(center + offset, 2.0);
+        let sphere1 = Sphere::new(center - offset, 2.0);
+        let lens = BiconvexLens::new(sphere0, sphere1);
+
+        let color = S::white();
+        let dielectric =
+            Dielectric::dispersive(RefractiveIndex::Constant(1.0), RefractiveIndex::diamond());
+        let fresnel_specular = FresnelSpecular::new(color, color, dielectric);
+        let bsdf = BSDF::new(vec![Box::new(fresnel_specular)]);
+
+        Receiver(Arc::new(ReceiverObj::new(lens, Arc::new(bsdf))))
+    }
+
+    fn capsule<S: Spectrum>() -> Instance<S> {
         let from = Vec3::new(
             LEFT_WALL * 1.5 + RIGHT_WALL,
             FLOOR + RADIUS,
@@ -172,14 +416,14 @@ impl CornellScene {
 
         let capsule = Capsule::new(from, to, RADIUS);
 
-        let color = Spectrum::white();
+        let color = S::white();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
         Receiver(Arc::new(ReceiverObj::new(capsule, Arc::new(bsdf))))
     }
 
-    fn tube() -> Instance {
+    fn tube<S: Spectrum>() -> Instance<S> {
         let radius = RADIUS / 4.0;
         let points = [
             Vec3::unit_y() * radius - Vec3::unit_x() * 2.0 - Vec3::unit_z() * 3.0,
@@ -190,7 +434,7 @@ impl CornellScene {
 
         let tube = Tube::new(&points, radius);
 
-        let color = (Spectrum::red() + Spectrum::blue()) / 2.0;
+        let color = (S::red() + S::blue()) / 2.0;
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
         // let alpha_x = roughness_to_alpha(0.5);
@@ -203,7 +447,51 @@ impl CornellScene {
         Receiver(Arc::new(ReceiverObj::new(tube, Arc::new(bsdf))))
     }
 
-    fn left_wall() -> Instance {
+    fn fog<S: Spectrum>() -> Instance<S> {
+        // slightly inside the walls to not coincide with them
+        let aabb = Aabb::new(
+            Vec3::new(LEFT_WALL, FLOOR, BACK_WALL) + Vec3::one() * THICKNESS,
+            Vec3::new(RIGHT_WALL, Y_CENTER, FRONT) - Vec3::one() * THICKNESS,
+        );
+
+        let sigma_a = S::new_const(0.05);
+        let sigma_s = S::new_const(0.2);
+        let medium = HomogeneousMedium::new(sigma_a, sigma_s, 0.3);
+
+        Receiver(Arc::new(ReceiverObj::with_medium(
+            aabb,
+            Arc::new(BSDF::empty()),
+            Arc::new(medium),
+        )))
+    }
+
+    fn smoke<S: Spectrum>() -> Instance<S> {
+        let center = Vec3::new(X_CENTER, Y_CENTER, Z_CENTER);
+        let aabb = Aabb::new(center - Vec3::one() * 2.0, center + Vec3::one() * 2.0);
+
+        // a puff, densest in the center and rippled towards the border
+        let file_name = "./resources/grids/smoke.grid";
+        let grid = DensityGrid::load(file_name).expect("Could not load smoke file");
+
+        let sigma_a = S::new_const(0.5);
+        let sigma_s = S::new_const(4.0);
+        let medium = GridMedium::new(grid, aabb, sigma_a, sigma_s, 0.5);
+
+        Receiver(Arc::new(ReceiverObj::with_medium(
+            aabb,
+            Arc::new(BSDF::empty()),
+            Arc::new(medium),
+        )))
+    }
+
+    fn atmosphere<S: Spectrum>() -> Arc<dyn Medium<S>> {
+        let sigma_a = S::new_const(0.01);
+        let sigma_s = S::new_const(0.02);
+
+        Arc::new(HomogeneousMedium::new(sigma_a, sigma_s, 0.0))
+    }
+
+    fn left_wall<S: Spectrum>() -> Instance<S> {
         let aabb = Aabb::new(
             Vec3::new(
                 LEFT_WALL - THICKNESS,
@@ -213,27 +501,27 @@ impl CornellScene {
             Vec3::new(LEFT_WALL, CEILING + THICKNESS, FRONT),
         );
 
-        let color = Spectrum::red();
+        let color = S::red();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
     }
 
-    fn right_wall() -> Instance {
+    fn right_wall<S: Spectrum>() -> Instance<S> {
         let aabb = Aabb::new(
             Vec3::new(RIGHT_WALL, FLOOR - THICKNESS, BACK_WALL - THICKNESS),
             Vec3::new(RIGHT_WALL + THICKNESS, CEILING + THICKNESS, FRONT),
         );
 
-        let color = Spectrum::green();
+        let color = S::green();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
     }
 
-    fn back_wall() -> Instance {
+    fn back_wall<S: Spectrum>() -> Instance<S> {
         let aabb = Aabb::new(
             Vec3::new(
                 LEFT_WALL - THICKNESS,
@@ -243,7 +531,7 @@ impl CornellScene {
             Vec3::new(RIGHT_WALL + THICKNESS, CEILING + THICKNESS, BACK_WALL),
         );
 
-        let color = Spectrum::white();
+        let color = S::white();
         // let spec_refl = SpecularReflection::new(color, Arc::new(FresnelNoOp));
         // let bsdf = BSDF::new(vec![Box::new(spec_refl)]);
         let oren_nayar = OrenNayar::new(color, SIGMA);
@@ -252,7 +540,7 @@ impl CornellScene {
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
     }
 
-    fn floor() -> Instance {
+    fn floor<S: Spectrum>() -> Instance<S> {
         let aabb = Aabb::new(
             Vec3::new(
                 LEFT_WALL - THICKNESS,
@@ -262,20 +550,20 @@ impl CornellScene {
             Vec3::new(RIGHT_WALL + THICKNESS, FLOOR, FRONT),
         );
 
-        let color = Spectrum::white();
+        let color = S::white();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
     }
 
-    fn ceiling() -> Instance {
+    fn ceiling<S: Spectrum>() -> Instance<S> {
         let aabb = Aabb::new(
             Vec3::new(LEFT_WALL - THICKNESS, CEILING, BACK_WALL - THICKNESS),
             Vec3::new(RIGHT_WALL + THICKNESS, CEILING + THICKNESS, FRONT),
         );
 
-        let color = Spectrum::white();
+        let color = S::white();
         let oren_nayar = OrenNayar::new(color, SIGMA);
         let bsdf = BSDF::new(vec![Box::new(oren_nayar)]);
 
@@ -284,7 +572,63 @@ impl CornellScene {
 }
 
 impl DemoScene for CornellScene {
-    fn create<'a>(width: u32, height: u32) -> (Scene, Camera) {
-        (Self::create_box(), Self::create_camera(width, height))
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
+        Self::create_with_sphere(SphereMaterial::Glass, width, height)
+    }
+}
+
+/// The cornell box filled with fog up to half its height, in a hazy atmosphere.
+pub struct FoggyCornellScene;
+
+impl DemoScene for FoggyCornellScene {
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
+        let mut scene = CornellScene::create_empty_box();
+
+        // objects
+        scene
+            .add(CornellScene::sphere())
+            .add(CornellScene::capsule())
+            .add(CornellScene::tube())
+            .add(CornellScene::fog());
+        scene.atmosphere = Some(CornellScene::atmosphere());
+
+        scene.build_bvh();
+
+        (scene, CornellScene::create_camera(width, height))
+    }
+}
+
+/// The cornell box with a puff of smoke in its center, loaded from a density grid.
+pub struct SmokyCornellScene;
+
+impl DemoScene for SmokyCornellScene {
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
+        let mut scene = CornellScene::create_empty_box();
+
+        // objects
+        scene
+            .add(CornellScene::capsule())
+            .add(CornellScene::tube())
+            .add(CornellScene::smoke());
+
+        scene.build_bvh();
+
+        (scene, CornellScene::create_camera(width, height))
+    }
+}
+
+/// The cornell box with a bunny of wax, scattering light below its surface.
+pub struct WaxCornellScene;
+
+impl DemoScene for WaxCornellScene {
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
+        let mut scene = CornellScene::create_empty_box();
+
+        // objects
+        scene.add(CornellScene::wax_bunny());
+
+        scene.build_bvh();
+
+        (scene, CornellScene::create_camera(width, height))
     }
 }
@@ -10,7 +10,7 @@ use crate::render::objects::Instance;
 use crate::render::objects::Instance::{Emitter, Receiver};
 use crate::render::scene::Scene;
 use crate::Spectrum;
-use color::Color;
+use color::{Color, Srgb};
 use geometry::aabb::Aabb;
 use geometry::sphere::Sphere;
 use std::sync::Arc;
@@ -19,27 +19,27 @@ use ultraviolet::Vec3;
 pub struct DebugScene;
 
 impl DebugScene {
-    fn plane() -> Instance {
+    fn plane<S: Spectrum>() -> Instance<S> {
         let min = Vec3::new(-10.0, -5.0, -10.0);
         let max = Vec3::new(10.0, 0.0, 10.0);
         let aabb = Aabb::new(min, max);
 
-        let lambertian = LambertianReflection::new(Spectrum::white());
+        let lambertian = LambertianReflection::new(S::white());
         let bsdf = BSDF::new(vec![Box::new(lambertian)]);
 
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
     }
 
-    fn sphere() -> Instance {
+    fn sphere<S: Spectrum>() -> Instance<S> {
         let sphere = Sphere::new(Vec3::unit_y() * 1.1, 1.0);
 
-        let lambertian = LambertianReflection::new(Spectrum::black());
+        let lambertian = LambertianReflection::new(S::black());
         let bsdf = BSDF::new(vec![Box::new(lambertian)]);
 
         Emitter(Arc::new(EmitterObj::new(
             sphere,
             Arc::new(bsdf),
-            Spectrum::white() * 3.0,
+            S::from_rgb_illuminant(Srgb::white()) * 3.0,
         )))
     }
 
@@ -56,7 +56,7 @@ impl DebugScene {
 }
 
 impl DemoScene for DebugScene {
-    fn create(width: u32, height: u32) -> (Scene, Camera) {
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
         let mut scene = Scene::default();
 
         scene.add(Self::plane()).add(Self::sphere());
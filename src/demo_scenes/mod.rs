@@ -1,16 +1,19 @@
 use crate::render::camera::Camera;
 use crate::render::scene::Scene;
+use crate::Spectrum;
 
 pub mod cornell_box;
 pub mod debug;
 pub mod spheres;
 
-pub use cornell_box::CornellScene;
+pub use cornell_box::{
+    CornellScene, FoggyCornellScene, SmokyCornellScene, SphereMaterial, WaxCornellScene,
+};
 pub use spheres::SphereScene;
 
 pub const SIGMA: f32 = 20.0;
 pub const FOVY: f32 = 70.0;
 
 pub trait DemoScene {
-    fn create(width: u32, height: u32) -> (Scene, Camera);
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera);
 }
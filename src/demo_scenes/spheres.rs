@@ -11,7 +11,7 @@ use crate::render::objects::Instance;
 use crate::render::objects::Instance::{Emitter, Receiver};
 use crate::render::scene::Scene;
 use crate::Spectrum;
-use color::Color;
+use color::Srgb;
 use geometry::aabb::Aabb;
 use geometry::sphere::Sphere;
 use std::sync::Arc;
@@ -22,12 +22,12 @@ const RADIUS: f32 = 0.5;
 pub struct SphereScene;
 
 impl SphereScene {
-    fn ground() -> Instance {
+    fn ground<S: Spectrum>() -> Instance<S> {
         let min = Vec3::new(-10000.0, -5.0, -10000.0);
         let max = Vec3::new(10000.0, 0.0, 10000.0);
         let aabb = Aabb::new(min, max);
 
-        let lambertian = OrenNayar::new(Spectrum::white(), SIGMA);
+        let lambertian = OrenNayar::new(S::white(), SIGMA);
         let bsdf = BSDF::new(vec![Box::new(lambertian)]);
 
         Receiver(Arc::new(ReceiverObj::new(aabb, Arc::new(bsdf))))
@@ -40,29 +40,29 @@ impl SphereScene {
         Vec3::new(x, RADIUS + 0.05, z)
     }
 
-    fn random_color() -> Spectrum {
+    fn random_color<S: Spectrum>() -> S {
         let rand = fastrand::f32() * 1.5;
 
         if rand < 0.25 {
-            Spectrum::red()
+            S::red()
         } else if rand < 0.5 {
-            Spectrum::green()
+            S::green()
         } else if rand < 0.75 {
-            Spectrum::blue()
+            S::blue()
         } else {
-            Spectrum::white()
+            S::white()
         }
     }
 
-    fn random_bsdf(color: Spectrum) -> (bool, BSDF) {
+    fn random_bsdf<S: Spectrum>(color: S) -> (bool, BSDF<S>) {
         let rand = fastrand::f32();
 
-        if color == Spectrum::white() {
+        if color == S::white() {
             if rand < 0.6 {
                 let oren_nayar = LambertianReflection::new(color);
                 (true, BSDF::new(vec![Box::new(oren_nayar)]))
             } else {
-                let oren_nayar = OrenNayar::new(Spectrum::black(), SIGMA);
+                let oren_nayar = OrenNayar::new(S::black(), SIGMA);
                 (false, BSDF::new(vec![Box::new(oren_nayar)]))
             }
         } else {
@@ -71,7 +71,7 @@ impl SphereScene {
         }
     }
 
-    fn emitter() -> Instance {
+    fn emitter<S: Spectrum>() -> Instance<S> {
         let center = Vec3::new(0.0, 50.0, 0.0);
         let sphere = Sphere::new(center, 0.05);
 
@@ -80,11 +80,11 @@ impl SphereScene {
         Emitter(Arc::new(EmitterObj::new(
             sphere,
             Arc::new(bsdf),
-            (Spectrum::red() + Spectrum::green()) * 1000.0,
+            S::from_rgb_illuminant(Srgb::new([1.0, 1.0, 0.0])) * 1000.0,
         )))
     }
 
-    fn create_scene() -> Scene {
+    fn create_scene<S: Spectrum>() -> Scene<S> {
         let mut scene = Scene::default();
 
         for _ in 0..5 {
@@ -128,7 +128,7 @@ impl SphereScene {
 }
 
 impl DemoScene for SphereScene {
-    fn create(width: u32, height: u32) -> (Scene, Camera) {
+    fn create<S: Spectrum>(width: u32, height: u32) -> (Scene<S>, Camera) {
         fastrand::seed(0);
         (Self::create_scene(), Self::create_camera(width, height))
     }
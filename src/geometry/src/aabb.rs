@@ -134,7 +134,13 @@ impl Intersectable for Aabb {
             return None;
         }
 
-        let hit = ray.at(t_min);
+        // the ray starts inside the box
+        let t = if t_min < ray.t_start { t_max } else { t_min };
+        if t > ray.t_end {
+            return None;
+        }
+
+        let hit = ray.at(t);
         let point = hit - self.center();
         let extent = self.size() / 2.0;
         let bias = 1.0 + floats::BIG_EPSILON;
@@ -151,7 +157,7 @@ impl Intersectable for Aabb {
         // }
 
         // approximating epsilon is too small (unlikely) or the given hit was illegal
-        Some(Intersection::new(*ray, t_min, hit, normal))
+        Some(Intersection::new(*ray, t, hit, normal))
     }
 
     fn intersects(&self, ray: &Ray) -> bool {
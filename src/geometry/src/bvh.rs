@@ -260,6 +260,57 @@ impl<T> Bvh<T> where T: Boundable {
         super_node.1
     }
 
+    /// # Summary
+    /// Builds the hierarchy top-down by splitting the objects at the median of their centers along
+    /// the largest extent.
+    /// Much faster to build than `aac` for many objects (e.g. the triangles of a mesh).
+    ///
+    /// # Arguments
+    /// * `objects` - The objects to store
+    ///
+    /// # Results
+    /// * `Arc<Self>` - The hierarchy
+    pub fn median_split(mut objects: Vec<T>) -> Arc<Self> {
+        const MAX_LEAF_SIZE: usize = 4;
+
+        let aabb = objects.iter().fold(Aabb::inverted_infinite(), |acc, o| {
+            acc.outer_join(&o.bounds())
+        });
+
+        if objects.len() <= MAX_LEAF_SIZE {
+            return Arc::new(Self::new(aabb, vec![], objects));
+        }
+
+        let centers = objects.iter().fold(Aabb::inverted_infinite(), |acc, o| {
+            let center = o.bounds().center();
+            acc.outer_join(&Aabb::new(center, center))
+        });
+        let extent = centers.size();
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = objects.len() / 2;
+        objects.select_nth_unstable_by(mid, |a, b| {
+            let a = a.bounds().center();
+            let b = b.bounds().center();
+            match axis {
+                0 => floats::fast_cmp(a.x, b.x),
+                1 => floats::fast_cmp(a.y, b.y),
+                _ => floats::fast_cmp(a.z, b.z),
+            }
+        });
+
+        let right = objects.split_off(mid);
+        let children = vec![Self::median_split(objects), Self::median_split(right)];
+
+        Arc::new(Self::new(aabb, children, vec![]))
+    }
+
     fn build_tree() {}
 
     fn combine_clusters() {}
@@ -0,0 +1,146 @@
+use ultraviolet::Vec3;
+use util::floats;
+
+/// A trait for objects located at a single point in space.
+pub trait Positioned {
+    fn position(&self) -> Vec3;
+}
+
+#[inline(always)]
+fn component(v: &Vec3, axis: u8) -> f32 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// A balanced kd-tree over points, stored implicitly in a vector.
+///
+/// Every subslice stores its splitting item at its median, the items left of the median lie
+/// below and the items right of it above the splitting plane.
+#[derive(Debug)]
+pub struct KdTree<T> {
+    items: Vec<T>,
+    axes: Vec<u8>,
+}
+
+impl<T> KdTree<T>
+where
+    T: Positioned,
+{
+    /// # Summary
+    /// Builds a kd-tree from the given items.
+    ///
+    /// # Arguments
+    /// * `items` - The items to store
+    ///
+    /// # Results
+    /// * `Self` - The kd-tree
+    pub fn new(mut items: Vec<T>) -> Self {
+        let mut axes = vec![0; items.len()];
+        Self::build(&mut items, &mut axes);
+
+        Self { items, axes }
+    }
+
+    fn build(items: &mut [T], axes: &mut [u8]) {
+        if items.len() <= 1 {
+            return;
+        }
+
+        let (min, max) = items.iter().fold(
+            (
+                Vec3::broadcast(f32::INFINITY),
+                Vec3::broadcast(f32::NEG_INFINITY),
+            ),
+            |(min, max), item| {
+                let p = item.position();
+                (min.min_by_component(p), max.max_by_component(p))
+            },
+        );
+
+        // split along the largest extent
+        let extent = max - min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |a, b| {
+            floats::fast_cmp(
+                component(&a.position(), axis),
+                component(&b.position(), axis),
+            )
+        });
+        axes[mid] = axis;
+
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let (left_axes, right_axes) = axes.split_at_mut(mid);
+        Self::build(left_items, left_axes);
+        Self::build(&mut right_items[1..], &mut right_axes[1..]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// # Summary
+    /// Calls the given function for every item within the radius around the point.
+    ///
+    /// # Arguments
+    /// * `point` - The center of the search
+    /// * `radius` - The search radius
+    /// * `f` - The function to call
+    pub fn for_each_in_radius<F>(&self, point: &Vec3, radius: f32, mut f: F)
+    where
+        F: FnMut(&T),
+    {
+        Self::query(&self.items, &self.axes, point, radius * radius, &mut f);
+    }
+
+    fn query<F>(items: &[T], axes: &[u8], point: &Vec3, radius_sq: f32, f: &mut F)
+    where
+        F: FnMut(&T),
+    {
+        if items.is_empty() {
+            return;
+        }
+
+        let mid = items.len() / 2;
+        let item = &items[mid];
+        let position = item.position();
+
+        if (position - *point).mag_sq() <= radius_sq {
+            f(item);
+        }
+
+        let axis = axes[mid];
+        let diff = component(point, axis) - component(&position, axis);
+
+        let (near, far) = if diff <= 0.0 {
+            (
+                (&items[..mid], &axes[..mid]),
+                (&items[mid + 1..], &axes[mid + 1..]),
+            )
+        } else {
+            (
+                (&items[mid + 1..], &axes[mid + 1..]),
+                (&items[..mid], &axes[..mid]),
+            )
+        };
+
+        Self::query(near.0, near.1, point, radius_sq, f);
+        if diff * diff <= radius_sq {
+            Self::query(far.0, far.1, point, radius_sq, f);
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub mod aabb;
 pub mod bvh;
 pub mod capsule;
 pub mod cylinder;
+pub mod kd_tree;
 pub mod lens;
 pub mod mesh;
 pub mod point;
@@ -36,7 +37,13 @@ impl Intersection {
     }
 
     pub fn create_ray(&self, dir: Vec3) -> Ray {
-        let origin = self.point + self.normal * floats::BIG_EPSILON;
+        // offset into the hemisphere of the direction (e.g. transmission)
+        let offset = if dir.dot(self.normal) < 0.0 {
+            -floats::BIG_EPSILON
+        } else {
+            floats::BIG_EPSILON
+        };
+        let origin = self.point + self.normal * offset;
 
         let t_start = floats::BIG_EPSILON;
         let t_end = f32::INFINITY;
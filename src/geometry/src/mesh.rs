@@ -38,8 +38,9 @@ impl Intersectable for Triangle {
         let ac = *self.c - *self.a;
         let h = ray.direction.cross(ac);
 
+        // both sides of the triangle are intersected
         let det = ab.dot(h);
-        if det < floats::EPSILON {
+        if det.abs() < floats::EPSILON {
             return None;
         }
 
@@ -64,13 +65,9 @@ impl Intersectable for Triangle {
 
         let ab = *self.b - *self.a;
         let ac = *self.c - *self.a;
-        let mut normal = ac.cross(ab).normalized();
-
-        // Choose the normal's orientation to be opposite the ray's
-        // (in case the ray intersects the inside surface)
-        if normal.dot(ray.direction) > 0.0 {
-            normal = -normal;
-        }
+        // counter-clockwise winding faces outside, such that media inside closed meshes are
+        // entered and left consistently
+        let normal = ab.cross(ac).normalized();
 
         Some(Intersection::new(*ray, t, point, normal))
     }
@@ -82,7 +79,7 @@ impl Intersectable for Triangle {
         let h = ray.direction.cross(ac);
 
         let det = ab.dot(h);
-        if det < floats::EPSILON {
+        if det.abs() < floats::EPSILON {
             return false;
         }
 
@@ -141,7 +138,7 @@ impl Mesh {
             Triangle::new(a, b, c)
         }).collect();
         // let clone = triangles.clone();
-        let bvh = Bvh::aac_vec(clone);
+        let bvh = Bvh::median_split(clone);
 
         Self {
             vertices,
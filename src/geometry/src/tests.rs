@@ -29,7 +29,7 @@ mod aabb {
     use crate::floats;
     use crate::aabb::*;
     use crate::ray::Ray;
-    use crate::{Container, Geometry};
+    use crate::{Boundable, Container, Intersectable};
     use ultraviolet::Vec3;
 
     #[test]
@@ -186,7 +186,7 @@ mod aabb {
         let max = Vec3::one();
         let aabb = Aabb::new(min, max);
 
-        assert!(aabb.contains(Vec3::one() / 2.0));
+        assert!(aabb.contains(&(Vec3::one() / 2.0)));
     }
 
     #[test]
@@ -195,8 +195,8 @@ mod aabb {
         let max = Vec3::one();
         let aabb = Aabb::new(min, max);
 
-        assert!(aabb.contains(min));
-        assert!(aabb.contains(max));
+        assert!(aabb.contains(&min));
+        assert!(aabb.contains(&max));
     }
 
     #[test]
@@ -205,7 +205,7 @@ mod aabb {
         let max = Vec3::one();
         let aabb = Aabb::new(min, max);
 
-        assert!(!aabb.contains(Vec3::one() * 2.0));
+        assert!(!aabb.contains(&(Vec3::one() * 2.0)));
     }
 
     #[test]
@@ -317,7 +317,7 @@ mod aabb {
 mod point {
     use crate::point::Point;
     use crate::ray::Ray;
-    use crate::Geometry;
+    use crate::{Boundable, Intersectable};
     use ultraviolet::Vec3;
 
     #[test]
@@ -363,7 +363,7 @@ mod sphere {
     use crate::aabb::Aabb;
     use crate::ray::Ray;
     use crate::sphere::Sphere;
-    use crate::{Container, Geometry};
+    use crate::{Boundable, Container, Intersectable};
     use ultraviolet::Vec3;
 
     #[test]
@@ -397,7 +397,7 @@ mod sphere {
         let sphere = Sphere::default();
         let point = Vec3::zero();
 
-        assert!(sphere.contains(point));
+        assert!(sphere.contains(&point));
     }
 
     #[test]
@@ -405,7 +405,7 @@ mod sphere {
         let sphere = Sphere::default();
         let point = Vec3::one();
 
-        assert!(!sphere.contains(point));
+        assert!(!sphere.contains(&point));
     }
 
     #[test]
@@ -413,7 +413,7 @@ mod sphere {
         let sphere = Sphere::default();
         let point = Vec3::unit_x();
 
-        assert!(!sphere.contains(point));
+        assert!(!sphere.contains(&point));
     }
 
     #[test]
@@ -478,3 +478,66 @@ mod sphere {
         assert!(intersection.is_none());
     }
 }
+
+#[cfg(test)]
+mod kd_tree {
+    use crate::kd_tree::*;
+    use ultraviolet::Vec3;
+
+    impl Positioned for Vec3 {
+        fn position(&self) -> Vec3 {
+            *self
+        }
+    }
+
+    fn grid() -> Vec<Vec3> {
+        let mut points = Vec::new();
+        for x in 0..10 {
+            for y in 0..10 {
+                for z in 0..10 {
+                    points.push(Vec3::new(x as f32, y as f32, z as f32));
+                }
+            }
+        }
+
+        points
+    }
+
+    #[test]
+    fn new() {
+        let tree = KdTree::new(grid());
+
+        assert_eq!(1000, tree.len());
+        assert!(!tree.is_empty());
+    }
+
+    #[test]
+    fn for_each_in_radius() {
+        let points = grid();
+        let tree = KdTree::new(points.clone());
+
+        let center = Vec3::new(4.2, 5.1, 3.7);
+        let radius = 2.5;
+
+        let mut found = Vec::new();
+        tree.for_each_in_radius(&center, radius, |p| found.push(*p));
+
+        let expected = points
+            .iter()
+            .filter(|p| (**p - center).mag() <= radius)
+            .count();
+
+        assert_eq!(expected, found.len());
+        assert!(found.iter().all(|p| (*p - center).mag() <= radius));
+    }
+
+    #[test]
+    fn empty() {
+        let tree: KdTree<Vec3> = KdTree::new(Vec::new());
+
+        let mut found = 0;
+        tree.for_each_in_radius(&Vec3::zero(), 1.0, |_| found += 1);
+
+        assert_eq!(0, found);
+    }
+}
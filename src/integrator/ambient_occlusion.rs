@@ -0,0 +1,72 @@
+use crate::integrator::Integrator;
+use crate::mc::cos_sample_hemisphere;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use geometry::CoordinateSystem;
+use std::sync::Arc;
+
+/// An integrator returning the ambient occlusion of the first intersection, i.e. the fraction of
+/// cosine-weighted rays not occluded within a maximum distance.
+pub struct AmbientOcclusion {
+    pub max_distance: f32,
+    pub samples: u32,
+}
+
+impl AmbientOcclusion {
+    /// # Summary
+    /// Creates a new ambient occlusion integrator.
+    ///
+    /// # Arguments
+    /// * `max_distance` - The maximum distance of an occluder
+    /// * `samples` - The number of occlusion rays per intersection, clamped to at least 1
+    ///
+    /// # Results
+    /// * `Self` - The integrator
+    pub fn new(max_distance: f32, samples: u32) -> Self {
+        Self {
+            max_distance,
+            samples: samples.max(1),
+        }
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for AmbientOcclusion {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        if let Some(si) = scene.intersect(primary_ray) {
+            self.illumination(scene, &si, sampler, 0)
+        } else {
+            S::black()
+        }
+    }
+
+    fn illumination(
+        &self,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        sampler: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        // the hemisphere facing the incoming ray
+        let mut normal = intersection.info.normal;
+        if normal.dot(intersection.info.ray.direction) > 0.0 {
+            normal = -normal;
+        }
+        let frame = CoordinateSystem::from(&normal);
+
+        let visible = (0..self.samples)
+            .filter(|_| {
+                let local = cos_sample_hemisphere(&sampler.get_2d());
+                let direction = local.x * frame.e1 + local.y * frame.e2 + local.z * frame.e3;
+
+                let mut ray = intersection.info.create_ray(direction);
+                ray.t_end = self.max_distance;
+
+                !scene.is_occluded(&ray)
+            })
+            .count();
+
+        S::new_const(visible as f32 / self.samples as f32)
+    }
+}
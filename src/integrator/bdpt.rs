@@ -0,0 +1,718 @@
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::render::camera::Camera;
+use crate::render::film::Film;
+use crate::render::objects::emitter::{Emitter, OcclusionTester};
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::Arc;
+use ultraviolet::{Vec2, Vec3};
+
+#[derive(Clone)]
+enum VertexType<S: Spectrum> {
+    Camera,
+    Light(Arc<dyn Emitter<S>>),
+    Surface(SceneIntersection<S>),
+}
+
+/// A vertex of a camera or light subpath.
+#[derive(Clone)]
+struct Vertex<S: Spectrum> {
+    typ: VertexType<S>,
+    point: Vec3,
+    /// The surface normal (zero for the camera and point lights)
+    normal: Vec3,
+    /// The direction towards the previous vertex of the subpath
+    outgoing: Vec3,
+    beta: S,
+    delta: bool,
+    pdf_fwd: f32,
+    pdf_rev: f32,
+}
+
+impl<S: Spectrum> Vertex<S> {
+    fn new(typ: VertexType<S>, point: Vec3, normal: Vec3, outgoing: Vec3, beta: S) -> Self {
+        Self {
+            typ,
+            point,
+            normal,
+            outgoing,
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }
+    }
+
+    fn camera(camera: &Camera, beta: S) -> Self {
+        Self::new(
+            VertexType::Camera,
+            camera.position,
+            Vec3::zero(),
+            Vec3::zero(),
+            beta,
+        )
+    }
+
+    fn light(light: Arc<dyn Emitter<S>>, point: Vec3, normal: Vec3, beta: S) -> Self {
+        Self::new(VertexType::Light(light), point, normal, Vec3::zero(), beta)
+    }
+
+    fn surface(intersection: SceneIntersection<S>, beta: S, pdf: f32, prev: &Vertex<S>) -> Self {
+        let point = intersection.info.point;
+        let normal = intersection.info.normal;
+        let outgoing = -intersection.info.ray.direction;
+
+        let mut vertex = Self::new(
+            VertexType::Surface(intersection),
+            point,
+            normal,
+            outgoing,
+            beta,
+        );
+        vertex.pdf_fwd = prev.convert_density(pdf, &vertex);
+
+        vertex
+    }
+
+    #[inline]
+    fn is_on_surface(&self) -> bool {
+        self.normal != Vec3::zero()
+    }
+
+    fn emitter(&self) -> Option<&Arc<dyn Emitter<S>>> {
+        match &self.typ {
+            VertexType::Light(e) => Some(e),
+            VertexType::Surface(si) => match &si.obj {
+                Instance::Emitter(e) => Some(e),
+                Instance::Receiver(_) => None,
+            },
+            VertexType::Camera => None,
+        }
+    }
+
+    #[inline]
+    fn is_light(&self) -> bool {
+        self.emitter().is_some()
+    }
+
+    #[inline]
+    fn is_delta_light(&self) -> bool {
+        matches!(&self.typ, VertexType::Light(e) if e.is_delta())
+    }
+
+    fn is_connectible(&self) -> bool {
+        match &self.typ {
+            VertexType::Surface(si) => si.obj.bsdf().is_type(BxDFType::ALL & !BxDFType::SPECULAR),
+            _ => true,
+        }
+    }
+
+    fn spawn_ray(&self, direction: Vec3) -> Ray {
+        match &self.typ {
+            VertexType::Surface(si) => si.info.create_ray(direction),
+            _ => Ray::new(self.point, direction),
+        }
+    }
+
+    /// Evaluates the BSDF of this vertex for the path continuing to the given vertex.
+    fn f(&self, next: &Vertex<S>) -> S {
+        match &self.typ {
+            VertexType::Surface(si) => {
+                let incident = (next.point - self.point).normalized();
+
+                si.obj
+                    .bsdf()
+                    .evaluate(&self.normal, &incident, &self.outgoing, BxDFType::ALL)
+            }
+            _ => S::black(),
+        }
+    }
+
+    /// Returns the radiance emitted from this vertex towards the given vertex.
+    fn le(&self, to: &Vertex<S>) -> S {
+        match self.emitter() {
+            Some(e) => e.radiance(&(to.point - self.point).normalized(), &self.normal),
+            None => S::black(),
+        }
+    }
+
+    /// Converts the solid angle density of sampling `next` from this vertex to an area density.
+    fn convert_density(&self, pdf: f32, next: &Vertex<S>) -> f32 {
+        let w = next.point - self.point;
+        let dist_sq = w.mag_sq();
+        if dist_sq == 0.0 {
+            return 0.0;
+        }
+
+        let mut pdf = pdf / dist_sq;
+        if next.is_on_surface() {
+            pdf *= next.normal.dot(w / dist_sq.sqrt()).abs();
+        }
+
+        pdf
+    }
+
+    /// # Summary
+    /// Computes the area density of sampling `next` from this vertex.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera of the scene
+    /// * `prev` - The previous vertex (if any)
+    /// * `next` - The next vertex
+    ///
+    /// # Results
+    /// * `f32` - The pdf (area measure)
+    fn pdf(&self, camera: &Camera, prev: Option<&Vertex<S>>, next: &Vertex<S>) -> f32 {
+        let incident = (next.point - self.point).normalized();
+
+        let pdf = match &self.typ {
+            VertexType::Light(_) => return self.pdf_light(next),
+            VertexType::Camera => camera.pdf_direction(&incident),
+            VertexType::Surface(si) => {
+                let outgoing = prev.map_or(self.outgoing, |p| (p.point - self.point).normalized());
+
+                si.obj
+                    .bsdf()
+                    .pdf(&self.normal, &incident, &outgoing, BxDFType::ALL)
+            }
+        };
+
+        self.convert_density(pdf, next)
+    }
+
+    /// Computes the area density of emitting light from this vertex towards `next`.
+    fn pdf_light(&self, next: &Vertex<S>) -> f32 {
+        match self.emitter() {
+            Some(e) => {
+                let direction = (next.point - self.point).normalized();
+                let (_, pdf_dir) = e.pdf_emission(&direction, &self.normal);
+
+                self.convert_density(pdf_dir, next)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Computes the area density of choosing this vertex as the origin of a light subpath.
+    fn pdf_light_origin(&self, scene: &Scene<S>, next: &Vertex<S>) -> f32 {
+        match self.emitter() {
+            Some(e) => {
+                let direction = (next.point - self.point).normalized();
+                let (pdf_pos, _) = e.pdf_emission(&direction, &self.normal);
+
+                pdf_pos / scene.lights.len() as f32
+            }
+            None => 0.0,
+        }
+    }
+}
+
+/// The pdfs of a vertex required for the multiple importance sampling weight.
+#[derive(Copy, Clone)]
+struct MisVertex {
+    pdf_fwd: f32,
+    pdf_rev: f32,
+    delta: bool,
+}
+
+impl<S: Spectrum> From<&Vertex<S>> for MisVertex {
+    fn from(v: &Vertex<S>) -> Self {
+        Self {
+            pdf_fwd: v.pdf_fwd,
+            pdf_rev: v.pdf_rev,
+            delta: v.delta,
+        }
+    }
+}
+
+/// # Summary
+/// Computes the geometric term between two vertices, including their visibility.
+fn g<S: Spectrum>(scene: &Scene<S>, a: &Vertex<S>, b: &Vertex<S>) -> f32 {
+    let d = a.point - b.point;
+    let dist_sq = d.mag_sq();
+    if dist_sq == 0.0 {
+        return 0.0;
+    }
+
+    let d = d / dist_sq.sqrt();
+    let mut g = 1.0 / dist_sq;
+    if a.is_on_surface() {
+        g *= a.normal.dot(d).abs();
+    }
+    if b.is_on_surface() {
+        g *= b.normal.dot(d).abs();
+    }
+
+    if OcclusionTester::between(a.point, b.point).is_occluded(scene) {
+        0.0
+    } else {
+        g
+    }
+}
+
+/// A bidirectional path tracer.
+///
+/// For every camera sample a camera and a light subpath are traced and connected with all
+/// strategies, weighted by multiple importance sampling (balance heuristic).
+/// Strategies connecting a light subpath directly to the camera are splatted to the film.
+pub struct Bdpt<S: Spectrum> {
+    pub max_depth: u32,
+    camera: Arc<Camera>,
+    film: Arc<Film<S>>,
+}
+
+impl<S: Spectrum> Bdpt<S> {
+    pub fn new(max_depth: u32, camera: Arc<Camera>, film: Arc<Film<S>>) -> Self {
+        Self {
+            max_depth,
+            camera,
+            film,
+        }
+    }
+
+    /// # Summary
+    /// Extends the given path by randomly sampling the BSDFs of the intersections.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene to trace
+    /// * `ray` - The ray leaving the last vertex of the path
+    /// * `sampler` - The sampler to use
+    /// * `beta` - The throughput of the path
+    /// * `pdf` - The pdf of the ray direction (solid angle measure)
+    /// * `max_vertices` - The maximum number of vertices to add
+    /// * `path` - The path to extend
+    #[allow(clippy::too_many_arguments)]
+    fn random_walk(
+        &self,
+        scene: &Scene<S>,
+        mut ray: Ray,
+        sampler: &Arc<dyn Sampler>,
+        mut beta: S,
+        mut pdf: f32,
+        max_vertices: u32,
+        path: &mut Vec<Vertex<S>>,
+    ) {
+        for bounce in 0..max_vertices {
+            let si = match scene.intersect(&ray) {
+                Some(si) => si,
+                None => break,
+            };
+
+            let prev = path.len() - 1;
+            let vertex = Vertex::surface(si, beta, pdf, &path[prev]);
+            path.push(vertex);
+
+            if bounce + 1 == max_vertices {
+                break;
+            }
+
+            let current = prev + 1;
+            let vertex = &path[current];
+            let (normal, outgoing) = (vertex.normal, vertex.outgoing);
+            let bsdf = match &vertex.typ {
+                VertexType::Surface(si) => si.obj.bsdf().clone(),
+                _ => unreachable!(),
+            };
+
+            let sample = sampler.get_sample();
+            let bxdf_sample = match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sample) {
+                Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                _ => break,
+            };
+
+            let incident = bxdf_sample.incident;
+            let (dot, pdf_rev) = if bxdf_sample.typ.is_specular() {
+                path[current].delta = true;
+                pdf = 0.0;
+                (1.0, 0.0)
+            } else {
+                // stochastic BxDFs only estimate their pdf, which is used for the MIS weights
+                pdf = if bsdf.is_stochastic() {
+                    bsdf.pdf(&normal, &incident, &outgoing, BxDFType::ALL)
+                } else {
+                    bxdf_sample.pdf
+                };
+                let pdf_rev = bsdf.pdf(&normal, &outgoing, &incident, BxDFType::ALL);
+                (incident.dot(normal).abs(), pdf_rev)
+            };
+
+            beta *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
+
+            let pdf_rev = path[current].convert_density(pdf_rev, &path[prev]);
+            path[prev].pdf_rev = pdf_rev;
+
+            ray = path[current].spawn_ray(incident);
+        }
+    }
+
+    fn camera_subpath(
+        &self,
+        scene: &Scene<S>,
+        ray: &Ray,
+        sampler: &Arc<dyn Sampler>,
+    ) -> Vec<Vertex<S>> {
+        let mut path = Vec::with_capacity(self.max_depth as usize + 2);
+
+        let beta = S::new_const(1.0);
+        path.push(Vertex::camera(&self.camera, beta));
+
+        let pdf = self.camera.pdf_direction(&ray.direction);
+        self.random_walk(
+            scene,
+            *ray,
+            sampler,
+            beta,
+            pdf,
+            self.max_depth + 1,
+            &mut path,
+        );
+
+        path
+    }
+
+    fn light_subpath(&self, scene: &Scene<S>, sampler: &Arc<dyn Sampler>) -> Vec<Vertex<S>> {
+        let mut path = Vec::with_capacity(self.max_depth as usize + 1);
+        if scene.lights.is_empty() {
+            return path;
+        }
+
+        let num_lights = scene.lights.len();
+        let light_pdf = 1.0 / num_lights as f32;
+        let index = usize::min(
+            (sampler.get_1d() * num_lights as f32) as usize,
+            num_lights - 1,
+        );
+        let light = &scene.lights[index];
+
+        let sample = light.sample_emission(&sampler.get_2d(), &sampler.get_2d());
+        if sample.pdf_pos == 0.0 || sample.pdf_dir == 0.0 || sample.radiance.is_black() {
+            return path;
+        }
+
+        let (normal, cos) = if light.is_delta() {
+            (Vec3::zero(), 1.0)
+        } else {
+            (sample.normal, sample.normal.dot(sample.ray.direction).abs())
+        };
+
+        let mut vertex = Vertex::light(light.clone(), sample.ray.origin, normal, sample.radiance);
+        vertex.pdf_fwd = sample.pdf_pos * light_pdf;
+        path.push(vertex);
+
+        let beta = sample.radiance * (cos / (light_pdf * sample.pdf_pos * sample.pdf_dir));
+        self.random_walk(
+            scene,
+            sample.ray,
+            sampler,
+            beta,
+            sample.pdf_dir,
+            self.max_depth,
+            &mut path,
+        );
+
+        path
+    }
+
+    /// # Summary
+    /// Connects the first `s` vertices of the light subpath with the first `t` vertices of the
+    /// camera subpath.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `light_path` - The light subpath
+    /// * `camera_path` - The camera subpath
+    /// * `s` - The number of light vertices
+    /// * `t` - The number of camera vertices
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The weighted contribution
+    /// * `Option<Vec2>` - The raster position to splat the contribution to (for `t = 1`)
+    fn connect(
+        &self,
+        scene: &Scene<S>,
+        light_path: &[Vertex<S>],
+        camera_path: &[Vertex<S>],
+        s: usize,
+        t: usize,
+        sampler: &Arc<dyn Sampler>,
+    ) -> (S, Option<Vec2>) {
+        let mut l = S::black();
+        let mut sampled = None;
+        let mut raster = None;
+
+        if s == 0 {
+            // the camera subpath hit an emitter
+            let pt = &camera_path[t - 1];
+            if pt.is_light() {
+                l = pt.le(&camera_path[t - 2]) * pt.beta;
+            }
+        } else if t == 1 {
+            // connect the light subpath to the camera
+            let qs = &light_path[s - 1];
+            if qs.is_connectible() {
+                if let Some(cs) = self.camera.sample_importance(&qs.point) {
+                    if cs.pdf > 0.0 && cs.importance > 0.0 {
+                        let beta = S::new_const(cs.importance / cs.pdf);
+                        let vertex = Vertex::camera(&self.camera, beta);
+
+                        l = qs.beta * qs.f(&vertex) * vertex.beta;
+                        if qs.is_on_surface() {
+                            l *= cs.incident.dot(qs.normal).abs();
+                        }
+                        if !l.is_black() && cs.occlusion_tester.is_occluded(scene) {
+                            l = S::black();
+                        }
+
+                        raster = Some(cs.raster);
+                        sampled = Some(vertex);
+                    }
+                }
+            }
+        } else if s == 1 {
+            // sample a point on a light
+            let pt = &camera_path[t - 1];
+            if let VertexType::Surface(si) = &pt.typ {
+                if pt.is_connectible() {
+                    let num_lights = scene.lights.len();
+                    let light_pdf = 1.0 / num_lights as f32;
+                    let index = usize::min(
+                        (sampler.get_1d() * num_lights as f32) as usize,
+                        num_lights - 1,
+                    );
+                    let light = &scene.lights[index];
+
+                    let sample = light.sample(si, &sampler.get_2d());
+                    if sample.pdf > 0.0 && !sample.radiance.is_black() {
+                        // point lights have no falloff in their sample
+                        let (normal, falloff) = if light.is_delta() {
+                            (Vec3::zero(), (sample.point - pt.point).mag_sq())
+                        } else {
+                            (sample.normal, 1.0)
+                        };
+
+                        let beta = sample.radiance / (sample.pdf * light_pdf * falloff);
+                        let mut vertex = Vertex::light(light.clone(), sample.point, normal, beta);
+                        vertex.pdf_fwd = vertex.pdf_light_origin(scene, pt);
+
+                        l = pt.beta * pt.f(&vertex) * vertex.beta;
+                        if pt.is_on_surface() {
+                            l *= sample.incident.dot(pt.normal).abs();
+                        }
+                        if !l.is_black() && sample.occlusion_tester.is_occluded(scene) {
+                            l = S::black();
+                        }
+
+                        sampled = Some(vertex);
+                    }
+                }
+            }
+        } else {
+            let qs = &light_path[s - 1];
+            let pt = &camera_path[t - 1];
+            if qs.is_connectible() && pt.is_connectible() {
+                l = qs.beta * qs.f(pt) * pt.f(qs) * pt.beta;
+                if !l.is_black() {
+                    l *= g(scene, qs, pt);
+                }
+            }
+        }
+
+        if l.is_black() {
+            return (l, raster);
+        }
+
+        let weight = self.mis_weight(scene, light_path, camera_path, sampled.as_ref(), s, t);
+
+        (l * weight, raster)
+    }
+
+    /// # Summary
+    /// Computes the multiple importance sampling weight of the strategy `(s, t)` with the balance
+    /// heuristic.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `light_path` - The light subpath
+    /// * `camera_path` - The camera subpath
+    /// * `sampled` - The newly sampled vertex for `s = 1` or `t = 1`
+    /// * `s` - The number of light vertices
+    /// * `t` - The number of camera vertices
+    ///
+    /// # Results
+    /// * `f32` - The weight
+    fn mis_weight(
+        &self,
+        scene: &Scene<S>,
+        light_path: &[Vertex<S>],
+        camera_path: &[Vertex<S>],
+        sampled: Option<&Vertex<S>>,
+        s: usize,
+        t: usize,
+    ) -> f32 {
+        if s + t == 2 {
+            return 1.0;
+        }
+
+        let remap0 = |f: f32| if f != 0.0 { f } else { 1.0 };
+
+        let pt = match (t, sampled) {
+            (1, Some(v)) => v,
+            _ => &camera_path[t - 1],
+        };
+        let qs = match (s, sampled) {
+            (0, _) => None,
+            (1, Some(v)) => Some(v),
+            _ => Some(&light_path[s - 1]),
+        };
+        let pt_minus = if t > 1 {
+            Some(&camera_path[t - 2])
+        } else {
+            None
+        };
+        let qs_minus = if s > 1 {
+            Some(&light_path[s - 2])
+        } else {
+            None
+        };
+
+        // the reverse pdfs of the vertices adjacent to the connection
+        let pt_rev = match (qs, pt_minus) {
+            (Some(qs), _) => qs.pdf(&self.camera, qs_minus, pt),
+            (None, Some(pt_minus)) => pt.pdf_light_origin(scene, pt_minus),
+            (None, None) => 0.0,
+        };
+        let pt_minus_rev = pt_minus.map(|pt_minus| match qs {
+            Some(qs) => pt.pdf(&self.camera, Some(qs), pt_minus),
+            None => pt.pdf_light(pt_minus),
+        });
+        let qs_rev = qs.map(|qs| pt.pdf(&self.camera, pt_minus, qs));
+        let qs_minus_rev = match (qs, qs_minus) {
+            (Some(qs), Some(qs_minus)) => Some(qs.pdf(&self.camera, Some(pt), qs_minus)),
+            _ => None,
+        };
+
+        let mut camera: Vec<MisVertex> = camera_path[..t].iter().map(MisVertex::from).collect();
+        camera[t - 1] = MisVertex {
+            pdf_fwd: pt.pdf_fwd,
+            pdf_rev: pt_rev,
+            delta: false,
+        };
+        if let Some(pdf_rev) = pt_minus_rev {
+            camera[t - 2].pdf_rev = pdf_rev;
+        }
+
+        let mut light: Vec<MisVertex> = light_path[..s].iter().map(MisVertex::from).collect();
+        if let (Some(qs), Some(pdf_rev)) = (qs, qs_rev) {
+            light[s - 1] = MisVertex {
+                pdf_fwd: qs.pdf_fwd,
+                pdf_rev,
+                delta: false,
+            };
+        }
+        if let Some(pdf_rev) = qs_minus_rev {
+            light[s - 2].pdf_rev = pdf_rev;
+        }
+
+        let origin_is_delta_light = match (s, qs) {
+            (1, Some(qs)) => qs.is_delta_light(),
+            _ => matches!(light_path.first(), Some(v) if v.is_delta_light()),
+        };
+
+        let mut sum = 0.0;
+
+        let mut ri = 1.0;
+        for i in (1..t).rev() {
+            ri *= remap0(camera[i].pdf_rev) / remap0(camera[i].pdf_fwd);
+            if !camera[i].delta && !camera[i - 1].delta {
+                sum += ri;
+            }
+        }
+
+        let mut ri = 1.0;
+        for i in (0..s).rev() {
+            ri *= remap0(light[i].pdf_rev) / remap0(light[i].pdf_fwd);
+            let prev_is_delta = if i > 0 {
+                light[i - 1].delta
+            } else {
+                origin_is_delta_light
+            };
+            if !light[i].delta && !prev_is_delta {
+                sum += ri;
+            }
+        }
+
+        1.0 / (1.0 + sum)
+    }
+
+    /// # Summary
+    /// Traces a camera and a light subpath and connects them with all strategies.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `primary_ray` - The ray leaving the camera
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The radiance along the primary ray
+    /// * `Vec<(Vec2, S)>` - The contributions to splat to other raster positions
+    pub fn trace(
+        &self,
+        scene: &Scene<S>,
+        primary_ray: &Ray,
+        sampler: &Arc<dyn Sampler>,
+    ) -> (S, Vec<(Vec2, S)>) {
+        let camera_path = self.camera_subpath(scene, primary_ray, sampler);
+        let light_path = self.light_subpath(scene, sampler);
+
+        let mut radiance = S::black();
+        let mut splats = Vec::new();
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                let depth = s + t;
+                if (s == 1 && t == 1) || depth < 2 || depth - 2 > self.max_depth as usize {
+                    continue;
+                }
+
+                let (l, raster) = self.connect(scene, &light_path, &camera_path, s, t, sampler);
+                if t == 1 {
+                    if let Some(raster) = raster {
+                        if !l.is_black() {
+                            splats.push((raster, l));
+                        }
+                    }
+                } else {
+                    radiance += l;
+                }
+            }
+        }
+
+        (radiance, splats)
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for Bdpt<S> {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        let (radiance, splats) = self.trace(scene, primary_ray, &sampler);
+        splats
+            .iter()
+            .for_each(|(raster, l)| self.film.splat(raster, *l));
+
+        radiance
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
@@ -2,32 +2,32 @@ use crate::integrator::Integrator;
 use crate::render::scene::{Scene, SceneIntersection};
 use crate::sampler::Sampler;
 use crate::Spectrum;
-use color::Color;
+use color::Srgb;
 use geometry::ray::Ray;
 use std::sync::Arc;
 use ultraviolet::Vec3;
 
 pub struct DebugNormals;
 
-impl Integrator for DebugNormals {
+impl<S: Spectrum> Integrator<S> for DebugNormals {
     #[inline(always)]
-    fn integrate(&self, scene: &Scene, primary_ray: &Ray, _: Arc<dyn Sampler>) -> Spectrum {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, _: Arc<dyn Sampler>) -> S {
         if let Some(si) = scene.intersect(primary_ray) {
             let color = (si.info.normal + Vec3::one()) / 2.0;
 
-            color.into()
+            S::from_rgb(Srgb::from(color))
         } else {
-            Spectrum::black()
+            S::black()
         }
     }
 
     fn illumination(
         &self,
-        _: &Scene,
-        _: &SceneIntersection,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
         _: Arc<dyn Sampler>,
         _: u32,
-    ) -> Spectrum {
+    ) -> S {
         unreachable!()
     }
 }
@@ -0,0 +1,303 @@
+use crate::bxdf::bsdf::BSDF;
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::mc::{power_heuristic, Distribution1D};
+use crate::render::objects::emitter::Emitter;
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::Arc;
+
+/// The strategy of choosing the lights to sample at an intersection.
+#[derive(Debug, Copy, Clone)]
+pub enum LightStrategy {
+    /// Samples every light
+    All,
+    /// Samples one light chosen uniformly
+    Uniform,
+    /// Samples one light chosen proportional to its power
+    Power,
+}
+
+/// A direct lighting integrator.
+///
+/// At every intersection the lights chosen by the strategy are sampled, combined with sampling
+/// the BSDF by multiple importance sampling (power heuristic).
+/// Specular surfaces are followed recursively like in the `Whitted` integrator.
+pub struct DirectLighting {
+    pub max_depth: u32,
+    pub strategy: LightStrategy,
+    /// The number of samples per light (`All`) or of chosen lights (`Uniform`, `Power`)
+    pub light_samples: usize,
+    light_distribution: Distribution1D,
+}
+
+impl DirectLighting {
+    /// # Summary
+    /// Creates a new direct lighting integrator.
+    ///
+    /// # Arguments
+    /// * `max_depth` - The maximum recursion depth of specular bounces
+    /// * `strategy` - The light sampling strategy
+    /// * `light_samples` - The number of light samples per intersection
+    /// * `scene` - The scene to build the light distribution of
+    ///
+    /// # Results
+    /// * `Self` - The integrator
+    pub fn new<S: Spectrum>(
+        max_depth: u32,
+        strategy: LightStrategy,
+        light_samples: usize,
+        scene: &Scene<S>,
+    ) -> Self {
+        let powers: Vec<f32> = scene
+            .lights
+            .iter()
+            .map(|light| light.power().luminance())
+            .collect();
+
+        Self {
+            max_depth,
+            strategy,
+            light_samples: light_samples.max(1),
+            light_distribution: Distribution1D::new(&powers),
+        }
+    }
+
+    /// # Summary
+    /// Estimates the radiance arriving from the given light and reflected towards `outgoing`.
+    /// Non-delta lights additionally sample the BSDF, both samples are weighted by the power
+    /// heuristic.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `intersection` - The intersection to illuminate
+    /// * `light` - The light to sample
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The reflected radiance
+    fn estimate_direct<S: Spectrum>(
+        &self,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        light: &Arc<dyn Emitter<S>>,
+        sampler: &Arc<dyn Sampler>,
+    ) -> S {
+        let bsdf = intersection.obj.bsdf();
+        let normal = intersection.info.normal;
+        let outgoing = -intersection.info.ray.direction;
+        let types = BxDFType::ALL & !BxDFType::SPECULAR;
+
+        let mut illumination = S::black();
+
+        // sample the light
+        let emitter_sample = light.sample(intersection, &sampler.get_2d());
+        if emitter_sample.pdf > 0.0 && !emitter_sample.radiance.is_black() {
+            let incident = emitter_sample.incident;
+            let c = bsdf.evaluate(&normal, &incident, &outgoing, types);
+
+            if !c.is_black() && !emitter_sample.occlusion_tester.is_occluded(scene) {
+                let cos = incident.dot(normal).abs();
+
+                if light.is_delta() {
+                    // point lights have no falloff in their sample
+                    let dist_sq = (emitter_sample.point - intersection.info.point).mag_sq();
+                    illumination += c * emitter_sample.radiance * (cos / dist_sq);
+                } else {
+                    let bsdf_pdf = bsdf.pdf(&normal, &incident, &outgoing, types);
+                    let weight = power_heuristic(1, emitter_sample.pdf, 1, bsdf_pdf);
+
+                    illumination +=
+                        c * emitter_sample.radiance * (cos * weight / emitter_sample.pdf);
+                }
+            }
+        }
+
+        // sample the BSDF
+        if !light.is_delta() {
+            illumination += Self::sample_bsdf(scene, intersection, light, sampler, bsdf, types);
+        }
+
+        illumination
+    }
+
+    /// # Summary
+    /// Samples the BSDF and weights the radiance of the given light, if it is hit.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `intersection` - The intersection to illuminate
+    /// * `light` - The light to hit
+    /// * `sampler` - The sampler to use
+    /// * `bsdf` - The BSDF at the intersection
+    /// * `types` - The types of the BSDF to sample
+    ///
+    /// # Results
+    /// * `S` - The reflected radiance
+    fn sample_bsdf<S: Spectrum>(
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        light: &Arc<dyn Emitter<S>>,
+        sampler: &Arc<dyn Sampler>,
+        bsdf: &BSDF<S>,
+        types: BxDFType,
+    ) -> S {
+        let normal = intersection.info.normal;
+        let outgoing = -intersection.info.ray.direction;
+
+        let sample = match bsdf.sample(&normal, &outgoing, types, &sampler.get_sample()) {
+            Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+            _ => return S::black(),
+        };
+        let incident = sample.incident;
+
+        let ray = intersection.info.create_ray(incident);
+        let light_pdf = light.pdf_incident(&ray);
+        if light_pdf == 0.0 {
+            return S::black();
+        }
+
+        let radiance = match scene.intersect(&ray) {
+            Some(si) => match &si.obj {
+                Instance::Emitter(e) if is_same_light(e, light) => {
+                    e.radiance(&-incident, &si.info.normal)
+                }
+                _ => return S::black(),
+            },
+            None => return S::black(),
+        };
+
+        let (cos, weight) = if sample.typ.is_specular() {
+            // the specular part of a stochastic BxDF can only be found by sampling it
+            (1.0, 1.0)
+        } else {
+            // the pdf of the whole BSDF, as used when sampling the light, which only estimates
+            // the pdf of stochastic BxDFs
+            let bsdf_pdf = bsdf.pdf(&normal, &incident, &outgoing, types);
+
+            (
+                incident.dot(normal).abs(),
+                power_heuristic(1, bsdf_pdf, 1, light_pdf),
+            )
+        };
+
+        // the throughput of a sample is the ratio of its own value and pdf
+        sample.spectrum * radiance * (cos * weight / sample.pdf)
+    }
+
+    /// # Summary
+    /// Estimates the direct illumination at the intersection according to the light strategy.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `intersection` - The intersection to illuminate
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The reflected radiance
+    fn sample_lights<S: Spectrum>(
+        &self,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        sampler: &Arc<dyn Sampler>,
+    ) -> S {
+        let num_lights = scene.lights.len();
+        if num_lights == 0 {
+            return S::black();
+        }
+
+        let mut illumination = S::black();
+
+        match self.strategy {
+            LightStrategy::All => {
+                for light in &scene.lights {
+                    for _ in 0..self.light_samples {
+                        illumination += self.estimate_direct(scene, intersection, light, sampler);
+                    }
+                }
+            }
+            LightStrategy::Uniform => {
+                for _ in 0..self.light_samples {
+                    let index = (sampler.get_1d() * num_lights as f32) as usize;
+                    let light = &scene.lights[index.min(num_lights - 1)];
+
+                    illumination += self.estimate_direct(scene, intersection, light, sampler)
+                        * num_lights as f32;
+                }
+            }
+            LightStrategy::Power => {
+                for _ in 0..self.light_samples {
+                    let (index, pdf) =
+                        match self.light_distribution.sample_discrete(sampler.get_1d()) {
+                            Some((index, pdf)) if pdf > 0.0 => (index, pdf),
+                            _ => continue,
+                        };
+                    let light = &scene.lights[index];
+
+                    illumination += self.estimate_direct(scene, intersection, light, sampler) / pdf;
+                }
+            }
+        }
+
+        illumination / self.light_samples as f32
+    }
+}
+
+/// Whether both emitters are the same object.
+#[inline]
+fn is_same_light<S: Spectrum>(a: &Arc<dyn Emitter<S>>, b: &Arc<dyn Emitter<S>>) -> bool {
+    // compare the data pointers only, vtables may be duplicated
+    Arc::as_ptr(a) as *const u8 == Arc::as_ptr(b) as *const u8
+}
+
+impl<S: Spectrum> Integrator<S> for DirectLighting {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        if let Some(si) = scene.intersect(primary_ray) {
+            self.illumination(scene, &si, sampler, self.max_depth)
+        } else {
+            S::black()
+        }
+    }
+
+    fn illumination(
+        &self,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        sampler: Arc<dyn Sampler>,
+        depth: u32,
+    ) -> S {
+        let new_depth = match depth.checked_sub(1) {
+            Some(new_depth) => new_depth,
+            None => return S::black(),
+        };
+
+        let outgoing = -intersection.info.ray.direction;
+        let normal = intersection.info.normal;
+
+        let mut illumination = S::black();
+
+        // emission is not part of the light samples of previous (specular) bounces
+        if let Instance::Emitter(e) = &intersection.obj {
+            illumination += e.radiance(&outgoing, &normal);
+        }
+
+        if intersection
+            .obj
+            .bsdf()
+            .is_type(BxDFType::ALL & !BxDFType::SPECULAR)
+        {
+            illumination += self.sample_lights(scene, intersection, &sampler);
+        }
+
+        if new_depth > 0 {
+            illumination +=
+                self.specular_reflection(scene, intersection, sampler.clone(), new_depth);
+            illumination += self.specular_transmission(scene, intersection, sampler, new_depth);
+        }
+
+        illumination
+    }
+}
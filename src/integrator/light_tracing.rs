@@ -0,0 +1,151 @@
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::render::camera::Camera;
+use crate::render::film::Film;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::Arc;
+use ultraviolet::Vec2;
+
+/// A light tracing (particle tracing) integrator.
+///
+/// Paths are only traced from the emitters. Every vertex of a path is connected to the camera
+/// and its contribution splatted to the raster position it is seen at.
+/// The primary ray of the renderer is ignored, such that every pixel sample traces one light path.
+pub struct LightTracing<S: Spectrum> {
+    pub max_depth: u32,
+    camera: Arc<Camera>,
+    film: Arc<Film<S>>,
+}
+
+impl<S: Spectrum> LightTracing<S> {
+    pub fn new(max_depth: u32, camera: Arc<Camera>, film: Arc<Film<S>>) -> Self {
+        Self {
+            max_depth,
+            camera,
+            film,
+        }
+    }
+
+    /// # Summary
+    /// Traces a path from a randomly chosen emitter and connects every vertex to the camera.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `Vec<(Vec2, S)>` - The contributions to splat to their raster positions
+    pub fn trace(&self, scene: &Scene<S>, sampler: &Arc<dyn Sampler>) -> Vec<(Vec2, S)> {
+        let mut splats = Vec::new();
+        if scene.lights.is_empty() {
+            return splats;
+        }
+
+        let num_lights = scene.lights.len();
+        let light_pdf = 1.0 / num_lights as f32;
+        let index = usize::min(
+            (sampler.get_1d() * num_lights as f32) as usize,
+            num_lights - 1,
+        );
+        let light = &scene.lights[index];
+
+        let sample = light.sample_emission(&sampler.get_2d(), &sampler.get_2d());
+        if sample.pdf_pos == 0.0 || sample.pdf_dir == 0.0 || sample.radiance.is_black() {
+            return splats;
+        }
+
+        // connect the emitter itself to the camera, point lights cannot be seen by camera rays
+        let origin = sample.ray.origin;
+        if let Some(cs) = self
+            .camera
+            .sample_importance(&origin)
+            .filter(|_| !light.is_delta())
+        {
+            if cs.pdf > 0.0 && cs.importance > 0.0 {
+                let radiance = light.radiance(&cs.incident, &sample.normal);
+                let cos = cs.incident.dot(sample.normal).abs();
+
+                let l = radiance * (cos * cs.importance / (cs.pdf * light_pdf * sample.pdf_pos));
+                if !l.is_black() && !cs.occlusion_tester.is_occluded(scene) {
+                    splats.push((cs.raster, l));
+                }
+            }
+        }
+
+        let cos = if light.is_delta() {
+            1.0
+        } else {
+            sample.normal.dot(sample.ray.direction).abs()
+        };
+
+        let mut beta = sample.radiance * (cos / (light_pdf * sample.pdf_pos * sample.pdf_dir));
+        let mut ray = sample.ray;
+
+        for _ in 0..self.max_depth {
+            let si = match scene.intersect(&ray) {
+                Some(si) => si,
+                None => break,
+            };
+
+            let bsdf = si.obj.bsdf();
+            let normal = si.info.normal;
+            let outgoing = -ray.direction;
+
+            // specular surfaces cannot be connected to the camera
+            if bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                if let Some(cs) = self.camera.sample_importance(&si.info.point) {
+                    if cs.pdf > 0.0 && cs.importance > 0.0 {
+                        let c = bsdf.evaluate(&normal, &cs.incident, &outgoing, BxDFType::ALL);
+                        let cos = cs.incident.dot(normal).abs();
+
+                        let l = beta * c * (cos * cs.importance / cs.pdf);
+                        if !l.is_black() && !cs.occlusion_tester.is_occluded(scene) {
+                            splats.push((cs.raster, l));
+                        }
+                    }
+                }
+            }
+
+            let bxdf_sample =
+                match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sampler.get_sample()) {
+                    Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                    _ => break,
+                };
+
+            let dot = if bxdf_sample.typ.is_specular() {
+                1.0
+            } else {
+                bxdf_sample.incident.dot(normal).abs()
+            };
+
+            beta *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
+            ray = si.info.create_ray(bxdf_sample.incident);
+        }
+
+        splats
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for LightTracing<S> {
+    /// Traces a light path. Every contribution is splatted to the film.
+    fn integrate(&self, scene: &Scene<S>, _: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        self.trace(scene, &sampler)
+            .iter()
+            .for_each(|(raster, l)| self.film.splat(raster, *l));
+
+        S::black()
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
@@ -0,0 +1,287 @@
+use crate::integrator::bdpt::Bdpt;
+use crate::integrator::path::Path;
+use crate::integrator::Integrator;
+use crate::mc::Distribution1D;
+use crate::render::camera::Camera;
+use crate::render::film::Film;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::mlt::MltSampler;
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use ultraviolet::Vec2;
+
+/// The integrator evaluating the samples of the Markov chains.
+pub enum MltCore<S: Spectrum> {
+    Path(Path),
+    Bdpt(Bdpt<S>),
+}
+
+impl<S: Spectrum> MltCore<S> {
+    /// # Summary
+    /// Evaluates the current samples of the sampler.
+    /// The first two samples determine the raster position of the camera ray.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera
+    /// * `scene` - The scene
+    /// * `sampler` - The sampler
+    ///
+    /// # Results
+    /// * `Vec<(Vec2, S)>` - The contributions at their raster positions
+    fn evaluate(
+        &self,
+        camera: &Camera,
+        scene: &Scene<S>,
+        sampler: &Arc<MltSampler>,
+    ) -> Vec<(Vec2, S)> {
+        let width = camera.width as f32;
+        let height = camera.height as f32;
+        let raster = Vec2::new(sampler.get_1d() * width, sampler.get_1d() * height);
+
+        let x = raster.x as u32;
+        let y = raster.y as u32;
+        let offset = Vec2::new(raster.x - x as f32, raster.y - y as f32);
+        let ray = camera.primary_ray(x, y, &offset);
+
+        let sampler: Arc<dyn Sampler> = sampler.clone();
+        self.contributions(scene, &ray, raster, &sampler)
+    }
+
+    fn contributions(
+        &self,
+        scene: &Scene<S>,
+        ray: &Ray,
+        raster: Vec2,
+        sampler: &Arc<dyn Sampler>,
+    ) -> Vec<(Vec2, S)> {
+        match self {
+            MltCore::Path(path) => vec![(raster, path.integrate(scene, ray, sampler.clone()))],
+            MltCore::Bdpt(bdpt) => {
+                let (radiance, mut splats) = bdpt.trace(scene, ray, sampler);
+                splats.push((raster, radiance));
+                splats
+            }
+        }
+    }
+}
+
+/// The scalar contribution the Markov chains are distributed by (the luminance).
+#[inline]
+fn scalar_contribution<S: Spectrum>(contributions: &[(Vec2, S)]) -> f32 {
+    contributions
+        .iter()
+        .map(|(_, l)| f32::max(0.0, l.luminance()))
+        .sum()
+}
+
+/// A Markov chain in primary sample space.
+struct Chain<S> {
+    sampler: Arc<MltSampler>,
+    contributions: Vec<(Vec2, S)>,
+    importance: f32,
+}
+
+/// A primary sample space Metropolis light transport integrator (Kelemen et al.).
+///
+/// Instead of sampling pixels independently, every call mutates one of many Markov chains and
+/// splats the contributions of the current and proposed sample to the film.
+/// The chains are normalized by a bootstrap phase before the first pass, which is spread over
+/// the given number of threads.
+pub struct Mlt<S: Spectrum> {
+    core: Arc<MltCore<S>>,
+    camera: Arc<Camera>,
+    film: Arc<Film<S>>,
+    pub threads: u32,
+    pub num_bootstrap: usize,
+    pub num_chains: usize,
+    pub sigma: f32,
+    pub large_step_probability: f32,
+    /// The normalization constant of the image
+    b: Mutex<f32>,
+    chains: Mutex<VecDeque<Chain<S>>>,
+}
+
+impl<S: Spectrum> Mlt<S> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        core: MltCore<S>,
+        camera: Arc<Camera>,
+        film: Arc<Film<S>>,
+        threads: u32,
+        num_bootstrap: usize,
+        num_chains: usize,
+        sigma: f32,
+        large_step_probability: f32,
+    ) -> Self {
+        Self {
+            core: Arc::new(core),
+            camera,
+            film,
+            threads,
+            num_bootstrap,
+            num_chains,
+            sigma,
+            large_step_probability,
+            b: Mutex::new(0.0),
+            chains: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn evaluate(&self, scene: &Scene<S>, sampler: &Arc<MltSampler>) -> Vec<(Vec2, S)> {
+        self.core.evaluate(&self.camera, scene, sampler)
+    }
+
+    fn new_sampler(&self, seed: usize) -> Arc<MltSampler> {
+        Arc::new(MltSampler::new(
+            seed as u64,
+            self.sigma,
+            self.large_step_probability,
+        ))
+    }
+
+    /// # Summary
+    /// Computes the scalar contributions of the bootstrap samples, each thread evaluating a
+    /// contiguous range of the seeds.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    ///
+    /// # Results
+    /// * `Vec<f32>` - The scalar contribution of every seed
+    fn bootstrap_weights(&self, scene: &Arc<Scene<S>>) -> Vec<f32> {
+        let threads = self.threads.max(1) as usize;
+        let per_thread = self.num_bootstrap / threads + 1;
+
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                let core = self.core.clone();
+                let camera = self.camera.clone();
+                let scene = scene.clone();
+                let (sigma, large_step_probability) = (self.sigma, self.large_step_probability);
+
+                let start = usize::min(thread * per_thread, self.num_bootstrap);
+                let end = usize::min(start + per_thread, self.num_bootstrap);
+
+                thread::spawn(move || {
+                    (start..end)
+                        .map(|seed| {
+                            let sampler =
+                                MltSampler::new(seed as u64, sigma, large_step_probability);
+                            let contributions = core.evaluate(&camera, &scene, &Arc::new(sampler));
+
+                            scalar_contribution(&contributions)
+                        })
+                        .collect::<Vec<f32>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Could not join bootstrap threads"))
+            .collect()
+    }
+
+    /// Computes the normalization constant and initializes the chains from the bootstrap samples.
+    fn bootstrap(&self, scene: &Arc<Scene<S>>) {
+        let weights = self.bootstrap_weights(scene);
+
+        let distribution = Distribution1D::new(&weights);
+        let b = distribution.func_int();
+
+        let rng = fastrand::Rng::with_seed(self.num_bootstrap as u64);
+        let chains = (0..self.num_chains)
+            .filter_map(|_| distribution.sample_discrete(rng.f32()))
+            .map(|(seed, _)| {
+                // recreates the bootstrap sample
+                let sampler = self.new_sampler(seed);
+                let contributions = self.evaluate(scene, &sampler);
+                let importance = scalar_contribution(&contributions);
+
+                Chain {
+                    sampler,
+                    contributions,
+                    importance,
+                }
+            })
+            .collect();
+
+        *self.b.lock().expect("Normalization is poisoned") = b;
+        *self.chains.lock().expect("Chains are poisoned") = chains;
+    }
+
+    /// Mutates the chain once and splats the contributions weighted by their acceptance.
+    fn mutate(&self, scene: &Scene<S>, chain: &mut Chain<S>, b: f32) {
+        chain.sampler.start_iteration();
+
+        let proposed = self.evaluate(scene, &chain.sampler);
+        let proposed_importance = scalar_contribution(&proposed);
+
+        let accept = if chain.importance > 0.0 {
+            f32::min(1.0, proposed_importance / chain.importance)
+        } else {
+            1.0
+        };
+
+        if accept > 0.0 {
+            let scale = accept * b / proposed_importance;
+            proposed
+                .iter()
+                .for_each(|(raster, l)| self.film.splat(raster, *l * scale));
+        }
+        if accept < 1.0 && chain.importance > 0.0 {
+            let scale = (1.0 - accept) * b / chain.importance;
+            chain
+                .contributions
+                .iter()
+                .for_each(|(raster, l)| self.film.splat(raster, *l * scale));
+        }
+
+        if chain.sampler.uniform() < accept {
+            chain.sampler.accept();
+            chain.contributions = proposed;
+            chain.importance = proposed_importance;
+        } else {
+            chain.sampler.reject();
+        }
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for Mlt<S> {
+    fn begin_pass(&self, scene: &Arc<Scene<S>>, pass: u32) {
+        if pass == 0 {
+            self.bootstrap(scene);
+        }
+    }
+
+    /// Mutates the next chain. Every contribution is splatted to the film.
+    fn integrate(&self, scene: &Scene<S>, _: &Ray, _: Arc<dyn Sampler>) -> S {
+        let chain = self.chains.lock().expect("Chains are poisoned").pop_front();
+
+        if let Some(mut chain) = chain {
+            let b = *self.b.lock().expect("Normalization is poisoned");
+            self.mutate(scene, &mut chain, b);
+
+            self.chains
+                .lock()
+                .expect("Chains are poisoned")
+                .push_back(chain);
+        }
+
+        S::black()
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
@@ -3,33 +3,51 @@ use crate::render::objects::Instance;
 use crate::render::scene::{Scene, SceneIntersection};
 use crate::sampler::Sampler;
 use crate::Spectrum;
-use color::Color;
 use geometry::ray::Ray;
 use std::sync::Arc;
 
+pub mod ambient_occlusion;
+pub mod bdpt;
 pub mod debug_normals;
+pub mod direct_lighting;
+pub mod light_tracing;
+pub mod mlt;
 pub mod path;
+pub mod photon_mapping;
+pub mod polarized_path;
+pub mod spectral_path;
+pub mod sppm;
+pub mod volumetric_path;
 pub mod whitted;
 
-pub trait Integrator: Send + Sync {
-    fn integrate(&self, scene: &Scene, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> Spectrum;
+pub trait Integrator<S: Spectrum>: Send + Sync {
+    /// # Summary
+    /// Called by the renderer before a pass starts, after all blocks of the previous pass were
+    /// rendered.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene to render
+    /// * `pass` - The index of the pass about to start
+    fn begin_pass(&self, _: &Arc<Scene<S>>, _: u32) {}
+
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S;
 
     fn illumination(
         &self,
-        scene: &Scene,
-        intersection: &SceneIntersection,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
         sampler: Arc<dyn Sampler>,
         depth: u32,
-    ) -> Spectrum;
+    ) -> S;
 
     //noinspection DuplicatedCode
     fn specular_reflection(
         &self,
-        scene: &Scene,
-        intersection: &SceneIntersection,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
         sampler: Arc<dyn Sampler>,
         depth: u32,
-    ) -> Spectrum {
+    ) -> S {
         let outgoing = -intersection.info.ray.direction;
 
         let obj = match &intersection.obj {
@@ -69,17 +87,17 @@ pub trait Integrator: Send + Sync {
             }
         }
 
-        Spectrum::black()
+        S::black()
     }
 
     //noinspection DuplicatedCode
     fn specular_transmission(
         &self,
-        scene: &Scene,
-        intersection: &SceneIntersection,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
         sampler: Arc<dyn Sampler>,
         depth: u32,
-    ) -> Spectrum {
+    ) -> S {
         let outgoing = -intersection.info.ray.direction;
 
         let obj = match &intersection.obj {
@@ -119,6 +137,52 @@ pub trait Integrator: Send + Sync {
             }
         }
 
-        Spectrum::black()
+        S::black()
     }
 }
+
+/// # Summary
+/// Estimates the direct illumination at the intersection by sampling every light once.
+///
+/// # Arguments
+/// * `scene` - The scene
+/// * `intersection` - The intersection to illuminate
+/// * `sampler` - The sampler to use
+///
+/// # Results
+/// * `S` - The reflected radiance towards the ray origin
+pub fn direct_illumination<S: Spectrum>(
+    scene: &Scene<S>,
+    intersection: &SceneIntersection<S>,
+    sampler: &Arc<dyn Sampler>,
+) -> S {
+    let bsdf = intersection.obj.bsdf();
+    let normal = intersection.info.normal;
+    let outgoing = -intersection.info.ray.direction;
+
+    let mut illumination = S::black();
+
+    for light in &scene.lights {
+        let emitter_sample = light.sample(intersection, &sampler.get_2d());
+
+        if emitter_sample.pdf > 0.0
+            && !emitter_sample.radiance.is_black()
+            && !emitter_sample.occlusion_tester.is_occluded(scene)
+        {
+            let c = bsdf.evaluate(&normal, &emitter_sample.incident, &outgoing, BxDFType::ALL);
+
+            if !c.is_black() {
+                let mut pdf = emitter_sample.pdf;
+                // point lights have no falloff in their sample
+                if light.is_delta() {
+                    pdf *= (emitter_sample.point - intersection.info.point).mag_sq();
+                }
+
+                let cos = emitter_sample.incident.dot(normal).abs();
+                illumination += c * emitter_sample.radiance * (cos / pdf);
+            }
+        }
+    }
+
+    illumination
+}
@@ -4,7 +4,6 @@ use crate::render::objects::Instance;
 use crate::render::scene::{Scene, SceneIntersection};
 use crate::sampler::Sampler;
 use crate::Spectrum;
-use color::Color;
 use geometry::ray::Ray;
 use std::sync::Arc;
 use util::floats;
@@ -23,26 +22,26 @@ impl Path {
     }
 }
 
-impl Integrator for Path {
+impl<S: Spectrum> Integrator<S> for Path {
     //noinspection DuplicatedCode
-    fn integrate(&self, scene: &Scene, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> Spectrum {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
         if let Some(si) = scene.intersect(primary_ray) {
             self.illumination(scene, &si, sampler, self.max_depth)
         } else {
-            Spectrum::black()
+            S::black()
         }
     }
 
     //noinspection DuplicatedCode
     fn illumination(
         &self,
-        scene: &Scene,
-        intersection: &SceneIntersection,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
         sampler: Arc<dyn Sampler>,
         _: u32,
-    ) -> Spectrum {
-        let mut color = Spectrum::black();
-        let mut throughput = Spectrum::new_const(1.0);
+    ) -> S {
+        let mut color = S::black();
+        let mut throughput = S::new_const(1.0);
 
         let mut hit = intersection.clone();
         let mut specular = false;
@@ -50,7 +49,8 @@ impl Integrator for Path {
         for bounce in 0..self.max_depth {
             let outgoing = -hit.info.ray.direction;
 
-            let (obj, emitter) = match &intersection.obj {
+            let instance = hit.obj.clone();
+            let (obj, emitter) = match &instance {
                 Instance::Emitter(e) => (e.as_receiver(), Some(e)),
                 Instance::Receiver(r) => (r.clone(), None),
             };
@@ -58,11 +58,11 @@ impl Integrator for Path {
             let bsdf = obj.bsdf();
             let normal = &hit.info.normal;
 
-            let mut illumination = Spectrum::black();
+            let mut illumination = S::black();
 
             if bounce == 0 || specular {
                 if let Some(e) = emitter {
-                    illumination += throughput * e.emission();
+                    illumination += e.radiance(&outgoing, normal);
                 }
             }
 
@@ -80,13 +80,16 @@ impl Integrator for Path {
                         bsdf.evaluate(normal, &emitter_sample.incident, &outgoing, BxDFType::ALL);
 
                     if !c.is_black() {
+                        let mut pdf = emitter_sample.pdf;
+                        // point lights have no falloff in their sample
+                        if light.is_delta() {
+                            pdf *= (emitter_sample.point - hit.info.point).mag_sq();
+                        }
+
                         let cos = emitter_sample.incident.dot(*normal).abs();
 
                         if cos != 0.0 {
-                            illumination += light.emission()
-                                * c
-                                * emitter_sample.radiance
-                                * (cos / emitter_sample.pdf);
+                            illumination += c * emitter_sample.radiance * (cos / pdf);
                         }
                     }
                 }
@@ -110,7 +113,7 @@ impl Integrator for Path {
                     floats::fast_clamp(bxdf_sample.incident.dot(hit.info.normal).abs(), 0.0, 1.0)
                 };
 
-                throughput *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf) * 0.5;
+                throughput *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
 
                 // if bounce > self.min_depth {
                 //     let const_prob = 0.75;
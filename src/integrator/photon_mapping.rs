@@ -0,0 +1,277 @@
+use crate::bxdf::bsdf::BSDF;
+use crate::bxdf::BxDFType;
+use crate::integrator::{direct_illumination, Integrator};
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::{RandomSampler, Sampler};
+use crate::Spectrum;
+use geometry::kd_tree::{KdTree, Positioned};
+use geometry::ray::Ray;
+use std::f32::consts::PI;
+use std::sync::{Arc, RwLock};
+use ultraviolet::Vec3;
+
+/// A photon deposited on a surface.
+#[derive(Debug, Clone)]
+pub struct Photon<S> {
+    pub position: Vec3,
+    /// The direction the photon arrived from
+    pub incident: Vec3,
+    pub power: S,
+}
+
+impl<S> Positioned for Photon<S> {
+    fn position(&self) -> Vec3 {
+        self.position
+    }
+}
+
+/// A photon map storing indirect illumination.
+pub struct PhotonMap<S> {
+    photons: KdTree<Photon<S>>,
+    num_emitted: usize,
+}
+
+impl<S: Spectrum> PhotonMap<S> {
+    pub fn empty() -> Self {
+        Self {
+            photons: KdTree::new(Vec::new()),
+            num_emitted: 0,
+        }
+    }
+
+    /// # Summary
+    /// Emits photons from the lights of the scene and stores them at every non-specular surface
+    /// they hit after the first bounce.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `num_photons` - The number of photons to emit
+    /// * `max_depth` - The maximum number of bounces of a photon
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `Self` - The photon map
+    pub fn emit(
+        scene: &Scene<S>,
+        num_photons: usize,
+        max_depth: u32,
+        sampler: &dyn Sampler,
+    ) -> Self {
+        if scene.lights.is_empty() {
+            return Self::empty();
+        }
+
+        let num_lights = scene.lights.len();
+        let light_pdf = 1.0 / num_lights as f32;
+
+        let mut photons = Vec::with_capacity(num_photons);
+
+        for _ in 0..num_photons {
+            let index = usize::min(
+                (sampler.get_1d() * num_lights as f32) as usize,
+                num_lights - 1,
+            );
+            let light = &scene.lights[index];
+
+            let sample = light.sample_emission(&sampler.get_2d(), &sampler.get_2d());
+            if sample.pdf_pos == 0.0 || sample.pdf_dir == 0.0 || sample.radiance.is_black() {
+                continue;
+            }
+
+            let cos = if light.is_delta() {
+                1.0
+            } else {
+                sample.normal.dot(sample.ray.direction).abs()
+            };
+
+            let mut power = sample.radiance * (cos / (light_pdf * sample.pdf_pos * sample.pdf_dir));
+            let mut ray = sample.ray;
+
+            for depth in 0..max_depth {
+                let si = match scene.intersect(&ray) {
+                    Some(si) => si,
+                    None => break,
+                };
+
+                let bsdf = si.obj.bsdf();
+                let normal = si.info.normal;
+                let outgoing = -ray.direction;
+
+                // direct illumination is estimated by sampling the lights
+                if depth > 0 && bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                    photons.push(Photon {
+                        position: si.info.point,
+                        incident: outgoing,
+                        power,
+                    });
+                }
+
+                let bxdf_sample =
+                    match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sampler.get_sample()) {
+                        Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                        _ => break,
+                    };
+
+                let dot = if bxdf_sample.typ.is_specular() {
+                    1.0
+                } else {
+                    bxdf_sample.incident.dot(normal).abs()
+                };
+
+                power *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
+                ray = si.info.create_ray(bxdf_sample.incident);
+            }
+        }
+
+        Self {
+            photons: KdTree::new(photons),
+            num_emitted: num_photons,
+        }
+    }
+
+    /// # Summary
+    /// Estimates the reflected radiance at the point by density estimation of the nearby photons.
+    ///
+    /// # Arguments
+    /// * `bsdf` - The BSDF at the point
+    /// * `normal` - The normal at the point
+    /// * `outgoing` - The outgoing direction
+    /// * `point` - The point
+    /// * `radius` - The gather radius
+    ///
+    /// # Results
+    /// * `S` - The reflected radiance
+    pub fn estimate(
+        &self,
+        bsdf: &BSDF<S>,
+        normal: &Vec3,
+        outgoing: &Vec3,
+        point: &Vec3,
+        radius: f32,
+    ) -> S {
+        if self.num_emitted == 0 {
+            return S::black();
+        }
+
+        let mut flux = S::black();
+        self.photons.for_each_in_radius(point, radius, |photon| {
+            flux += bsdf.evaluate(normal, &photon.incident, outgoing, BxDFType::ALL) * photon.power;
+        });
+
+        flux / (self.num_emitted as f32 * PI * radius * radius)
+    }
+
+    /// # Summary
+    /// Traces the ray through specular surfaces and estimates the radiance at the first
+    /// non-specular surface with direct lighting and the photon map.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `ray` - The ray to trace
+    /// * `sampler` - The sampler to use
+    /// * `max_depth` - The maximum number of specular bounces
+    /// * `radius` - The gather radius
+    ///
+    /// # Results
+    /// * `S` - The incoming radiance along the ray
+    pub fn trace(
+        &self,
+        scene: &Scene<S>,
+        ray: &Ray,
+        sampler: &Arc<dyn Sampler>,
+        max_depth: u32,
+        radius: f32,
+    ) -> S {
+        let mut radiance = S::black();
+        let mut throughput = S::new_const(1.0);
+        let mut ray = *ray;
+
+        for _ in 0..max_depth {
+            let si = match scene.intersect(&ray) {
+                Some(si) => si,
+                None => break,
+            };
+
+            let bsdf = si.obj.bsdf();
+            let normal = si.info.normal;
+            let outgoing = -ray.direction;
+
+            if let Instance::Emitter(e) = &si.obj {
+                radiance += throughput * e.radiance(&outgoing, &normal);
+            }
+
+            if bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                let direct = direct_illumination(scene, &si, sampler);
+                let indirect = self.estimate(bsdf, &normal, &outgoing, &si.info.point, radius);
+
+                radiance += throughput * (direct + indirect);
+                break;
+            }
+
+            let bxdf_sample =
+                match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sampler.get_sample()) {
+                    Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                    _ => break,
+                };
+
+            throughput *= bxdf_sample.spectrum / bxdf_sample.pdf;
+            ray = si.info.create_ray(bxdf_sample.incident);
+        }
+
+        radiance
+    }
+}
+
+/// A photon mapping integrator.
+///
+/// Before the first pass photons are emitted from the lights and stored in a photon map.
+/// Camera rays are traced through specular surfaces, at the first non-specular surface the
+/// direct illumination is sampled and the indirect illumination estimated from the photon map.
+pub struct PhotonMapping<S> {
+    pub max_depth: u32,
+    pub num_photons: usize,
+    pub radius: f32,
+    photon_map: RwLock<Option<PhotonMap<S>>>,
+}
+
+impl<S> PhotonMapping<S> {
+    pub fn new(max_depth: u32, num_photons: usize, radius: f32) -> Self {
+        Self {
+            max_depth,
+            num_photons,
+            radius,
+            photon_map: RwLock::new(None),
+        }
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for PhotonMapping<S> {
+    fn begin_pass(&self, scene: &Arc<Scene<S>>, _: u32) {
+        let mut photon_map = self.photon_map.write().expect("Photon map is poisoned");
+
+        if photon_map.is_none() {
+            let map = PhotonMap::emit(scene, self.num_photons, self.max_depth, &RandomSampler);
+            *photon_map = Some(map);
+        }
+    }
+
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        let photon_map = self.photon_map.read().expect("Photon map is poisoned");
+
+        match photon_map.as_ref() {
+            Some(map) => map.trace(scene, primary_ray, &sampler, self.max_depth, self.radius),
+            None => S::black(),
+        }
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
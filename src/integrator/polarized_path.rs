@@ -0,0 +1,207 @@
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::polarization::{Mueller, Stokes, StokesFilm};
+use crate::render::camera::Camera;
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::Arc;
+use ultraviolet::Vec3;
+
+/// A path tracer carrying the polarization of light.
+///
+/// Paths accumulate the Mueller matrices of their interactions, such that the Stokes vector of
+/// the (unpolarized) light reaching the camera can be computed. The reference frame of the camera
+/// is its horizontal axis. The intensity `S0` is returned, while `S1` to `S3` are splatted onto
+/// the Stokes film.
+pub struct PolarizedPath<S: Spectrum> {
+    /// The depth after which paths are terminated by russian roulette
+    pub min_depth: u32,
+    pub max_depth: u32,
+    camera: Arc<Camera>,
+    stokes: Arc<StokesFilm<S>>,
+}
+
+impl<S: Spectrum> PolarizedPath<S> {
+    pub fn new(
+        min_depth: u32,
+        max_depth: u32,
+        camera: Arc<Camera>,
+        stokes: Arc<StokesFilm<S>>,
+    ) -> Self {
+        Self {
+            min_depth,
+            max_depth,
+            camera,
+            stokes,
+        }
+    }
+
+    /// # Summary
+    /// Samples every light once and estimates the light reflected towards `outgoing`.
+    /// The BSDF is assumed to depolarize, as specular BxDFs cannot be illuminated this way.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `intersection` - The intersection to illuminate
+    /// * `outgoing` - The outgoing direction
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The reflected radiance
+    fn sample_lights(
+        &self,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
+        outgoing: &Vec3,
+        sampler: &Arc<dyn Sampler>,
+    ) -> S {
+        let bsdf = intersection.obj.bsdf();
+        let normal = intersection.info.normal;
+
+        let mut illumination = S::black();
+
+        for light in &scene.lights {
+            let emitter_sample = light.sample(intersection, &sampler.get_2d());
+
+            if emitter_sample.pdf == 0.0 || emitter_sample.radiance.is_black() {
+                continue;
+            }
+
+            let incident = emitter_sample.incident;
+            let c = bsdf.evaluate(&normal, &incident, outgoing, BxDFType::ALL);
+            if c.is_black() || emitter_sample.occlusion_tester.is_occluded(scene) {
+                continue;
+            }
+
+            let mut pdf = emitter_sample.pdf;
+            // point lights have no falloff in their sample
+            if light.is_delta() {
+                pdf *= (emitter_sample.point - intersection.info.point).mag_sq();
+            }
+
+            illumination += c * emitter_sample.radiance * (incident.dot(normal).abs() / pdf);
+        }
+
+        illumination
+    }
+
+    /// # Summary
+    /// Computes the axis perpendicular to the plane of incidence, the s-axis of the Fresnel
+    /// equations.
+    ///
+    /// # Arguments
+    /// * `incident` - The incident direction
+    /// * `normal` - The surface normal
+    /// * `frame` - The reference axis of the outgoing direction, used at normal incidence
+    ///
+    /// # Results
+    /// * `Vec3` - The s-axis, perpendicular to `incident`
+    fn s_axis(incident: &Vec3, normal: &Vec3, frame: &Vec3) -> Vec3 {
+        let s = incident.cross(*normal);
+        if s.mag_sq() > 1e-8 {
+            return s.normalized();
+        }
+
+        let projected = *frame - *incident * frame.dot(*incident);
+        if projected.mag_sq() > 1e-8 {
+            projected.normalized()
+        } else {
+            incident.cross(Vec3::unit_x() + Vec3::unit_z()).normalized()
+        }
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for PolarizedPath<S> {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        // maps Stokes vectors of the current ray into the frame of the camera
+        let mut throughput = Mueller::identity();
+        // the horizontal axis of the camera, perpendicular to the primary ray
+        let mut frame = Self::s_axis(&primary_ray.direction, &self.camera.up, &self.camera.right);
+        let mut stokes = Stokes::black();
+
+        let mut ray = *primary_ray;
+        let mut specular = true;
+
+        for bounce in 0..self.max_depth {
+            let si = match scene.intersect(&ray) {
+                Some(si) => si,
+                None => break,
+            };
+
+            let outgoing = -ray.direction;
+            let normal = si.info.normal;
+
+            if specular {
+                if let Instance::Emitter(e) = &si.obj {
+                    let radiance = e.radiance(&outgoing, &normal);
+                    stokes = stokes + throughput.apply_unpolarized(radiance);
+                }
+            }
+
+            let bsdf = si.obj.bsdf();
+            if bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                let illumination = self.sample_lights(scene, &si, &outgoing, &sampler);
+                stokes = stokes + throughput.apply_unpolarized(illumination);
+            }
+
+            let sample = sampler.get_sample();
+            let bxdf_sample = match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sample) {
+                Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                _ => break,
+            };
+
+            specular = bxdf_sample.typ.is_specular();
+            let dot = if specular {
+                1.0
+            } else {
+                bxdf_sample.incident.dot(normal).abs()
+            };
+
+            // the interaction in the s/p frame, rotated into the frame of the outgoing ray
+            let incident = bxdf_sample.incident;
+            let s = Self::s_axis(&incident, &normal, &frame);
+            let polarization = bsdf
+                .polarization(&normal, &incident, &outgoing, bxdf_sample.typ)
+                .scaled(bxdf_sample.spectrum * (dot / bxdf_sample.pdf));
+
+            throughput = throughput * Mueller::rotate_frame(&s, &frame, &outgoing) * polarization;
+            frame = s;
+
+            ray = si.info.create_ray(incident);
+
+            if bounce >= self.min_depth {
+                let max = (0..S::SIZE)
+                    .map(|i| throughput.m[0][0][i])
+                    .fold(0.0, f32::max);
+                let q = f32::max(0.05, 1.0 - max);
+                if sampler.get_1d() < q {
+                    break;
+                }
+
+                throughput = throughput.scaled(S::new_const(1.0 / (1.0 - q)));
+            }
+        }
+
+        if let Some(raster) = self
+            .camera
+            .raster(&(primary_ray.origin + primary_ray.direction))
+        {
+            self.stokes.splat(&raster, &stokes);
+        }
+
+        stokes.s[0]
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
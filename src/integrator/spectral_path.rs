@@ -0,0 +1,203 @@
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use color::{Color, Spectral, WavelengthSample};
+use geometry::ray::Ray;
+use std::sync::Arc;
+use ultraviolet::Vec3;
+
+/// A path tracer using hero wavelength sampling.
+///
+/// Every path carries a hero wavelength and `HERO_SAMPLES - 1` stratified wavelengths only,
+/// such that BxDFs and emitters contribute at these wavelengths only. Once a path scatters at a
+/// dispersive BSDF, the direction is sampled for the hero wavelength and the secondary wavelengths
+/// are terminated. Fluorescent BSDFs terminate the secondary wavelengths as well and switch the
+/// hero wavelength to the wavelength the light was absorbed at.
+pub struct SpectralPath {
+    /// The depth after which paths are terminated by russian roulette
+    pub min_depth: u32,
+    pub max_depth: u32,
+}
+
+impl SpectralPath {
+    pub fn new(min_depth: u32, max_depth: u32) -> Self {
+        Self {
+            min_depth,
+            max_depth,
+        }
+    }
+
+    /// # Summary
+    /// Samples every light once and estimates the light reflected towards `outgoing`.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `intersection` - The intersection to illuminate
+    /// * `outgoing` - The outgoing direction
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `Spectral` - The reflected radiance
+    fn sample_lights(
+        &self,
+        scene: &Scene<Spectral>,
+        intersection: &SceneIntersection<Spectral>,
+        outgoing: &Vec3,
+        sampler: &Arc<dyn Sampler>,
+    ) -> Spectral {
+        let bsdf = intersection.obj.bsdf();
+        let normal = intersection.info.normal;
+
+        let mut illumination = Spectral::black();
+
+        for light in &scene.lights {
+            let emitter_sample = light.sample(intersection, &sampler.get_2d());
+
+            if emitter_sample.pdf == 0.0 || emitter_sample.radiance.is_black() {
+                continue;
+            }
+
+            let incident = emitter_sample.incident;
+            let c = bsdf.scatter(
+                &normal,
+                &incident,
+                outgoing,
+                BxDFType::ALL,
+                &emitter_sample.radiance,
+            );
+            if c.is_black() || emitter_sample.occlusion_tester.is_occluded(scene) {
+                continue;
+            }
+
+            let mut pdf = emitter_sample.pdf;
+            // point lights have no falloff in their sample
+            if light.is_delta() {
+                pdf *= (emitter_sample.point - intersection.info.point).mag_sq();
+            }
+
+            illumination += c * (incident.dot(normal).abs() / pdf);
+        }
+
+        illumination
+    }
+
+    /// # Summary
+    /// Terminates the secondary wavelengths of the path, if not already terminated, reweighting
+    /// the throughput of the hero wavelength.
+    ///
+    /// # Arguments
+    /// * `wavelengths` - The wavelengths of the path
+    /// * `throughput` - The throughput of the path
+    fn terminate_secondary(wavelengths: &mut WavelengthSample, throughput: &mut Spectral) {
+        if !wavelengths.is_secondary_terminated() {
+            let weight = wavelengths.weight();
+            wavelengths.terminate_secondary();
+            *throughput *= wavelengths.mask() * (wavelengths.weight() / weight);
+        }
+    }
+}
+
+impl Integrator<Spectral> for SpectralPath {
+    fn integrate(
+        &self,
+        scene: &Scene<Spectral>,
+        primary_ray: &Ray,
+        sampler: Arc<dyn Sampler>,
+    ) -> Spectral {
+        let mut wavelengths = WavelengthSample::new(sampler.get_1d());
+
+        let mut radiance = Spectral::black();
+        // the throughput includes the inverse probability of the wavelengths being sampled
+        let mut throughput = wavelengths.mask() * wavelengths.weight();
+
+        let mut ray = *primary_ray;
+        let mut specular = true;
+
+        for bounce in 0..self.max_depth {
+            let si = match scene.intersect(&ray) {
+                Some(si) => si,
+                None => break,
+            };
+
+            let outgoing = -ray.direction;
+            let normal = si.info.normal;
+
+            if specular {
+                if let Instance::Emitter(e) = &si.obj {
+                    radiance +=
+                        wavelengths.to_sensor(&(throughput * e.radiance(&outgoing, &normal)));
+                }
+            }
+
+            let bsdf = si.obj.bsdf();
+            if bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                let illumination = self.sample_lights(scene, &si, &outgoing, &sampler);
+                radiance += wavelengths.to_sensor(&(throughput * illumination));
+            }
+
+            let sample = sampler.get_sample();
+            let bxdf_sample = if bsdf.is_dispersive() {
+                Self::terminate_secondary(&mut wavelengths, &mut throughput);
+
+                let lambda = wavelengths.hero_lambda();
+                bsdf.sample_wavelength(&normal, &outgoing, BxDFType::ALL, &sample, lambda)
+            } else {
+                bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sample)
+            };
+
+            let bxdf_sample = match bxdf_sample {
+                Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                _ => break,
+            };
+
+            specular = bxdf_sample.typ.is_specular();
+            let dot = if specular {
+                1.0
+            } else {
+                bxdf_sample.incident.dot(normal).abs()
+            };
+
+            throughput *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
+            ray = si.info.create_ray(bxdf_sample.incident);
+
+            if bsdf.is_fluorescent() {
+                let emission = wavelengths.hero();
+                let excitation =
+                    bsdf.sample_excitation(bxdf_sample.typ, emission, sampler.get_1d());
+
+                if let Some((index, weight)) = excitation {
+                    Self::terminate_secondary(&mut wavelengths, &mut throughput);
+
+                    let value = throughput[emission] * weight;
+                    wavelengths.switch_hero(index);
+                    throughput = Spectral::black();
+                    throughput[index] = value;
+                }
+            }
+
+            if bounce >= self.min_depth {
+                let max = wavelengths.max_value(&throughput) / wavelengths.weight();
+                let q = f32::max(0.05, 1.0 - max);
+                if sampler.get_1d() < q {
+                    break;
+                }
+
+                throughput /= 1.0 - q;
+            }
+        }
+
+        radiance
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<Spectral>,
+        _: &SceneIntersection<Spectral>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> Spectral {
+        unreachable!()
+    }
+}
@@ -0,0 +1,73 @@
+use crate::integrator::photon_mapping::PhotonMap;
+use crate::integrator::Integrator;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::{RandomSampler, Sampler};
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::{Arc, RwLock};
+
+/// A stochastic progressive photon mapping integrator (Knaus & Zwicker).
+///
+/// Every pass emits a new photon map and gathers with a global radius that shrinks from pass to
+/// pass, such that the average of all passes converges to the correct solution.
+pub struct Sppm<S> {
+    pub max_depth: u32,
+    pub num_photons: usize,
+    pub initial_radius: f32,
+    /// The fraction of photons kept per pass, within `(0, 1)`
+    pub alpha: f32,
+    pass: RwLock<(PhotonMap<S>, f32)>,
+}
+
+impl<S: Spectrum> Sppm<S> {
+    pub fn new(max_depth: u32, num_photons: usize, initial_radius: f32, alpha: f32) -> Self {
+        Self {
+            max_depth,
+            num_photons,
+            initial_radius,
+            alpha,
+            pass: RwLock::new((PhotonMap::empty(), initial_radius)),
+        }
+    }
+
+    /// # Summary
+    /// Computes the gather radius of the given pass.
+    ///
+    /// # Arguments
+    /// * `pass` - The pass
+    ///
+    /// # Results
+    /// * `f32` - The radius
+    pub fn radius(&self, pass: u32) -> f32 {
+        let radius_sq = (1..=pass).fold(self.initial_radius * self.initial_radius, |r2, i| {
+            r2 * (i as f32 + self.alpha) / (i as f32 + 1.0)
+        });
+
+        radius_sq.sqrt()
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for Sppm<S> {
+    fn begin_pass(&self, scene: &Arc<Scene<S>>, pass: u32) {
+        let map = PhotonMap::emit(scene, self.num_photons, self.max_depth, &RandomSampler);
+
+        *self.pass.write().expect("Pass is poisoned") = (map, self.radius(pass));
+    }
+
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        let pass = self.pass.read().expect("Pass is poisoned");
+        let (map, radius) = &*pass;
+
+        map.trace(scene, primary_ray, &sampler, self.max_depth, *radius)
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
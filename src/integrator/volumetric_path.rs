@@ -0,0 +1,232 @@
+use crate::bxdf::BxDFType;
+use crate::integrator::Integrator;
+use crate::medium::phase::PhaseFunction;
+use crate::medium::Medium;
+use crate::render::objects::Instance;
+use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::sync::Arc;
+use ultraviolet::Vec3;
+
+/// A point where light is scattered, either on a surface or inside a medium.
+enum Scattering<'a, S: Spectrum> {
+    Surface(&'a SceneIntersection<S>),
+    Medium(Vec3, &'a dyn PhaseFunction),
+}
+
+impl<S: Spectrum> Scattering<'_, S> {
+    /// Evaluates the scattered light (including the cosine term on surfaces).
+    fn f(&self, incident: &Vec3, outgoing: &Vec3) -> S {
+        match self {
+            Scattering::Surface(si) => {
+                let normal = si.info.normal;
+                let c = si
+                    .obj
+                    .bsdf()
+                    .evaluate(&normal, incident, outgoing, BxDFType::ALL);
+
+                c * incident.dot(normal).abs()
+            }
+            Scattering::Medium(_, phase) => S::new_const(phase.evaluate(incident, outgoing)),
+        }
+    }
+}
+
+/// A volumetric path tracer.
+///
+/// Inside media, scattering events are sampled along the rays by their transmittance, where the
+/// lights are sampled with transmittance-aware shadow rays and the path is continued by sampling
+/// the phase function. Surfaces without BxDFs bounding a medium are passed through.
+pub struct VolumetricPath {
+    /// The depth after which paths are terminated by russian roulette
+    pub min_depth: u32,
+    pub max_depth: u32,
+}
+
+impl VolumetricPath {
+    pub fn new(min_depth: u32, max_depth: u32) -> Self {
+        Self {
+            min_depth,
+            max_depth,
+        }
+    }
+
+    /// # Summary
+    /// Samples every light once and estimates the light scattered towards `outgoing`.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `scattering` - The scattering point
+    /// * `outgoing` - The outgoing direction
+    /// * `medium` - The medium the path arrived in
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The scattered radiance
+    fn sample_lights<S: Spectrum>(
+        &self,
+        scene: &Scene<S>,
+        scattering: &Scattering<S>,
+        outgoing: &Vec3,
+        medium: Option<&Arc<dyn Medium<S>>>,
+        sampler: &Arc<dyn Sampler>,
+    ) -> S {
+        let mut illumination = S::black();
+
+        for light in &scene.lights {
+            let emitter_sample = match scattering {
+                Scattering::Surface(si) => light.sample(si, &sampler.get_2d()),
+                Scattering::Medium(point, _) => light.sample_from(point, &sampler.get_2d()),
+            };
+
+            if emitter_sample.pdf == 0.0 || emitter_sample.radiance.is_black() {
+                continue;
+            }
+
+            let incident = emitter_sample.incident;
+            let f = scattering.f(&incident, outgoing);
+            if f.is_black() {
+                continue;
+            }
+
+            let (point, medium) = match scattering {
+                Scattering::Surface(si) => {
+                    (si.info.point, scene.medium_after(si, &incident, medium))
+                }
+                Scattering::Medium(point, _) => (*point, medium.cloned()),
+            };
+
+            let transmittance =
+                emitter_sample
+                    .occlusion_tester
+                    .transmittance(scene, medium.as_ref(), &**sampler);
+            if transmittance.is_black() {
+                continue;
+            }
+
+            let mut pdf = emitter_sample.pdf;
+            // point lights have no falloff in their sample
+            if light.is_delta() {
+                pdf *= (emitter_sample.point - point).mag_sq();
+            }
+
+            illumination += f * emitter_sample.radiance * transmittance / pdf;
+        }
+
+        illumination
+    }
+}
+
+impl<S: Spectrum> Integrator<S> for VolumetricPath {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
+        let mut radiance = S::black();
+        let mut throughput = S::new_const(1.0);
+
+        let mut ray = *primary_ray;
+        let mut medium = scene.atmosphere.clone();
+        let mut specular = true;
+
+        let mut bounce = 0;
+        while bounce < self.max_depth {
+            let hit = scene.intersect(&ray);
+            let outgoing = -ray.direction;
+
+            // sample a scattering event inside the current medium
+            if let Some(m) = &medium {
+                let mut segment = ray;
+                if let Some(si) = &hit {
+                    segment.t_end = si.info.t;
+                }
+
+                let medium_sample = m.sample(&segment, &*sampler);
+                throughput *= medium_sample.beta;
+                if throughput.is_black() {
+                    break;
+                }
+
+                if let Some(point) = medium_sample.point {
+                    let phase = m.phase();
+                    let scattering = Scattering::Medium(point, phase);
+                    radiance += throughput
+                        * self.sample_lights(scene, &scattering, &outgoing, Some(m), &sampler);
+
+                    // the phase function value equals its pdf
+                    let (incident, _) = phase.sample(&outgoing, &sampler.get_2d());
+                    ray = Ray::new(point, incident);
+                    specular = false;
+
+                    bounce += 1;
+                    continue;
+                }
+            }
+
+            let si = match hit {
+                Some(si) => si,
+                None => break,
+            };
+
+            // pass through medium boundaries without counting a bounce
+            if si.obj.is_medium_boundary() {
+                medium = scene.medium_after(&si, &ray.direction, medium.as_ref());
+                ray = si.info.create_ray(ray.direction);
+                continue;
+            }
+
+            let normal = si.info.normal;
+
+            if specular {
+                if let Instance::Emitter(e) = &si.obj {
+                    radiance += throughput * e.radiance(&outgoing, &normal);
+                }
+            }
+
+            let bsdf = si.obj.bsdf();
+            if bsdf.is_type(BxDFType::ALL & !BxDFType::SPECULAR) {
+                let scattering = Scattering::Surface(&si);
+                radiance += throughput
+                    * self.sample_lights(scene, &scattering, &outgoing, medium.as_ref(), &sampler);
+            }
+
+            let bxdf_sample =
+                match bsdf.sample(&normal, &outgoing, BxDFType::ALL, &sampler.get_sample()) {
+                    Some(s) if s.pdf > 0.0 && !s.spectrum.is_black() => s,
+                    _ => break,
+                };
+
+            specular = bxdf_sample.typ.is_specular();
+            let dot = if specular {
+                1.0
+            } else {
+                bxdf_sample.incident.dot(normal).abs()
+            };
+
+            throughput *= bxdf_sample.spectrum * (dot / bxdf_sample.pdf);
+            medium = scene.medium_after(&si, &bxdf_sample.incident, medium.as_ref());
+            ray = si.info.create_ray(bxdf_sample.incident);
+
+            bounce += 1;
+            if bounce > self.min_depth {
+                let q = f32::max(0.05, 1.0 - throughput.luminance());
+                if sampler.get_1d() < q {
+                    break;
+                }
+
+                throughput /= 1.0 - q;
+            }
+        }
+
+        radiance
+    }
+
+    fn illumination(
+        &self,
+        _: &Scene<S>,
+        _: &SceneIntersection<S>,
+        _: Arc<dyn Sampler>,
+        _: u32,
+    ) -> S {
+        unreachable!()
+    }
+}
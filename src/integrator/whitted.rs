@@ -4,7 +4,6 @@ use crate::render::objects::Instance;
 use crate::render::scene::{Scene, SceneIntersection};
 use crate::sampler::Sampler;
 use crate::Spectrum;
-use color::Color;
 use geometry::ray::Ray;
 use std::sync::Arc;
 
@@ -19,22 +18,22 @@ impl Whitted {
 }
 
 #[allow(unused_variables)]
-impl Integrator for Whitted {
-    fn integrate(&self, scene: &Scene, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> Spectrum {
+impl<S: Spectrum> Integrator<S> for Whitted {
+    fn integrate(&self, scene: &Scene<S>, primary_ray: &Ray, sampler: Arc<dyn Sampler>) -> S {
         if let Some(si) = scene.intersect(primary_ray) {
             self.illumination(scene, &si, sampler, self.max_depth)
         } else {
-            Spectrum::black()
+            S::black()
         }
     }
 
     fn illumination(
         &self,
-        scene: &Scene,
-        intersection: &SceneIntersection,
+        scene: &Scene<S>,
+        intersection: &SceneIntersection<S>,
         sampler: Arc<dyn Sampler>,
         depth: u32,
-    ) -> Spectrum {
+    ) -> S {
         let outgoing = -intersection.info.ray.direction;
 
         let (obj, emitter) = match &intersection.obj {
@@ -46,7 +45,7 @@ impl Integrator for Whitted {
         let point = &intersection.info.point;
         let normal = &intersection.info.normal;
 
-        let mut illumination = Spectrum::black();
+        let mut illumination = S::black();
 
         if depth == self.max_depth {
             if let Some(e) = emitter {
@@ -4,6 +4,7 @@
 #[macro_use]
 extern crate bitflags;
 
+use crate::bxdf::fluorescence::Reradiation;
 use color::*;
 
 pub mod bxdf;
@@ -11,10 +12,41 @@ pub mod configuration;
 mod demo_scenes;
 pub mod integrator;
 pub mod mc;
+pub mod medium;
+pub mod polarization;
 pub mod render;
 pub mod sampler;
+mod tests;
 
-pub type Spectrum = Srgb;
+/// The colors light is transported in. The renderer is generic over them, such that the color
+/// (e.g. RGB or spectral) is chosen at runtime.
+///
+/// Besides the colors themselves, this provides the parts of the light transport only some
+/// colors can represent.
+pub trait Spectrum: Color + Send + Sync + 'static {
+    /// # Summary
+    /// Re-radiates light at other wavelengths (fluorescence).
+    ///
+    /// # Arguments
+    /// * `reradiation` - The re-radiation matrix
+    /// * `radiance` - The incident light
+    ///
+    /// # Results
+    /// * `Option<Self>` - The re-radiated light or `None` if this color cannot represent it
+    fn reradiate(_reradiation: &Reradiation, _radiance: &Self) -> Option<Self> {
+        None
+    }
+}
+
+impl Spectrum for Srgb {}
+
+impl Spectrum for Xyz {}
+
+impl Spectrum for Spectral {
+    fn reradiate(reradiation: &Reradiation, radiance: &Self) -> Option<Self> {
+        Some(reradiation.reradiate(radiance))
+    }
+}
 
 pub const LIGHT_SAMPLES_1D: usize = 2;
 pub const LIGHT_SAMPLES_3D: usize = LIGHT_SAMPLES_1D * LIGHT_SAMPLES_1D * LIGHT_SAMPLES_1D;
@@ -1,4 +1,5 @@
 use geometry::CoordinateSystem;
+use std::cmp::Ordering;
 use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 use ultraviolet::{Lerp, Vec2, Vec3};
 
@@ -69,6 +70,15 @@ pub fn uniform_sample_sphere(sample: &Vec2) -> Vec3 {
     Vec3::new(phi.cos() * r, phi.sin() * r, z)
 }
 
+/// # Summary
+/// Computes the pdf for uniformly sampling a sphere.
+///
+/// # Results
+/// * `f32` - The pdf
+pub fn uniform_sphere_pdf() -> f32 {
+    1.0 / (4.0 * PI)
+}
+
 /// # Summary
 /// Samples a cone around the `(0, 1, 0)` axis with a uniform distribution described by the sample.
 ///
@@ -119,3 +129,122 @@ pub fn uniform_sample_cone_frame(
 pub fn uniform_cone_pdf(cos_theta: f32) -> f32 {
     1.0 / (2.0 * PI * (1.0 - cos_theta))
 }
+
+/// # Summary
+/// Computes the multiple importance sampling weight of the power heuristic (with exponent 2).
+///
+/// # Arguments
+/// * `num_f` - The number of samples taken from distribution `f`
+/// * `pdf_f` - The pdf of the sample in distribution `f`
+/// * `num_g` - The number of samples taken from distribution `g`
+/// * `pdf_g` - The pdf of the sample in distribution `g`
+///
+/// # Results
+/// * `f32` - The weight of the sample taken from `f`
+pub fn power_heuristic(num_f: usize, pdf_f: f32, num_g: usize, pdf_g: f32) -> f32 {
+    let f = num_f as f32 * pdf_f;
+    let g = num_g as f32 * pdf_g;
+
+    if f == 0.0 && g == 0.0 {
+        0.0
+    } else {
+        (f * f) / (f * f + g * g)
+    }
+}
+
+/// A piecewise-constant 1D distribution, e.g. for sampling discrete items by their weight.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    func_int: f32,
+}
+
+impl Distribution1D {
+    /// # Summary
+    /// Creates a new distribution proportional to the given (non-negative) function values.
+    ///
+    /// # Arguments
+    /// * `func` - The function values
+    ///
+    /// # Results
+    /// * `Self` - The distribution
+    pub fn new(func: &[f32]) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1] / n as f32;
+        }
+
+        let func_int = cdf[n];
+        if func_int == 0.0 {
+            // fall back to a uniform distribution
+            for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+                *c = i as f32 / n as f32;
+            }
+        } else {
+            cdf.iter_mut().skip(1).for_each(|c| *c /= func_int);
+        }
+
+        Self {
+            func: func.to_vec(),
+            cdf,
+            func_int,
+        }
+    }
+
+    /// Returns the number of function values.
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Returns the integral of the function.
+    pub fn func_int(&self) -> f32 {
+        self.func_int
+    }
+
+    /// # Summary
+    /// Samples a discrete index of the function values.
+    ///
+    /// # Arguments
+    /// * `sample` - A random sample in `[0, 1]`
+    ///
+    /// # Results
+    /// * `Option<(usize, f32)>` - The sampled index and its probability, `None` if empty
+    pub fn sample_discrete(&self, sample: f32) -> Option<(usize, f32)> {
+        if self.func.is_empty() {
+            return None;
+        }
+
+        // the last cdf entry not greater than the sample (never equal, such that the search
+        // always ends after it)
+        let index = match self.cdf.binary_search_by(|c| {
+            if *c <= sample {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }) {
+            Ok(index) | Err(index) => index,
+        };
+        let index = index.saturating_sub(1).min(self.count() - 1);
+
+        Some((index, self.discrete_pdf(index)))
+    }
+
+    /// # Summary
+    /// Computes the probability of sampling the given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index
+    ///
+    /// # Results
+    /// * `f32` - The probability
+    pub fn discrete_pdf(&self, index: usize) -> f32 {
+        if self.func_int == 0.0 {
+            1.0 / self.count() as f32
+        } else {
+            self.func[index] / (self.func_int * self.count() as f32)
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::medium::phase::{HenyeyGreenstein, PhaseFunction};
+use crate::medium::{Medium, MediumSample};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::aabb::Aabb;
+use geometry::ray::Ray;
+use std::convert::TryInto;
+use ultraviolet::Vec3;
+
+/// A dense grid of densities, stored with x varying fastest, then y, then z.
+#[derive(Debug, Clone)]
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    data: Vec<f32>,
+}
+
+impl DensityGrid {
+    /// # Summary
+    /// Creates a new density grid.
+    ///
+    /// # Arguments
+    /// * `nx` - The number of voxels in x direction
+    /// * `ny` - The number of voxels in y direction
+    /// * `nz` - The number of voxels in z direction
+    /// * `data` - The `nx * ny * nz` non-negative densities
+    ///
+    /// # Results
+    /// * `Self` - The grid
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f32>) -> Self {
+        assert_eq!(
+            nx * ny * nz,
+            data.len(),
+            "Grid size does not match its data"
+        );
+        debug_assert!(data.iter().all(|d| *d >= 0.0));
+
+        Self { nx, ny, nz, data }
+    }
+
+    /// # Summary
+    /// Loads a density grid from a raw file.
+    /// The file consists of the resolution `nx`, `ny`, `nz` as little endian `u32`, followed by
+    /// the `nx * ny * nz` densities as little endian `f32`.
+    ///
+    /// # Arguments
+    /// * `file_name` - The path of the file
+    ///
+    /// # Results
+    /// * `Result<Self, String>` - The grid or the reason it could not be loaded
+    pub fn load(file_name: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(file_name).map_err(|e| format!("{}: {}", file_name, e))?;
+        if bytes.len() < 12 {
+            return Err(format!("{}: Missing grid resolution", file_name));
+        }
+
+        let words: Vec<[u8; 4]> = bytes
+            .chunks_exact(4)
+            .map(|c| c.try_into().expect("Chunk is not 4 bytes"))
+            .collect();
+
+        let nx = u32::from_le_bytes(words[0]) as usize;
+        let ny = u32::from_le_bytes(words[1]) as usize;
+        let nz = u32::from_le_bytes(words[2]) as usize;
+
+        let data: Vec<f32> = words[3..].iter().map(|w| f32::from_le_bytes(*w)).collect();
+        if data.len() != nx * ny * nz || bytes.len() % 4 != 0 {
+            return Err(format!(
+                "{}: Expected {} densities, found {}",
+                file_name,
+                nx * ny * nz,
+                data.len()
+            ));
+        }
+        if data.iter().any(|d| d.is_nan() || *d < 0.0) {
+            return Err(format!("{}: Densities must be non-negative", file_name));
+        }
+
+        Ok(Self::new(nx, ny, nz, data))
+    }
+
+    /// The maximum density of the grid.
+    pub fn max(&self) -> f32 {
+        self.data.iter().copied().fold(0.0, f32::max)
+    }
+
+    #[inline]
+    fn voxel(&self, x: isize, y: isize, z: isize) -> f32 {
+        if x < 0
+            || y < 0
+            || z < 0
+            || x as usize >= self.nx
+            || y as usize >= self.ny
+            || z as usize >= self.nz
+        {
+            0.0
+        } else {
+            self.data[(z as usize * self.ny + y as usize) * self.nx + x as usize]
+        }
+    }
+
+    /// # Summary
+    /// Looks up the density by trilinear interpolation of the voxel centers.
+    ///
+    /// # Arguments
+    /// * `point` - The point in grid space `[0, 1]^3`
+    ///
+    /// # Results
+    /// * `f32` - The density (`0` outside the grid)
+    pub fn lookup(&self, point: &Vec3) -> f32 {
+        let x = point.x * self.nx as f32 - 0.5;
+        let y = point.y * self.ny as f32 - 0.5;
+        let z = point.z * self.nz as f32 - 0.5;
+
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (dx, dy, dz) = (x - x0, y - y0, z - z0);
+        let (x0, y0, z0) = (x0 as isize, y0 as isize, z0 as isize);
+
+        let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+
+        let d00 = lerp(dx, self.voxel(x0, y0, z0), self.voxel(x0 + 1, y0, z0));
+        let d10 = lerp(
+            dx,
+            self.voxel(x0, y0 + 1, z0),
+            self.voxel(x0 + 1, y0 + 1, z0),
+        );
+        let d01 = lerp(
+            dx,
+            self.voxel(x0, y0, z0 + 1),
+            self.voxel(x0 + 1, y0, z0 + 1),
+        );
+        let d11 = lerp(
+            dx,
+            self.voxel(x0, y0 + 1, z0 + 1),
+            self.voxel(x0 + 1, y0 + 1, z0 + 1),
+        );
+
+        let d0 = lerp(dy, d00, d10);
+        let d1 = lerp(dy, d01, d11);
+
+        lerp(dz, d0, d1)
+    }
+}
+
+/// A heterogeneous medium with densities given by a grid, scaling the absorption and scattering
+/// coefficients.
+///
+/// Tentative collisions are sampled against a grey majorant, bounding the extinction of every
+/// channel. Scattering events are sampled by spectral tracking, which weights each channel by
+/// its own ratio of real and null collisions, and the transmittance is estimated by ratio
+/// tracking per channel.
+#[derive(Debug)]
+pub struct GridMedium<S> {
+    pub sigma_a: S,
+    pub sigma_s: S,
+    grid: DensityGrid,
+    bounds: Aabb,
+    sigma_t: S,
+    /// The maximum extinction coefficient over all channels
+    max_sigma_t: f32,
+    inv_max_density: f32,
+    phase: HenyeyGreenstein,
+}
+
+impl<S: Spectrum> GridMedium<S> {
+    /// # Summary
+    /// Creates a new grid medium.
+    ///
+    /// # Arguments
+    /// * `grid` - The density grid
+    /// * `bounds` - The world space bounds the grid is stretched to
+    /// * `sigma_a` - The absorption coefficient at density `1`
+    /// * `sigma_s` - The scattering coefficient at density `1`
+    /// * `g` - The asymmetry parameter of the Henyey-Greenstein phase function
+    ///
+    /// # Results
+    /// * `Self` - The medium
+    pub fn new(grid: DensityGrid, bounds: Aabb, sigma_a: S, sigma_s: S, g: f32) -> Self {
+        let sigma_t = sigma_a + sigma_s;
+        let max_sigma_t = (0..S::SIZE).map(|i| sigma_t[i]).fold(0.0, f32::max);
+        let max_density = grid.max();
+        let inv_max_density = if max_density > 0.0 {
+            1.0 / max_density
+        } else {
+            0.0
+        };
+
+        Self {
+            sigma_a,
+            sigma_s,
+            grid,
+            bounds,
+            sigma_t,
+            max_sigma_t,
+            inv_max_density,
+            phase: HenyeyGreenstein::new(g),
+        }
+    }
+
+    #[inline]
+    fn density(&self, point: &Vec3) -> f32 {
+        let local = (*point - self.bounds.min) / self.bounds.size();
+
+        self.grid.lookup(&local)
+    }
+
+    /// Clips the ray segment to the bounds of the grid.
+    fn clip(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let t1 = (self.bounds.min - ray.origin) / ray.direction;
+        let t2 = (self.bounds.max - ray.origin) / ray.direction;
+
+        let t_min_vec = t1.min_by_component(t2);
+        let t_max_vec = t1.max_by_component(t2);
+
+        let t_min = f32::max(ray.t_start, t_min_vec.component_max());
+        let t_max = f32::min(ray.t_end, t_max_vec.component_min());
+
+        if t_min < t_max {
+            Some((t_min, t_max))
+        } else {
+            None
+        }
+    }
+
+    /// The majorant of the extinction of all channels inside the grid.
+    #[inline]
+    fn majorant(&self) -> f32 {
+        self.max_sigma_t / self.inv_max_density
+    }
+}
+
+impl<S: Spectrum> Medium<S> for GridMedium<S> {
+    fn transmittance(&self, ray: &Ray, sampler: &dyn Sampler) -> S {
+        let (mut t, t_max) = match self.clip(ray) {
+            Some(segment) if self.inv_max_density > 0.0 && self.max_sigma_t > 0.0 => segment,
+            _ => return S::new_const(1.0),
+        };
+
+        let majorant = self.majorant();
+        let mut transmittance = S::new_const(1.0);
+
+        loop {
+            t -= f32::ln(1.0 - sampler.get_1d()) / majorant;
+            if t >= t_max {
+                break;
+            }
+
+            // the ratio of null collisions per channel
+            let sigma_t = self.sigma_t * self.density(&ray.at(t));
+            transmittance *= (S::new_const(majorant) - sigma_t).clamp(0.0, majorant) / majorant;
+        }
+
+        transmittance
+    }
+
+    fn sample(&self, ray: &Ray, sampler: &dyn Sampler) -> MediumSample<S> {
+        let (mut t, t_max) = match self.clip(ray) {
+            Some(segment) if self.inv_max_density > 0.0 && self.max_sigma_t > 0.0 => segment,
+            _ => return MediumSample::new(None, S::new_const(1.0)),
+        };
+
+        let majorant = self.majorant();
+        let mut beta = S::new_const(1.0);
+
+        loop {
+            t -= f32::ln(1.0 - sampler.get_1d()) / majorant;
+            if t >= t_max {
+                return MediumSample::new(None, beta);
+            }
+
+            let point = ray.at(t);
+            let density = self.density(&point);
+            let sigma_s = self.sigma_s * density;
+            let sigma_n = (S::new_const(majorant) - self.sigma_t * density).clamp(0.0, majorant);
+
+            // choose between scattering and a null collision by the average over all channels,
+            // absorption only weights the path
+            let scattering = (beta * sigma_s).avg();
+            let null = (beta * sigma_n).avg();
+            if scattering + null <= 0.0 {
+                return MediumSample::new(None, S::black());
+            }
+
+            let p_scatter = scattering / (scattering + null);
+            if sampler.get_1d() < p_scatter {
+                beta *= sigma_s / (majorant * p_scatter);
+
+                return MediumSample::new(Some(point), beta);
+            }
+
+            beta *= sigma_n / (majorant * (1.0 - p_scatter));
+        }
+    }
+
+    fn phase(&self) -> &dyn PhaseFunction {
+        &self.phase
+    }
+}
@@ -0,0 +1,80 @@
+use crate::medium::phase::{HenyeyGreenstein, PhaseFunction};
+use crate::medium::{Medium, MediumSample};
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+
+/// A medium with constant absorption and scattering coefficients.
+#[derive(Debug)]
+pub struct HomogeneousMedium<S> {
+    pub sigma_a: S,
+    pub sigma_s: S,
+    sigma_t: S,
+    phase: HenyeyGreenstein,
+}
+
+impl<S: Spectrum> HomogeneousMedium<S> {
+    /// # Summary
+    /// Creates a new homogeneous medium.
+    ///
+    /// # Arguments
+    /// * `sigma_a` - The absorption coefficient (per unit distance)
+    /// * `sigma_s` - The scattering coefficient (per unit distance)
+    /// * `g` - The asymmetry parameter of the Henyey-Greenstein phase function
+    ///
+    /// # Results
+    /// * `Self` - The medium
+    pub fn new(sigma_a: S, sigma_s: S, g: f32) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            sigma_t: sigma_a + sigma_s,
+            phase: HenyeyGreenstein::new(g),
+        }
+    }
+
+    #[inline]
+    fn segment(ray: &Ray) -> f32 {
+        (ray.t_end - ray.t_start).clamp(0.0, f32::MAX)
+    }
+}
+
+impl<S: Spectrum> Medium<S> for HomogeneousMedium<S> {
+    fn transmittance(&self, ray: &Ray, _: &dyn Sampler) -> S {
+        (self.sigma_t * -Self::segment(ray)).exp()
+    }
+
+    fn sample(&self, ray: &Ray, sampler: &dyn Sampler) -> MediumSample<S> {
+        let segment = Self::segment(ray);
+
+        // sample a channel and a distance along the ray
+        let channels = S::SIZE;
+        let channel = usize::min((sampler.get_1d() * channels as f32) as usize, channels - 1);
+        let distance = -f32::ln(1.0 - sampler.get_1d()) / self.sigma_t[channel];
+
+        let scattered = distance < segment;
+        let transmittance = (self.sigma_t * -f32::min(distance, segment)).exp();
+
+        // the pdf is averaged over all channels
+        let density = if scattered {
+            self.sigma_t * transmittance
+        } else {
+            transmittance
+        };
+        let pdf = density.avg();
+        if pdf == 0.0 {
+            return MediumSample::new(None, S::new_const(0.0));
+        }
+
+        if scattered {
+            let point = ray.at(ray.t_start + distance);
+            MediumSample::new(Some(point), transmittance * self.sigma_s / pdf)
+        } else {
+            MediumSample::new(None, transmittance / pdf)
+        }
+    }
+
+    fn phase(&self) -> &dyn PhaseFunction {
+        &self.phase
+    }
+}
@@ -0,0 +1,58 @@
+use crate::medium::phase::PhaseFunction;
+use crate::sampler::Sampler;
+use crate::Spectrum;
+use geometry::ray::Ray;
+use std::fmt::Debug;
+use ultraviolet::Vec3;
+
+pub mod grid;
+pub mod homogeneous;
+pub mod phase;
+pub mod subsurface;
+
+/// A participating medium absorbing and scattering light along rays.
+///
+/// Rays passed to a medium are expected to be normalized and to lie within the medium between
+/// `t_start` and `t_end`.
+pub trait Medium<S: Spectrum>: Debug + Send + Sync {
+    /// # Summary
+    /// Computes the transmittance along the ray between `t_start` and `t_end`.
+    ///
+    /// # Arguments
+    /// * `ray` - The ray segment inside the medium
+    /// * `sampler` - The sampler to use (for stochastic estimates)
+    ///
+    /// # Results
+    /// * `S` - The transmittance
+    fn transmittance(&self, ray: &Ray, sampler: &dyn Sampler) -> S;
+
+    /// # Summary
+    /// Samples a scattering event along the ray between `t_start` and `t_end`.
+    ///
+    /// # Arguments
+    /// * `ray` - The ray segment inside the medium
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `MediumSample<S>` - The scattering point (if any) and the throughput weight of the
+    ///   sample
+    fn sample(&self, ray: &Ray, sampler: &dyn Sampler) -> MediumSample<S>;
+
+    /// The phase function of the medium.
+    fn phase(&self) -> &dyn PhaseFunction;
+}
+
+/// # Summary
+/// Contains of
+/// * `point` - The sampled scattering point, `None` if the ray passed the medium
+/// * `beta` - The throughput weight of the sample
+pub struct MediumSample<S> {
+    pub point: Option<Vec3>,
+    pub beta: S,
+}
+
+impl<S> MediumSample<S> {
+    pub fn new(point: Option<Vec3>, beta: S) -> Self {
+        Self { point, beta }
+    }
+}
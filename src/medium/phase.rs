@@ -0,0 +1,82 @@
+use geometry::CoordinateSystem;
+use std::f32::consts::PI;
+use std::fmt::Debug;
+use ultraviolet::{Vec2, Vec3};
+
+/// A phase function describes the angular distribution of light scattered in a medium.
+///
+/// Both directions point away from the scattering point.
+pub trait PhaseFunction: Debug + Send + Sync {
+    /// # Summary
+    /// Evaluates the phase function for the given pair of directions.
+    ///
+    /// # Arguments
+    /// * `incident` - The incident direction
+    /// * `outgoing` - The outgoing direction
+    ///
+    /// # Results
+    /// * `f32` - The value of the phase function
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> f32;
+
+    /// # Summary
+    /// Samples an incident direction of the phase function.
+    ///
+    /// # Arguments
+    /// * `outgoing` - The outgoing direction
+    /// * `sample` - A random sample in `[0, 1]`
+    ///
+    /// # Results
+    /// * `(Vec3, f32)` - The incident direction and the phase function value (equal to its pdf)
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> (Vec3, f32);
+}
+
+/// The Henyey-Greenstein phase function.
+#[derive(Debug, Copy, Clone)]
+pub struct HenyeyGreenstein {
+    /// The asymmetry parameter in `(-1, 1)`. Positive values scatter forward, negative values
+    /// backward, `0` is isotropic.
+    pub g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f32) -> Self {
+        debug_assert!(g > -1.0 && g < 1.0);
+
+        Self { g }
+    }
+
+    #[inline]
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let denom = 1.0 + self.g * self.g + 2.0 * self.g * cos_theta;
+
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.max(0.0).sqrt())
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn evaluate(&self, incident: &Vec3, outgoing: &Vec3) -> f32 {
+        self.phase(incident.dot(*outgoing))
+    }
+
+    fn sample(&self, outgoing: &Vec3, sample: &Vec2) -> (Vec3, f32) {
+        let g = self.g;
+
+        // the angle to the forward direction (-outgoing)
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * sample.x
+        } else {
+            let sqr_term = (1.0 - g * g) / (1.0 - g + 2.0 * g * sample.x);
+            (1.0 + g * g - sqr_term * sqr_term) / (2.0 * g)
+        };
+
+        let sin_theta = f32::max(0.0, 1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * sample.y;
+
+        let frame = CoordinateSystem::from(&-*outgoing);
+        let incident = frame.e1 * (sin_theta * phi.cos())
+            + frame.e2 * (sin_theta * phi.sin())
+            + frame.e3 * cos_theta;
+
+        (incident, self.phase(-cos_theta))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::medium::homogeneous::HomogeneousMedium;
+use crate::Spectrum;
+
+/// A subsurface scattering material, rendered by random walks through a homogeneous medium
+/// inside the object (e.g. skin, wax or marble).
+///
+/// The medium is parameterized artistically by the albedo of the surface and the mean free path
+/// of light inside the object. The boundary is index-matched, i.e. the object should use an empty
+/// BSDF, such that light enters and leaves the medium unrefracted.
+#[derive(Debug, Copy, Clone)]
+pub struct SubsurfaceMaterial<S> {
+    /// The (multiple scattering) albedo the surface should appear with
+    pub albedo: S,
+    /// The average distance light travels between two interactions
+    pub mean_free_path: S,
+    /// The asymmetry parameter of the Henyey-Greenstein phase function
+    pub g: f32,
+}
+
+impl<S: Spectrum> SubsurfaceMaterial<S> {
+    pub fn new(albedo: S, mean_free_path: S, g: f32) -> Self {
+        Self {
+            albedo,
+            mean_free_path,
+            g,
+        }
+    }
+
+    /// # Summary
+    /// Computes the absorption and scattering coefficients of the interior medium.
+    /// The single scattering albedo is inverted from the desired multiple scattering albedo with
+    /// the fit of Chiang et al. (2016).
+    ///
+    /// # Results
+    /// * `(S, S)` - The absorption and scattering coefficients
+    pub fn coefficients(&self) -> (S, S) {
+        let mut sigma_a = S::new_const(0.0);
+        let mut sigma_s = S::new_const(0.0);
+
+        for i in 0..S::SIZE {
+            let a = self.albedo[i].clamp(0.0, 1.0);
+            let fit = 4.09712 + 4.20863 * a - f32::sqrt(9.59217 + 41.6808 * a + 17.7126 * a * a);
+            let single_albedo = 1.0 - fit * fit;
+
+            let sigma_t = 1.0 / self.mean_free_path[i].max(f32::EPSILON);
+            sigma_s[i] = single_albedo * sigma_t;
+            sigma_a[i] = sigma_t - sigma_s[i];
+        }
+
+        (sigma_a, sigma_s)
+    }
+
+    /// Creates the medium inside the object.
+    pub fn medium(&self) -> HomogeneousMedium<S> {
+        let (sigma_a, sigma_s) = self.coefficients();
+
+        HomogeneousMedium::new(sigma_a, sigma_s, self.g)
+    }
+}
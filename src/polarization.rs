@@ -0,0 +1,410 @@
+//! Polarization of light with Stokes vectors and Mueller matrices.
+//!
+//! Stokes vectors `(S0, S1, S2, S3)` describe the intensity, the linear polarization along the
+//! horizontal (S1) and the diagonal (S2) axis and the circular polarization (S3) of light with
+//! respect to a reference frame perpendicular to the direction of propagation.
+//! Mueller matrices transform Stokes vectors at interactions.
+
+use crate::render::film::Film;
+use crate::Spectrum;
+use std::ops::{Add, Div, Mul, Sub};
+use ultraviolet::{Vec2, Vec3};
+use util::floats;
+
+/// A complex number, as needed for the Fresnel amplitudes of absorbing materials or total
+/// internal reflection.
+#[derive(Debug, Copy, Clone)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn norm_sqr(&self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Returns the principal square root.
+    pub fn sqrt(&self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = ((norm + self.re) * 0.5).max(0.0).sqrt();
+        let im = ((norm - self.re) * 0.5).max(0.0).sqrt();
+
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl From<f32> for Complex {
+    fn from(re: f32) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let norm = rhs.norm_sqr();
+        let num = self * rhs.conj();
+
+        Self::new(num.re / norm, num.im / norm)
+    }
+}
+
+/// # Summary
+/// Computes the Fresnel amplitude coefficients of the s- and p-polarized parts.
+///
+/// # Arguments
+/// * `cos_i` - The cosine of the incident angle, in `[0, 1]`
+/// * `eta` - The relative (complex) refractive index of the medium the light enters
+///
+/// # Results
+/// * `(Complex, Complex, Complex, Complex)` - The reflected `r_s` and `r_p` and the transmitted
+///   `t_s` and `t_p` amplitudes
+pub fn fresnel_amplitudes(cos_i: f32, eta: Complex) -> (Complex, Complex, Complex, Complex) {
+    let cos_i = floats::fast_clamp(cos_i, 0.0, 1.0);
+    let sin2_i = 1.0 - cos_i * cos_i;
+
+    // Snell's law, complex on absorption or total internal reflection
+    let cos_t = (Complex::from(1.0) - Complex::from(sin2_i) / (eta * eta)).sqrt();
+    let cos_i = Complex::from(cos_i);
+
+    let r_s = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let r_p = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let t_s = (cos_i * 2.0.into()) / (cos_i + eta * cos_t);
+    let t_p = (cos_i * 2.0.into()) / (eta * cos_i + cos_t);
+
+    (r_s, r_p, t_s, t_p)
+}
+
+/// A Stokes vector of every color channel.
+#[derive(Debug, Copy, Clone)]
+pub struct Stokes<S> {
+    pub s: [S; 4],
+}
+
+impl<S: Spectrum> Stokes<S> {
+    pub fn black() -> Self {
+        Self { s: [S::black(); 4] }
+    }
+
+    /// Creates the Stokes vector of unpolarized light.
+    pub fn unpolarized(intensity: S) -> Self {
+        let mut stokes = Self::black();
+        stokes.s[0] = intensity;
+
+        stokes
+    }
+}
+
+impl<S: Spectrum> Add for Stokes<S> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        for i in 0..4 {
+            self.s[i] += rhs.s[i];
+        }
+
+        self
+    }
+}
+
+/// A Mueller matrix of every color channel, indexed by row and column.
+#[derive(Debug, Copy, Clone)]
+pub struct Mueller<S> {
+    pub m: [[S; 4]; 4],
+}
+
+impl<S: Spectrum> Mueller<S> {
+    pub fn black() -> Self {
+        Self {
+            m: [[S::black(); 4]; 4],
+        }
+    }
+
+    /// Creates the identity, an interaction not affecting polarization.
+    pub fn identity() -> Self {
+        let mut mueller = Self::black();
+        for i in 0..4 {
+            mueller.m[i][i] = S::new_const(1.0);
+        }
+
+        mueller
+    }
+
+    /// Creates an ideal depolarizer, which keeps the intensity only.
+    pub fn depolarizer() -> Self {
+        let mut mueller = Self::black();
+        mueller.m[0][0] = S::new_const(1.0);
+
+        mueller
+    }
+
+    /// # Summary
+    /// Creates the matrix rotating the reference frame of a Stokes vector.
+    ///
+    /// # Arguments
+    /// * `phi` - The counter-clockwise angle of rotation, looking against the direction of
+    ///   propagation
+    ///
+    /// # Results
+    /// * `Self` - The rotator
+    pub fn rotator(phi: f32) -> Self {
+        let (sin, cos) = (2.0 * phi).sin_cos();
+
+        let mut mueller = Self::identity();
+        mueller.m[1][1] = S::new_const(cos);
+        mueller.m[1][2] = S::new_const(sin);
+        mueller.m[2][1] = S::new_const(-sin);
+        mueller.m[2][2] = S::new_const(cos);
+
+        mueller
+    }
+
+    /// # Summary
+    /// Creates the matrix rotating the reference frame from one axis to another.
+    ///
+    /// # Arguments
+    /// * `from` - The current reference axis, perpendicular to `direction`
+    /// * `to` - The new reference axis, perpendicular to `direction`
+    /// * `direction` - The direction of propagation
+    ///
+    /// # Results
+    /// * `Self` - The rotator
+    pub fn rotate_frame(from: &Vec3, to: &Vec3, direction: &Vec3) -> Self {
+        let phi = f32::atan2(from.cross(*to).dot(*direction), from.dot(*to));
+
+        Self::rotator(phi)
+    }
+
+    /// # Summary
+    /// Creates the matrix of an interaction, given by the amplitude coefficients of the s- and
+    /// p-polarized parts of every color channel.
+    /// The matrix is normalized to an intensity of `1`, such that it only describes the change
+    /// of polarization.
+    ///
+    /// # Arguments
+    /// * `amplitudes` - The amplitudes `(s, p)` of a color channel
+    ///
+    /// # Results
+    /// * `Self` - The normalized matrix in the s/p reference frame
+    pub fn from_amplitudes<F>(amplitudes: F) -> Self
+    where
+        F: Fn(usize) -> (Complex, Complex),
+    {
+        let mut mueller = Self::identity();
+
+        for i in 0..S::SIZE {
+            let (s, p) = amplitudes(i);
+
+            let a = (s.norm_sqr() + p.norm_sqr()) * 0.5;
+            if a == 0.0 {
+                continue;
+            }
+
+            let b = (s.norm_sqr() - p.norm_sqr()) * 0.5;
+            let sp = s * p.conj();
+
+            mueller.m[0][1][i] = b / a;
+            mueller.m[1][0][i] = b / a;
+            mueller.m[2][2][i] = sp.re / a;
+            mueller.m[2][3][i] = sp.im / a;
+            mueller.m[3][2][i] = -sp.im / a;
+            mueller.m[3][3][i] = sp.re / a;
+        }
+
+        mueller
+    }
+
+    /// Scales every element by the given spectrum.
+    pub fn scaled(mut self, spectrum: S) -> Self {
+        self.m
+            .iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|e| *e *= spectrum);
+
+        self
+    }
+
+    /// # Summary
+    /// Applies this matrix to unpolarized light.
+    ///
+    /// # Arguments
+    /// * `intensity` - The intensity of the light
+    ///
+    /// # Results
+    /// * `Stokes<S>` - The resulting Stokes vector
+    pub fn apply_unpolarized(&self, intensity: S) -> Stokes<S> {
+        Stokes {
+            s: [
+                self.m[0][0] * intensity,
+                self.m[1][0] * intensity,
+                self.m[2][0] * intensity,
+                self.m[3][0] * intensity,
+            ],
+        }
+    }
+}
+
+impl<S: Spectrum> Mul for Mueller<S> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut mueller = Self::black();
+
+        for row in 0..4 {
+            for col in 0..4 {
+                mueller.m[row][col] = (0..4).map(|k| self.m[row][k] * rhs.m[k][col]).sum();
+            }
+        }
+
+        mueller
+    }
+}
+
+/// A film accumulating the polarization components `S1` to `S3` of every sample, whose
+/// intensity `S0` is accumulated by the renderer.
+pub struct StokesFilm<S> {
+    components: [Film<S>; 3],
+}
+
+impl<S: Spectrum> StokesFilm<S> {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            components: [
+                Film::new(width, height),
+                Film::new(width, height),
+                Film::new(width, height),
+            ],
+        }
+    }
+
+    /// # Summary
+    /// Adds the polarization components of the given Stokes vector to the pixel at the raster
+    /// position.
+    ///
+    /// # Arguments
+    /// * `raster` - The raster position
+    /// * `stokes` - The contribution to add
+    pub fn splat(&self, raster: &Vec2, stokes: &Stokes<S>) {
+        for i in 0..3 {
+            self.components[i].splat(raster, stokes.s[i + 1]);
+        }
+    }
+
+    /// Returns the accumulated polarization components `S1` to `S3` of the given pixel.
+    pub fn get(&self, x: u32, y: u32) -> [S; 3] {
+        [
+            self.components[0].get(x, y),
+            self.components[1].get(x, y),
+            self.components[2].get(x, y),
+        ]
+    }
+
+    pub fn reset(&self) {
+        self.components.iter().for_each(Film::reset);
+    }
+}
+
+/// The images derived from the Stokes vectors of a rendering.
+/// The intensity `S0` is the rendering itself.
+#[derive(Debug, Copy, Clone)]
+pub enum StokesImage {
+    /// The horizontal linear polarization `S1 / S0`, mapped from `[-1, 1]` to `[0, 1]`
+    S1,
+    /// The diagonal linear polarization `S2 / S0`, mapped from `[-1, 1]` to `[0, 1]`
+    S2,
+    /// The circular polarization `S3 / S0`, mapped from `[-1, 1]` to `[0, 1]`
+    S3,
+    /// The degree of polarization in `[0, 1]`
+    DegreeOfPolarization,
+    /// The angle of linear polarization, mapped from `[-π/2, π/2]` to `[0, 1]`
+    AngleOfPolarization,
+}
+
+impl StokesImage {
+    pub const ALL: [StokesImage; 5] = [
+        StokesImage::S1,
+        StokesImage::S2,
+        StokesImage::S3,
+        StokesImage::DegreeOfPolarization,
+        StokesImage::AngleOfPolarization,
+    ];
+
+    /// Returns the suffix of the file name of the image.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            StokesImage::S1 => "s1",
+            StokesImage::S2 => "s2",
+            StokesImage::S3 => "s3",
+            StokesImage::DegreeOfPolarization => "dop",
+            StokesImage::AngleOfPolarization => "aop",
+        }
+    }
+
+    /// # Summary
+    /// Computes the value of the image for a Stokes vector of a single color channel.
+    ///
+    /// # Arguments
+    /// * `s` - The Stokes vector
+    ///
+    /// # Results
+    /// * `f32` - The value in `[0, 1]`
+    pub fn value(&self, s: [f32; 4]) -> f32 {
+        if s[0] <= 0.0 {
+            return match self {
+                StokesImage::DegreeOfPolarization => 0.0,
+                _ => 0.5,
+            };
+        }
+
+        let value = match self {
+            StokesImage::S1 => (s[1] / s[0] + 1.0) * 0.5,
+            StokesImage::S2 => (s[2] / s[0] + 1.0) * 0.5,
+            StokesImage::S3 => (s[3] / s[0] + 1.0) * 0.5,
+            StokesImage::DegreeOfPolarization => {
+                (s[1] * s[1] + s[2] * s[2] + s[3] * s[3]).sqrt() / s[0]
+            }
+            StokesImage::AngleOfPolarization => {
+                0.5 * f32::atan2(s[2], s[1]) * std::f32::consts::FRAC_1_PI + 0.5
+            }
+        };
+
+        floats::fast_clamp(value, 0.0, 1.0)
+    }
+}
@@ -3,17 +3,18 @@ use util::floats;
 
 use crate::render::objects::Instance;
 use crate::render::scene::SceneIntersection;
+use crate::Spectrum;
 use geometry::bvh::Bvh;
 use geometry::{Boundable, Intersectable};
 
-pub type SceneBvh = Bvh<Instance>;
+pub type SceneBvh<S> = Bvh<Instance<S>>;
 
-pub trait SceneGeometry {
-    fn intersect_detailed(&self, ray: &Ray) -> Option<SceneIntersection>;
+pub trait SceneGeometry<S: Spectrum> {
+    fn intersect_detailed(&self, ray: &Ray) -> Option<SceneIntersection<S>>;
 }
 
-impl SceneGeometry for SceneBvh {
-    fn intersect_detailed(&self, ray: &Ray) -> Option<SceneIntersection> {
+impl<S: Spectrum> SceneGeometry<S> for SceneBvh<S> {
+    fn intersect_detailed(&self, ray: &Ray) -> Option<SceneIntersection<S>> {
         if !self.bounds().intersects(ray) {
             return None;
         }
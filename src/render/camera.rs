@@ -1,6 +1,40 @@
+use crate::render::objects::emitter::OcclusionTester;
 use geometry::ray::Ray;
 use ultraviolet::{Vec2, Vec3};
 
+/// # Summary
+/// Contains of
+/// * `importance` - The emitted importance towards the sampled point
+/// * `incident` - The direction from the sampled point towards the camera
+/// * `pdf` - The pdf of the sampled direction (solid angle measure)
+/// * `raster` - The raster position the importance is splatted to
+/// * `occlusion_tester` - A tester between the sampled point and the camera
+pub struct CameraSample {
+    pub importance: f32,
+    pub incident: Vec3,
+    pub pdf: f32,
+    pub raster: Vec2,
+    pub occlusion_tester: OcclusionTester,
+}
+
+impl CameraSample {
+    pub fn new(
+        importance: f32,
+        incident: Vec3,
+        pdf: f32,
+        raster: Vec2,
+        occlusion_tester: OcclusionTester,
+    ) -> Self {
+        Self {
+            importance,
+            incident,
+            pdf,
+            raster,
+            occlusion_tester,
+        }
+    }
+}
+
 /// A camera consists of
 /// - position: camera center
 /// - center: center of the scene, which the camera is looking at
@@ -23,6 +57,8 @@ pub struct Camera {
     x_dir: Vec3,
     y_dir: Vec3,
     lower_left: Vec3,
+    distance: f32,
+    area: f32,
 }
 
 impl Camera {
@@ -45,6 +81,9 @@ impl Camera {
         let y_dir = -up * image_height / h;
         let lower_left = center - 0.5 * w * x_dir - 0.5 * h * y_dir;
 
+        // area of the image plane at distance 1 to the eye
+        let area = image_width * image_height / (distance * distance);
+
         Self {
             position,
             center,
@@ -57,6 +96,8 @@ impl Camera {
             x_dir,
             y_dir,
             lower_left,
+            distance,
+            area,
         }
     }
 
@@ -73,6 +114,97 @@ impl Camera {
         Ray::new(origin, direction)
     }
 
+    /// # Summary
+    /// Projects the given point onto the image plane.
+    ///
+    /// # Arguments
+    /// * `point` - A point in world space
+    ///
+    /// # Results
+    /// * `Option<Vec2>` - The raster position, if the point is visible to the camera
+    pub fn raster(&self, point: &Vec3) -> Option<Vec2> {
+        let direction = *point - self.position;
+        let cos = direction.dot(self.forward);
+        if cos <= 0.0 {
+            return None;
+        }
+
+        let on_plane = self.position + direction * (self.distance / cos) - self.lower_left;
+        let x = on_plane.dot(self.x_dir) / self.x_dir.mag_sq();
+        let y = on_plane.dot(self.y_dir) / self.y_dir.mag_sq();
+
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            None
+        } else {
+            Some(Vec2::new(x, y))
+        }
+    }
+
+    /// # Summary
+    /// Evaluates the importance emitted by the camera along the given direction.
+    ///
+    /// # Arguments
+    /// * `direction` - A normalized direction leaving the camera
+    ///
+    /// # Results
+    /// * `f32` - The importance (`0` if the direction does not hit the image plane)
+    pub fn importance(&self, direction: &Vec3) -> f32 {
+        let cos = direction.dot(self.forward);
+        if cos <= 0.0 || self.raster(&(self.position + *direction)).is_none() {
+            return 0.0;
+        }
+
+        let cos2 = cos * cos;
+
+        1.0 / (self.area * cos2 * cos2)
+    }
+
+    /// # Summary
+    /// Computes the pdf of the camera sampling the given direction.
+    ///
+    /// # Arguments
+    /// * `direction` - A normalized direction leaving the camera
+    ///
+    /// # Results
+    /// * `f32` - The pdf (solid angle measure)
+    pub fn pdf_direction(&self, direction: &Vec3) -> f32 {
+        let cos = direction.dot(self.forward);
+        if cos <= 0.0 || self.raster(&(self.position + *direction)).is_none() {
+            return 0.0;
+        }
+
+        1.0 / (self.area * cos * cos * cos)
+    }
+
+    /// # Summary
+    /// Samples the importance arriving at the given point from the camera.
+    ///
+    /// # Arguments
+    /// * `point` - The point receiving importance
+    ///
+    /// # Results
+    /// * `Option<CameraSample>` - The sample, if the point is visible to the camera
+    pub fn sample_importance(&self, point: &Vec3) -> Option<CameraSample> {
+        let raster = self.raster(point)?;
+
+        let to_camera = self.position - *point;
+        let dist_sq = to_camera.mag_sq();
+        let incident = to_camera.normalized();
+
+        let cos = -incident.dot(self.forward);
+        let pdf = dist_sq / cos;
+        let importance = self.importance(&-incident);
+        let occlusion_tester = OcclusionTester::between(*point, self.position);
+
+        Some(CameraSample::new(
+            importance,
+            incident,
+            pdf,
+            raster,
+            occlusion_tester,
+        ))
+    }
+
     pub fn reset(&mut self) {
         *self = Self::new(
             self.position,
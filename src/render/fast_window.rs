@@ -1,16 +1,17 @@
 use crate::configuration::Configuration;
 use crate::render::renderer::Renderer;
+use crate::Spectrum;
 use show_image::{make_window_full, KeyCode, Window, WindowOptions};
 use std::time::Duration;
 
-pub struct FastWindow {
+pub struct FastWindow<S: Spectrum> {
     window: Window,
     config: Configuration,
-    renderer: Renderer,
+    renderer: Renderer<S>,
 }
 
-impl FastWindow {
-    pub fn new(name: String, config: Configuration, renderer: Renderer) -> Result<Self, String> {
+impl<S: Spectrum> FastWindow<S> {
+    pub fn new(name: String, config: Configuration, renderer: Renderer<S>) -> Result<Self, String> {
         // let div = f32::max(config.width as f32 / 900.0, config.height as f32 / 900.0).max(1.0);
         // let width = (config.width as f32 / div) as u32;
         // let height = (config.height as f32 / div) as u32;
@@ -0,0 +1,62 @@
+use crate::Spectrum;
+use std::sync::Mutex;
+use ultraviolet::Vec2;
+
+/// A film accumulating contributions that are splatted to arbitrary pixels, e.g. by light
+/// tracing strategies.
+///
+/// The renderer adds the splats of a pixel to its average, divided by the number of samples the
+/// pixel received.
+pub struct Film<S> {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Mutex<S>>,
+}
+
+impl<S: Spectrum> Film<S> {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pixels = (0..width * height)
+            .map(|_| Mutex::new(S::black()))
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    /// # Summary
+    /// Adds the given spectrum to the pixel at the raster position.
+    ///
+    /// # Arguments
+    /// * `raster` - The raster position
+    /// * `spectrum` - The contribution to add
+    pub fn splat(&self, raster: &Vec2, spectrum: S) {
+        let x = raster.x as u32;
+        let y = raster.y as u32;
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = self.index(x, y);
+        *self.pixels[index].lock().expect("Film pixel is poisoned") += spectrum;
+    }
+
+    /// Returns the accumulated splats of the given pixel.
+    pub fn get(&self, x: u32, y: u32) -> S {
+        let index = self.index(x, y);
+        *self.pixels[index].lock().expect("Film pixel is poisoned")
+    }
+
+    pub fn reset(&self) {
+        self.pixels
+            .iter()
+            .for_each(|p| *p.lock().expect("Film pixel is poisoned") = S::black());
+    }
+}
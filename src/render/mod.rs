@@ -2,7 +2,9 @@ pub mod bvh;
 pub mod camera;
 #[cfg(feature = "live-window")]
 pub mod fast_window;
+pub mod film;
 pub mod objects;
+pub mod png;
 pub mod renderer;
 pub mod scene;
 pub mod transform;
@@ -1,45 +1,125 @@
 use crate::bxdf::bsdf::BSDF;
+use crate::bxdf::world_to_bxdf;
+use crate::mc::{cos_sample_hemisphere, uniform_sample_sphere, uniform_sphere_pdf};
+use crate::medium::Medium;
 use crate::render::objects::receiver::{Receiver, ReceiverObj};
 use crate::render::scene::{Scene, SceneIntersection};
+use crate::sampler::Sampler;
 use crate::Spectrum;
+use color::illuminant::Illuminant;
 use geometry::aabb::Aabb;
 use geometry::ray::Ray;
 use geometry::{Boundable, Geometry, Intersectable, Intersection};
+use std::f32::consts::{FRAC_1_PI, PI};
 use std::fmt::Debug;
 use std::sync::Arc;
 use ultraviolet::{Vec2, Vec3};
 use util::floats;
 
-pub trait Emitter: Receiver {
-    fn as_receiver(&self) -> Arc<dyn Receiver + '_> {
-        Arc::new(ReceiverObj::new(self.shape().clone(), self.bsdf().clone()))
+pub trait Emitter<S: Spectrum>: Receiver<S> {
+    fn as_receiver(&self) -> Arc<dyn Receiver<S> + '_> {
+        Arc::new(ReceiverObj::new(self.shape(), self.bsdf().clone()))
     }
 
-    fn emission(&self) -> Spectrum;
+    fn emission(&self) -> S;
 
     #[inline]
-    fn radiance(&self, incident: &Vec3, normal: &Vec3) -> Spectrum {
+    fn radiance(&self, incident: &Vec3, normal: &Vec3) -> S {
         let dot = incident.dot(*normal);
 
         if dot > 0.0 {
             self.emission()
         } else {
-            Spectrum::new_const(0.0)
+            S::new_const(0.0)
         }
     }
 
-    fn sample(&self, intersection: &SceneIntersection, sample: &Vec2) -> EmitterSample;
+    fn sample(&self, intersection: &SceneIntersection<S>, sample: &Vec2) -> EmitterSample<S> {
+        self.sample_from(&intersection.info.point, sample)
+    }
+
+    /// # Summary
+    /// Samples the emitter from the given point, e.g. a scattering point inside a medium.
+    ///
+    /// # Arguments
+    /// * `point` - The point receiving light
+    /// * `sample` - The sample space
+    ///
+    /// # Results
+    /// * `EmitterSample<S>` - The radiance arriving at the point
+    fn sample_from(&self, point: &Vec3, sample: &Vec2) -> EmitterSample<S>;
+
+    /// Whether this emitter is described by a delta distribution in position (e.g. a point light).
+    fn is_delta(&self) -> bool;
+
+    /// # Summary
+    /// Samples a ray leaving this emitter.
+    ///
+    /// # Arguments
+    /// * `sample_pos` - The sample space for the position on the emitter
+    /// * `sample_dir` - The sample space for the direction of the ray
+    ///
+    /// # Results
+    /// * `EmissionSample` - The radiance, ray, normal and pdfs of the emission
+    fn sample_emission(&self, sample_pos: &Vec2, sample_dir: &Vec2) -> EmissionSample<S>;
+
+    /// # Summary
+    /// Computes the pdfs of emitting light from a point with the given normal into the direction.
+    ///
+    /// # Arguments
+    /// * `direction` - The direction leaving the emitter
+    /// * `normal` - The normal at the point on the emitter
+    ///
+    /// # Results
+    /// * `(f32, f32)` - The pdf of the position (area measure) and direction (solid angle measure)
+    fn pdf_emission(&self, direction: &Vec3, normal: &Vec3) -> (f32, f32);
+
+    /// # Summary
+    /// Computes the pdf of sampling the direction of the ray with `Emitter::sample` from its
+    /// origin.
+    ///
+    /// # Arguments
+    /// * `ray` - The ray towards the emitter
+    ///
+    /// # Results
+    /// * `f32` - The pdf (solid angle measure)
+    fn pdf_incident(&self, ray: &Ray) -> f32;
+
+    /// The total power emitted by this emitter.
+    fn power(&self) -> S;
 }
 
 #[derive(Debug)]
-pub struct EmitterObj<T> {
+pub struct EmitterObj<T, S: Spectrum> {
     shape: T,
-    bsdf: Arc<BSDF>,
-    emission: Spectrum,
+    bsdf: Arc<BSDF<S>>,
+    emission: S,
 }
 
-impl<T> EmitterObj<T> {
-    pub fn new(shape: T, bsdf: Arc<BSDF>, emission: Spectrum) -> Self {
+impl<T, S: Spectrum> EmitterObj<T, S> {
+    /// # Summary
+    /// Creates a new emitter with the spectrum of a standard illuminant.
+    ///
+    /// # Arguments
+    /// * `shape` - The shape of the emitter
+    /// * `bsdf` - The BSDF of the emitter
+    /// * `illuminant` - The illuminant, normalized to a luminance of `1`
+    /// * `scale` - The luminance of the emission
+    ///
+    /// # Results
+    /// * `Self` - The emitter
+    pub fn with_illuminant(
+        shape: T,
+        bsdf: Arc<BSDF<S>>,
+        illuminant: Illuminant,
+        scale: f32,
+    ) -> Self {
+        let emission = S::from_spectral(&illuminant.spectrum()) * scale;
+
+        Self::new(shape, bsdf, emission)
+    }
+
+    pub fn new(shape: T, bsdf: Arc<BSDF<S>>, emission: S) -> Self {
         Self {
             shape,
             bsdf,
@@ -48,7 +128,7 @@ impl<T> EmitterObj<T> {
     }
 }
 
-impl<T> Boundable for EmitterObj<T>
+impl<T, S: Spectrum> Boundable for EmitterObj<T, S>
 where
     T: Boundable,
 {
@@ -57,7 +137,7 @@ where
     }
 }
 
-impl<T> Intersectable for EmitterObj<T>
+impl<T, S: Spectrum> Intersectable for EmitterObj<T, S>
 where
     T: Intersectable,
 {
@@ -70,7 +150,7 @@ where
     }
 }
 
-impl<T> Receiver for EmitterObj<T>
+impl<T, S: Spectrum> Receiver<S> for EmitterObj<T, S>
 where
     T: Debug + Geometry,
 {
@@ -78,21 +158,21 @@ where
         &self.shape
     }
 
-    fn bsdf(&self) -> &Arc<BSDF> {
+    fn bsdf(&self) -> &Arc<BSDF<S>> {
         &self.bsdf
     }
 }
 
-impl<T> Emitter for EmitterObj<T>
+impl<T, S: Spectrum> Emitter<S> for EmitterObj<T, S>
 where
     T: Debug + Geometry + Sampleable + Send + Sync,
 {
-    fn emission(&self) -> Spectrum {
+    fn emission(&self) -> S {
         self.emission
     }
 
-    fn sample(&self, intersection: &SceneIntersection, sample: &Vec2) -> EmitterSample {
-        let point = intersection.info.point;
+    fn sample_from(&self, point: &Vec3, sample: &Vec2) -> EmitterSample<S> {
+        let point = *point;
         let surface = self.shape.sample_surface(&point, sample);
 
         let incident = (surface.point - point).normalized();
@@ -101,35 +181,124 @@ where
         let occlusion_tester = OcclusionTester::between(from, surface.point);
 
         let pdf = self.shape.pdf(&occlusion_tester.ray);
-        let radiance = self.radiance(&incident, &surface.normal);
+        let radiance = self.radiance(&-incident, &surface.normal);
+
+        EmitterSample::new(
+            radiance,
+            incident,
+            pdf,
+            surface.point,
+            surface.normal,
+            occlusion_tester,
+        )
+    }
+
+    fn is_delta(&self) -> bool {
+        self.shape.surface_area() == 0.0
+    }
+
+    fn sample_emission(&self, sample_pos: &Vec2, sample_dir: &Vec2) -> EmissionSample<S> {
+        let surface = self.shape.sample_area(sample_pos);
+
+        if self.is_delta() {
+            let direction = uniform_sample_sphere(sample_dir);
+            let ray = Ray::new(surface.point, direction);
 
-        EmitterSample::new(radiance, incident, pdf, occlusion_tester)
+            EmissionSample::new(self.emission(), ray, direction, 1.0, uniform_sphere_pdf())
+        } else {
+            // the hemisphere is sampled around the z-axis, our normal is the y-axis
+            let hemisphere = cos_sample_hemisphere(sample_dir);
+            let local = Vec3::new(hemisphere.x, hemisphere.z, hemisphere.y);
+            let direction = world_to_bxdf(&surface.normal).reversed() * local;
+
+            let ray = Ray::with(surface.point, direction, floats::BIG_EPSILON, f32::INFINITY);
+            let radiance = self.radiance(&direction, &surface.normal);
+            let (pdf_pos, pdf_dir) = self.pdf_emission(&direction, &surface.normal);
+
+            EmissionSample::new(radiance, ray, surface.normal, pdf_pos, pdf_dir)
+        }
+    }
+
+    fn pdf_emission(&self, direction: &Vec3, normal: &Vec3) -> (f32, f32) {
+        if self.is_delta() {
+            (1.0, uniform_sphere_pdf())
+        } else {
+            let pdf_pos = 1.0 / self.shape.surface_area();
+            let pdf_dir = f32::max(0.0, direction.dot(*normal)) * FRAC_1_PI;
+
+            (pdf_pos, pdf_dir)
+        }
+    }
+
+    fn pdf_incident(&self, ray: &Ray) -> f32 {
+        self.shape.pdf(ray)
+    }
+
+    fn power(&self) -> S {
+        if self.is_delta() {
+            self.emission * (4.0 * PI)
+        } else {
+            self.emission * (PI * self.shape.surface_area())
+        }
     }
 }
 
-pub struct EmitterSample {
-    pub radiance: Spectrum,
+pub struct EmitterSample<S> {
+    pub radiance: S,
     pub incident: Vec3,
     pub pdf: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
     pub occlusion_tester: OcclusionTester,
 }
 
-impl EmitterSample {
+impl<S> EmitterSample<S> {
     pub fn new(
-        radiance: Spectrum,
+        radiance: S,
         incident: Vec3,
         pdf: f32,
+        point: Vec3,
+        normal: Vec3,
         occlusion_tester: OcclusionTester,
     ) -> Self {
         Self {
             radiance,
             incident,
             pdf,
+            point,
+            normal,
             occlusion_tester,
         }
     }
 }
 
+/// # Summary
+/// Contains of
+/// * `radiance` - The emitted radiance along the ray
+/// * `ray` - The ray leaving the emitter
+/// * `normal` - The normal at the origin of the ray
+/// * `pdf_pos` - The pdf of the origin (area measure)
+/// * `pdf_dir` - The pdf of the direction (solid angle measure)
+pub struct EmissionSample<S> {
+    pub radiance: S,
+    pub ray: Ray,
+    pub normal: Vec3,
+    pub pdf_pos: f32,
+    pub pdf_dir: f32,
+}
+
+impl<S> EmissionSample<S> {
+    pub fn new(radiance: S, ray: Ray, normal: Vec3, pdf_pos: f32, pdf_dir: f32) -> Self {
+        Self {
+            radiance,
+            ray,
+            normal,
+            pdf_pos,
+            pdf_dir,
+        }
+    }
+}
+
 pub struct OcclusionTester {
     ray: Ray,
 }
@@ -147,9 +316,55 @@ impl OcclusionTester {
         Self { ray }
     }
 
-    pub fn is_occluded(&self, scene: &Scene) -> bool {
+    pub fn is_occluded<S: Spectrum>(&self, scene: &Scene<S>) -> bool {
         scene.is_occluded(&self.ray)
     }
+
+    /// # Summary
+    /// Computes the transmittance between both points through the media of the scene.
+    /// Boundaries of media are passed, any other surface occludes.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene
+    /// * `medium` - The medium at the start of the tester
+    /// * `sampler` - The sampler to use
+    ///
+    /// # Results
+    /// * `S` - The transmittance (black if occluded)
+    pub fn transmittance<S: Spectrum>(
+        &self,
+        scene: &Scene<S>,
+        medium: Option<&Arc<dyn Medium<S>>>,
+        sampler: &dyn Sampler,
+    ) -> S {
+        let mut ray = self.ray;
+        let mut medium = medium.cloned();
+        let mut transmittance = S::new_const(1.0);
+
+        loop {
+            let hit = scene.intersect(&ray);
+            if matches!(&hit, Some(si) if !si.obj.is_medium_boundary()) {
+                return S::new_const(0.0);
+            }
+
+            if let Some(m) = &medium {
+                let mut segment = ray;
+                if let Some(si) = &hit {
+                    segment.t_end = si.info.t;
+                }
+
+                transmittance *= m.transmittance(&segment, sampler);
+            }
+
+            match hit {
+                Some(si) => {
+                    medium = scene.medium_after(&si, &ray.direction, medium.as_ref());
+                    ray.t_start = si.info.t + floats::BIG_EPSILON;
+                }
+                None => return transmittance,
+            }
+        }
+    }
 }
 
 pub struct SurfaceSample {
@@ -171,6 +386,9 @@ pub trait Sampleable {
     /// Sample this object of the solid angle from `point` to the sampled point on the surface.
     fn sample_surface(&self, point: &Vec3, sample: &Vec2) -> SurfaceSample;
 
+    /// Sample a point uniformly distributed on the surface of this object.
+    fn sample_area(&self, sample: &Vec2) -> SurfaceSample;
+
     /// Computes the PDF that the ray intersects this object.
     fn pdf(&self, ray: &Ray) -> f32;
 }
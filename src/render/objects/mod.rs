@@ -1,5 +1,8 @@
+use crate::bxdf::bsdf::BSDF;
+use crate::medium::Medium;
 use crate::render::objects::emitter::Emitter;
 use crate::render::objects::receiver::Receiver;
+use crate::Spectrum;
 use geometry::aabb::Aabb;
 use geometry::ray::Ray;
 use geometry::{Boundable, Intersectable, Intersection};
@@ -11,12 +14,33 @@ pub mod receiver;
 mod sphere;
 
 #[derive(Clone)]
-pub enum Instance {
-    Emitter(Arc<dyn Emitter>),
-    Receiver(Arc<dyn Receiver>),
+pub enum Instance<S: Spectrum> {
+    Emitter(Arc<dyn Emitter<S>>),
+    Receiver(Arc<dyn Receiver<S>>),
 }
 
-impl Boundable for Instance {
+impl<S: Spectrum> Instance<S> {
+    pub fn bsdf(&self) -> &Arc<BSDF<S>> {
+        match self {
+            Instance::Emitter(e) => e.bsdf(),
+            Instance::Receiver(r) => r.bsdf(),
+        }
+    }
+
+    pub fn medium(&self) -> Option<&Arc<dyn Medium<S>>> {
+        match self {
+            Instance::Emitter(e) => e.medium(),
+            Instance::Receiver(r) => r.medium(),
+        }
+    }
+
+    /// Whether this instance is only the invisible boundary of its medium (it has no BxDFs).
+    pub fn is_medium_boundary(&self) -> bool {
+        self.medium().is_some() && self.bsdf().num_bxdfs() == 0
+    }
+}
+
+impl<S: Spectrum> Boundable for Instance<S> {
     fn bounds(&self) -> Aabb {
         match self {
             Instance::Emitter(e) => e.bounds(),
@@ -25,7 +49,7 @@ impl Boundable for Instance {
     }
 }
 
-impl Intersectable for Instance {
+impl<S: Spectrum> Intersectable for Instance<S> {
     fn intersect(&self, ray: &Ray) -> Option<Intersection> {
         match self {
             Instance::Emitter(e) => e.intersect(ray),
@@ -9,11 +9,15 @@ impl Sampleable for Point {
     }
 
     fn sample_surface(&self, point: &Vec3, _: &Vec2) -> SurfaceSample {
-        let normal = self.position - *point;
+        let normal = *point - self.position;
 
         SurfaceSample::new(self.position, normal.normalized())
     }
 
+    fn sample_area(&self, _: &Vec2) -> SurfaceSample {
+        SurfaceSample::new(self.position, Vec3::unit_y())
+    }
+
     fn pdf(&self, _: &Ray) -> f32 {
         1.0
     }
@@ -1,29 +1,60 @@
 use crate::bxdf::bsdf::BSDF;
+use crate::medium::Medium;
+use crate::Spectrum;
 use geometry::aabb::Aabb;
 use geometry::ray::Ray;
 use geometry::{Boundable, Geometry, Intersectable, Intersection};
 use std::fmt::Debug;
 use std::sync::Arc;
 
-pub trait Receiver: Geometry {
+pub trait Receiver<S: Spectrum>: Geometry {
     fn shape(&self) -> &dyn Geometry;
 
-    fn bsdf(&self) -> &Arc<BSDF>;
+    fn bsdf(&self) -> &Arc<BSDF<S>>;
+
+    /// The medium inside this object, if any.
+    fn medium(&self) -> Option<&Arc<dyn Medium<S>>> {
+        None
+    }
 }
 
 #[derive(Debug)]
-pub struct ReceiverObj<T> {
+pub struct ReceiverObj<T, S: Spectrum> {
     shape: T,
-    bsdf: Arc<BSDF>,
+    bsdf: Arc<BSDF<S>>,
+    medium: Option<Arc<dyn Medium<S>>>,
 }
 
-impl<T> ReceiverObj<T> {
-    pub fn new(shape: T, bsdf: Arc<BSDF>) -> Self {
-        Self { shape, bsdf }
+impl<T, S: Spectrum> ReceiverObj<T, S> {
+    pub fn new(shape: T, bsdf: Arc<BSDF<S>>) -> Self {
+        Self {
+            shape,
+            bsdf,
+            medium: None,
+        }
+    }
+
+    /// # Summary
+    /// Creates a new receiver filled with the given medium.
+    /// An empty BSDF makes the surface an invisible boundary of the medium.
+    ///
+    /// # Arguments
+    /// * `shape` - The shape of the receiver
+    /// * `bsdf` - The BSDF of the surface
+    /// * `medium` - The medium inside the shape
+    ///
+    /// # Results
+    /// * `Self` - The receiver
+    pub fn with_medium(shape: T, bsdf: Arc<BSDF<S>>, medium: Arc<dyn Medium<S>>) -> Self {
+        Self {
+            shape,
+            bsdf,
+            medium: Some(medium),
+        }
     }
 }
 
-impl<T> Boundable for ReceiverObj<T>
+impl<T, S: Spectrum> Boundable for ReceiverObj<T, S>
 where
     T: Boundable,
 {
@@ -32,7 +63,7 @@ where
     }
 }
 
-impl<T> Intersectable for ReceiverObj<T>
+impl<T, S: Spectrum> Intersectable for ReceiverObj<T, S>
 where
     T: Intersectable,
 {
@@ -45,7 +76,7 @@ where
     }
 }
 
-impl<T> Receiver for ReceiverObj<T>
+impl<T, S: Spectrum> Receiver<S> for ReceiverObj<T, S>
 where
     T: Geometry,
 {
@@ -53,7 +84,11 @@ where
         &self.shape
     }
 
-    fn bsdf(&self) -> &Arc<BSDF> {
+    fn bsdf(&self) -> &Arc<BSDF<S>> {
         &self.bsdf
     }
+
+    fn medium(&self) -> Option<&Arc<dyn Medium<S>>> {
+        self.medium.as_ref()
+    }
 }
@@ -25,9 +25,9 @@ impl Sampleable for Sphere {
 
             SurfaceSample::new(self.center + p, normal)
         } else {
-            let cos_theta_max = f32::max(0.0, 1.0 - r2 / dist_sq).sqrt() / 2.0;
+            let cos_theta_max = f32::max(0.0, 1.0 - r2 / dist_sq).sqrt();
 
-            let axis = to_center;
+            let axis = to_center.normalized();
 
             let rotation = world_to_bxdf(&axis);
             let direction = rotation.reversed() * uniform_sample_cone(sample, cos_theta_max).normalized();
@@ -51,6 +51,12 @@ impl Sampleable for Sphere {
         }
     }
 
+    fn sample_area(&self, sample: &Vec2) -> SurfaceSample {
+        let normal = uniform_sample_sphere(sample);
+
+        SurfaceSample::new(self.center + normal * self.radius, normal)
+    }
+
     fn pdf(&self, ray: &Ray) -> f32 {
         let dist_sq = (ray.origin - self.center).mag_sq();
         let r2 = self.radius * self.radius;
@@ -0,0 +1,120 @@
+//! Encoding of PNG images with embedded color space information (`sRGB`, `gAMA` and `cHRM`
+//! chunks).
+
+use color::color_space::ColorSpace;
+use image::png::PngEncoder;
+use image::ColorType;
+
+/// The length of the PNG signature and the `IHDR` chunk, after which we insert our chunks.
+const HEADER_LENGTH: usize = 8 + 4 + 4 + 13 + 4;
+
+/// # Summary
+/// Computes the CRC-32 (ISO 3309) of the given bytes, as used by PNG chunks.
+///
+/// # Arguments
+/// * `bytes` - The bytes
+///
+/// # Results
+/// * `u32` - The checksum
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// # Summary
+/// Creates a PNG chunk of the given type and data.
+///
+/// # Arguments
+/// * `typ` - The chunk type
+/// * `data` - The chunk data
+///
+/// # Results
+/// * `Vec<u8>` - The chunk, including length and checksum
+fn chunk(typ: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(typ);
+    chunk.extend_from_slice(data);
+
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+
+    chunk
+}
+
+/// # Summary
+/// Creates the chunks describing the given color space.
+///
+/// # Arguments
+/// * `space` - The color space
+///
+/// # Results
+/// * `Vec<u8>` - The chunks
+fn color_space_chunks(space: ColorSpace) -> Vec<u8> {
+    let to_png = |v: f32| ((v * 100_000.0).round() as u32).to_be_bytes();
+
+    let mut chunks = Vec::new();
+
+    if space == ColorSpace::Srgb {
+        // perceptual rendering intent
+        chunks.append(&mut chunk(b"sRGB", &[0]));
+    }
+
+    chunks.append(&mut chunk(b"gAMA", &to_png(space.gamma())));
+
+    let (x, y) = space.white_point();
+    let mut chrm = Vec::with_capacity(32);
+    chrm.extend_from_slice(&to_png(x));
+    chrm.extend_from_slice(&to_png(y));
+    for (x, y) in space.primaries().iter() {
+        chrm.extend_from_slice(&to_png(*x));
+        chrm.extend_from_slice(&to_png(*y));
+    }
+    chunks.append(&mut chunk(b"cHRM", &chrm));
+
+    chunks
+}
+
+/// # Summary
+/// Encodes an image as PNG, tagged with the given color space.
+///
+/// # Arguments
+/// * `data` - The pixel data (in big endianness for 16 bit colors)
+/// * `width` - The width of the image
+/// * `height` - The height of the image
+/// * `color_type` - The color type of the pixel data
+/// * `space` - The color space the pixel data is encoded in
+///
+/// # Results
+/// * `Result<Vec<u8>, String>` - The PNG file contents or an error message
+pub fn encode(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    space: ColorSpace,
+) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .encode(data, width, height, color_type)
+        .map_err(|e| format!("Unable to encode image: {}", e))?;
+
+    // the color space chunks must precede the image data
+    let tail = png.split_off(HEADER_LENGTH);
+    png.append(&mut color_space_chunks(space));
+    png.extend(tail);
+
+    Ok(png)
+}
@@ -1,22 +1,26 @@
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Mutex};
 
 use image::{ImageBuffer, Rgb};
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
 use lazy_static::lazy_static;
 
-use color::Color;
+use color::color_space::ColorSpace;
+use color::{Color, Srgb};
 use util::range_block::{Block, RangeBlock};
 
 use crate::configuration::Configuration;
 use crate::integrator::Integrator;
+use crate::polarization::{StokesFilm, StokesImage};
 use crate::render::camera::Camera;
+use crate::render::film::Film;
 use crate::render::scene::Scene;
 use crate::sampler::Sampler;
 use crate::Spectrum;
 use bitflags::_core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::thread::JoinHandle;
+use ultraviolet::Mat3;
 
 lazy_static! {
     pub static ref PROGRESS_BAR: Mutex<ProgressBar> =
@@ -61,23 +65,23 @@ impl<T: Default> RenderJob<T> {
 }
 
 #[derive(Default)]
-struct SpectrumStatistic {
+struct SpectrumStatistic<S> {
     x: u32,
     y: u32,
-    spectrum: Spectrum,
+    spectrum: S,
     samples: usize,
 }
 
-impl SpectrumStatistic {
+impl<S: Spectrum> SpectrumStatistic<S> {
     pub fn new(x: u32, y: u32) -> Self {
         Self {
             x,
             y,
-            spectrum: Spectrum::black(),
+            spectrum: S::black(),
             samples: 0,
         }
     }
-    pub fn average(&self) -> Spectrum {
+    pub fn average(&self) -> S {
         if self.samples == 0 {
             self.spectrum
         } else {
@@ -87,21 +91,21 @@ impl SpectrumStatistic {
 
     pub fn reset(&mut self) {
         self.samples = 0;
-        self.spectrum = Spectrum::black();
+        self.spectrum = S::black();
     }
 }
 
-struct RenderBlock {
-    stats: Vec<SpectrumStatistic>,
+struct RenderBlock<S> {
+    stats: Vec<SpectrumStatistic<S>>,
 }
 
-impl RenderBlock {
+impl<S: Spectrum> RenderBlock<S> {
     pub fn reset(&mut self) {
         self.stats.iter_mut().for_each(|s| s.reset());
     }
 }
 
-impl From<&Block> for RenderBlock {
+impl<S: Spectrum> From<&Block> for RenderBlock<S> {
     fn from(block: &Block) -> Self {
         let stats = block
             .prod()
@@ -114,23 +118,29 @@ impl From<&Block> for RenderBlock {
 
 #[derive(Clone)]
 #[allow(clippy::rc_buffer)]
-pub struct Renderer {
-    scene: Arc<Scene>,
+pub struct Renderer<S: Spectrum> {
+    scene: Arc<Scene<S>>,
     camera: Arc<Camera>,
+    film: Arc<Film<S>>,
+    stokes: Option<Arc<StokesFilm<S>>>,
     sampler: Arc<dyn Sampler>,
-    integrator: Arc<dyn Integrator>,
-    render_blocks: Arc<Vec<Mutex<RenderBlock>>>,
-    rendering: Arc<RwLock<ImageBuffer<Rgb<u8>, Vec<u8>>>>,
+    integrator: Arc<dyn Integrator<S>>,
+    render_blocks: Arc<Vec<Mutex<RenderBlock<S>>>>,
     progress: Arc<AtomicUsize>,
+    completed: Arc<AtomicUsize>,
+    passes_begun: Arc<AtomicUsize>,
+    pass_lock: Arc<Mutex<()>>,
     config: Arc<Configuration>,
 }
 
-impl Renderer {
+impl<S: Spectrum> Renderer<S> {
     pub fn new(
-        scene: Arc<Scene>,
+        scene: Arc<Scene<S>>,
         camera: Arc<Camera>,
+        film: Arc<Film<S>>,
+        stokes: Option<Arc<StokesFilm<S>>>,
         sampler: Arc<dyn Sampler>,
-        integrator: Arc<dyn Integrator>,
+        integrator: Arc<dyn Integrator<S>>,
         config: Arc<Configuration>,
     ) -> Self {
         let (img_width, img_height) = (camera.width, camera.height);
@@ -145,11 +155,15 @@ impl Renderer {
         Self {
             scene,
             camera,
+            film,
+            stokes,
             sampler,
             integrator,
             progress: Arc::new(AtomicUsize::new(0)),
+            completed: Arc::new(AtomicUsize::new(0)),
+            passes_begun: Arc::new(AtomicUsize::new(0)),
+            pass_lock: Arc::new(Mutex::new(())),
             render_blocks: Arc::new(render_blocks),
-            rendering: Arc::new(RwLock::new(ImageBuffer::new(img_width, img_height))),
             config,
         }
     }
@@ -170,7 +184,7 @@ impl Renderer {
         self.get_progress() >= self.num_blocks() * self.config.passes as usize
     }
 
-    fn render(&self, x: u32, y: u32) -> Spectrum {
+    fn render(&self, x: u32, y: u32) -> S {
         let sample = self.sampler.get_2d();
         let ray = self.camera.primary_ray(x, y, &sample);
 
@@ -178,14 +192,61 @@ impl Renderer {
             .integrate(&self.scene, &ray, self.sampler.clone())
     }
 
+    /// Combines the average of the given pixel with the contributions splatted onto the film.
+    fn pixel(&self, stats: &SpectrumStatistic<S>) -> S {
+        let splats = self.film.get(stats.x, stats.y);
+
+        stats.average() + splats / stats.samples.max(1) as f32
+    }
+
+    /// # Summary
+    /// Lets the integrator begin every pass up to the given one.
+    /// A pass only begins after all blocks of the previous passes were rendered.
+    ///
+    /// # Arguments
+    /// * `pass` - The pass of the block to render next
+    /// * `should_stop` - Whether the rendering should stop
+    ///
+    /// # Results
+    /// * `bool` - Whether the pass has begun (`false` if the rendering was stopped)
+    fn begin_pass(&self, pass: usize, should_stop: &AtomicBool) -> bool {
+        if pass < self.passes_begun.load(Ordering::Acquire) {
+            return true;
+        }
+
+        let _lock = self.pass_lock.lock().expect("Pass lock is poisoned");
+        loop {
+            let next = self.passes_begun.load(Ordering::Acquire);
+            if next > pass {
+                return true;
+            }
+
+            while self.completed.load(Ordering::Acquire) < next * self.num_blocks() {
+                if should_stop.load(Ordering::Relaxed) {
+                    return false;
+                }
+                thread::yield_now();
+            }
+
+            self.integrator.begin_pass(&self.scene, next as u32);
+            self.passes_begun.store(next + 1, Ordering::Release);
+        }
+    }
+
     pub fn reset_progress(&mut self) {
         self.progress.store(0, Ordering::Relaxed);
+        self.completed.store(0, Ordering::Relaxed);
+        self.passes_begun.store(0, Ordering::Relaxed);
     }
 
     pub fn reset_image(&mut self) {
         self.render_blocks
             .iter()
             .for_each(|b| b.lock().expect("Block is poisoned").reset());
+        self.film.reset();
+        if let Some(stokes) = &self.stokes {
+            stokes.reset();
+        }
     }
 
     pub fn render_all(&mut self) -> RenderJob<()> {
@@ -200,6 +261,11 @@ impl Renderer {
             bar.reset();
         }
 
+        // blocks abandoned by a previous stop are never rendered
+        let total = self.num_blocks() * self.config.passes as usize;
+        let fetched = self.progress.load(Ordering::Relaxed);
+        self.completed.store(fetched.min(total), Ordering::Relaxed);
+
         let num_threads = self.config.threads;
         let mut handles = Vec::with_capacity(num_threads as usize);
 
@@ -217,6 +283,9 @@ impl Renderer {
                 if index >= this.num_blocks() * this.config.passes as usize {
                     break;
                 }
+                if !this.begin_pass(index / this.num_blocks(), &this_should_stop) {
+                    break;
+                }
                 let index = index % this.num_blocks();
 
                 let mut lock = this.render_blocks[index].lock().expect("Block is poisoned");
@@ -228,14 +297,11 @@ impl Renderer {
                     let pixel = this.render(stats.x, stats.y); //.clamp(0.0, 1.0); // FIXME
                     stats.spectrum += pixel;
                     stats.samples += 1;
-
-                    let avg = stats.average().into();
-                    this.rendering
-                        .write()
-                        .expect("Rendering poisoned")
-                        .put_pixel(stats.x, stats.y, avg);
                 });
 
+                drop(lock);
+                this.completed.fetch_add(1, Ordering::Release);
+
                 PROGRESS_BAR.lock().expect("Progress bar poisoned").inc(1);
             });
 
@@ -246,21 +312,162 @@ impl Renderer {
     }
 
     pub fn get_image_u8(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
-        self.rendering
-            .read()
-            .expect("Rendering is poisoned")
-            .clone()
+        let white_balance = self.white_balance_mat();
+
+        let mut buffer = ImageBuffer::new(self.config.width, self.config.height);
+        self.render_blocks.iter().for_each(|block| {
+            let lock = block.lock().expect("Block is poisoned");
+
+            lock.stats.iter().for_each(|stat| {
+                let pixel = self.developed_pixel(stat, &white_balance);
+                buffer.put_pixel(stat.x, stat.y, pixel.into())
+            });
+        });
+
+        buffer
     }
 
     // TODO: Possible to make more efficient?
     pub fn get_image_u16(&self) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        let white_balance = self.white_balance_mat();
+
         let mut buffer = ImageBuffer::new(self.config.width, self.config.height);
         self.render_blocks.iter().for_each(|block| {
             let lock = block.lock().expect("Block is poisoned");
 
-            lock.stats
-                .iter()
-                .for_each(|stat| buffer.put_pixel(stat.x, stat.y, stat.average().into()));
+            lock.stats.iter().for_each(|stat| {
+                let pixel = self.developed_pixel(stat, &white_balance);
+                buffer.put_pixel(stat.x, stat.y, pixel.into())
+            });
+        });
+
+        buffer
+    }
+
+    /// # Summary
+    /// Computes an image derived from the Stokes vectors of the pixels, if the integrator carries
+    /// polarization. Each color channel is computed separately.
+    ///
+    /// # Arguments
+    /// * `image` - The image to compute
+    ///
+    /// # Results
+    /// * `Option<ImageBuffer<Rgb<u16>, Vec<u16>>>` - The image or `None` without polarization
+    pub fn get_stokes_image_u16(
+        &self,
+        image: StokesImage,
+    ) -> Option<ImageBuffer<Rgb<u16>, Vec<u16>>> {
+        let film = self.stokes.as_ref()?;
+
+        let mut buffer = ImageBuffer::new(self.config.width, self.config.height);
+        self.render_blocks.iter().for_each(|block| {
+            let lock = block.lock().expect("Block is poisoned");
+
+            lock.stats.iter().for_each(|stat| {
+                let samples = stat.samples.max(1) as f32;
+                let s0 = self.pixel(stat).to_rgb();
+                let [s1, s2, s3] = film.get(stat.x, stat.y);
+                let [s1, s2, s3] = [
+                    (s1 / samples).to_rgb(),
+                    (s2 / samples).to_rgb(),
+                    (s3 / samples).to_rgb(),
+                ];
+
+                let mut pixel = Srgb::black();
+                for i in 0..3 {
+                    pixel[i] = image.value([s0[i], s1[i], s2[i], s3[i]]);
+                }
+                buffer.put_pixel(stat.x, stat.y, pixel.into())
+            });
+        });
+
+        Some(buffer)
+    }
+
+    /// # Summary
+    /// Computes the matrix applying the configured white balance to the linear sRGB pixels.
+    ///
+    /// # Results
+    /// * `Mat3` - The white balance matrix (the identity if none is configured)
+    fn white_balance_mat(&self) -> Mat3 {
+        match self.config.white_balance {
+            Some(illuminant) => ColorSpace::Srgb
+                .white_balance_mat(illuminant.white().to_vec3(), self.config.adaptation),
+            None => Mat3::identity(),
+        }
+    }
+
+    /// # Summary
+    /// Develops the pixel by applying the white balance and the configured response curve.
+    ///
+    /// # Arguments
+    /// * `stats` - The statistics of the pixel
+    /// * `white_balance` - The white balance matrix
+    ///
+    /// # Results
+    /// * `Srgb` - The (linear) sRGB values
+    fn developed_pixel(&self, stats: &SpectrumStatistic<S>, white_balance: &Mat3) -> Srgb {
+        let rgb = *white_balance * self.pixel(stats).to_rgb().to_vec3();
+
+        match self.config.response {
+            Some(response) => Srgb::from(rgb.map(|v| response.apply(v))),
+            None => Srgb::from(rgb),
+        }
+    }
+
+    /// # Summary
+    /// Develops the pixel, converts it to the given color space and applies its transfer
+    /// function.
+    ///
+    /// # Arguments
+    /// * `stats` - The statistics of the pixel
+    /// * `space` - The color space to encode in
+    /// * `white_balance` - The white balance matrix
+    /// * `conversion` - The matrix converting from linear sRGB to `space`
+    ///
+    /// # Results
+    /// * `Srgb` - The encoded values
+    fn encoded_pixel(
+        &self,
+        stats: &SpectrumStatistic<S>,
+        space: ColorSpace,
+        white_balance: &Mat3,
+        conversion: &Mat3,
+    ) -> Srgb {
+        let rgb = *conversion * self.developed_pixel(stats, white_balance).to_vec3();
+
+        Srgb::from(rgb.map(|v| space.encode(v)))
+    }
+
+    pub fn get_encoded_image_u8(&self, space: ColorSpace) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let white_balance = self.white_balance_mat();
+        let conversion = ColorSpace::Srgb.conversion_mat(space, self.config.adaptation);
+
+        let mut buffer = ImageBuffer::new(self.config.width, self.config.height);
+        self.render_blocks.iter().for_each(|block| {
+            let lock = block.lock().expect("Block is poisoned");
+
+            lock.stats.iter().for_each(|stat| {
+                let pixel = self.encoded_pixel(stat, space, &white_balance, &conversion);
+                buffer.put_pixel(stat.x, stat.y, pixel.into())
+            });
+        });
+
+        buffer
+    }
+
+    pub fn get_encoded_image_u16(&self, space: ColorSpace) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        let white_balance = self.white_balance_mat();
+        let conversion = ColorSpace::Srgb.conversion_mat(space, self.config.adaptation);
+
+        let mut buffer = ImageBuffer::new(self.config.width, self.config.height);
+        self.render_blocks.iter().for_each(|block| {
+            let lock = block.lock().expect("Block is poisoned");
+
+            lock.stats.iter().for_each(|stat| {
+                let pixel = self.encoded_pixel(stat, space, &white_balance, &conversion);
+                buffer.put_pixel(stat.x, stat.y, pixel.into())
+            });
         });
 
         buffer
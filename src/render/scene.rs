@@ -1,32 +1,37 @@
+use crate::medium::Medium;
 use crate::render::bvh::{SceneBvh, SceneGeometry};
 use crate::render::objects::emitter::Emitter;
 use crate::render::objects::Instance;
+use crate::Spectrum;
 use geometry::aabb::Aabb;
 use geometry::ray::Ray;
 use geometry::{Intersectable, Intersection};
 use std::sync::Arc;
+use ultraviolet::Vec3;
 
 #[derive(Clone)]
-pub struct SceneIntersection {
+pub struct SceneIntersection<S: Spectrum> {
     pub info: Intersection,
-    pub obj: Instance,
+    pub obj: Instance<S>,
 }
 
-impl SceneIntersection {
-    pub fn new(info: Intersection, obj: Instance) -> Self {
+impl<S: Spectrum> SceneIntersection<S> {
+    pub fn new(info: Intersection, obj: Instance<S>) -> Self {
         Self { info, obj }
     }
 }
 
-pub struct Scene {
+pub struct Scene<S: Spectrum> {
     pub aabb: Aabb,
-    pub lights: Vec<Arc<dyn Emitter>>,
-    pub objects: Vec<Instance>,
-    bvh: Arc<SceneBvh>,
+    pub lights: Vec<Arc<dyn Emitter<S>>>,
+    pub objects: Vec<Instance<S>>,
+    /// The medium surrounding all objects (and the camera)
+    pub atmosphere: Option<Arc<dyn Medium<S>>>,
+    bvh: Arc<SceneBvh<S>>,
 }
 
-impl Scene {
-    pub fn add(&mut self, obj: Instance) -> &mut Self {
+impl<S: Spectrum> Scene<S> {
+    pub fn add(&mut self, obj: Instance<S>) -> &mut Self {
         match &obj {
             Instance::Emitter(e) => {
                 self.objects.push(obj.clone());
@@ -49,7 +54,7 @@ impl Scene {
         self.bvh.intersects(ray)
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Option<SceneIntersection> {
+    pub fn intersect(&self, ray: &Ray) -> Option<SceneIntersection<S>> {
         // let mut ray = *ray;
         //
         // let mut intersection = None;
@@ -65,7 +70,33 @@ impl Scene {
         self.bvh.intersect_detailed(ray)
     }
 
-    pub fn reflect_from(&self, intersection: SceneIntersection) -> Option<SceneIntersection> {
+    /// # Summary
+    /// Determines the medium a ray continues in after leaving the intersection.
+    /// Objects with an interior medium switch between it and the atmosphere, other objects do not
+    /// change the medium.
+    ///
+    /// # Arguments
+    /// * `intersection` - The intersection the ray leaves
+    /// * `direction` - The direction of the ray
+    /// * `current` - The medium the ray arrived in
+    ///
+    /// # Results
+    /// * `Option<Arc<dyn Medium<S>>>` - The medium the ray continues in
+    pub fn medium_after(
+        &self,
+        intersection: &SceneIntersection<S>,
+        direction: &Vec3,
+        current: Option<&Arc<dyn Medium<S>>>,
+    ) -> Option<Arc<dyn Medium<S>>> {
+        match intersection.obj.medium() {
+            // the normal points outside
+            Some(medium) if direction.dot(intersection.info.normal) < 0.0 => Some(medium.clone()),
+            Some(_) => self.atmosphere.clone(),
+            None => current.cloned(),
+        }
+    }
+
+    pub fn reflect_from(&self, intersection: SceneIntersection<S>) -> Option<SceneIntersection<S>> {
         let direction = intersection
             .info
             .ray
@@ -77,12 +108,13 @@ impl Scene {
     }
 }
 
-impl Default for Scene {
+impl<S: Spectrum> Default for Scene<S> {
     fn default() -> Self {
         Self {
             aabb: Aabb::inverted_infinite(),
             lights: Vec::default(),
             objects: Vec::default(),
+            atmosphere: None,
             bvh: Arc::new(SceneBvh::default()),
         }
     }
@@ -0,0 +1,165 @@
+use crate::sampler::Sampler;
+use std::f32::consts::SQRT_2;
+use std::sync::Mutex;
+use util::floats::{self, fast_clamp};
+use util::math::erf_inv;
+
+/// A single coordinate of the primary sample space.
+#[derive(Debug, Default, Copy, Clone)]
+struct PrimarySample {
+    value: f32,
+    last_modified: u64,
+    backup_value: f32,
+    backup_modified: u64,
+}
+
+impl PrimarySample {
+    fn backup(&mut self) {
+        self.backup_value = self.value;
+        self.backup_modified = self.last_modified;
+    }
+
+    fn restore(&mut self) {
+        self.value = self.backup_value;
+        self.last_modified = self.backup_modified;
+    }
+}
+
+#[derive(Debug)]
+struct MltState {
+    rng: fastrand::Rng,
+    samples: Vec<PrimarySample>,
+    index: usize,
+    iteration: u64,
+    large_step: bool,
+    last_large_step: u64,
+}
+
+/// A sampler mutating a vector in primary sample space (Kelemen et al.).
+///
+/// Every iteration either replaces all samples (large step) or perturbs them slightly
+/// (small step). Samples are created and mutated lazily once they are requested, such that the
+/// consumer of the sampler can use an arbitrary number of samples.
+#[derive(Debug)]
+pub struct MltSampler {
+    /// The standard deviation of small step mutations
+    pub sigma: f32,
+    /// The probability of a large step mutation
+    pub large_step_probability: f32,
+    state: Mutex<MltState>,
+}
+
+impl MltSampler {
+    /// # Summary
+    /// Creates a new sampler. Samplers with the same seed produce the same initial samples.
+    ///
+    /// # Arguments
+    /// * `seed` - The seed of the random number generator
+    /// * `sigma` - The standard deviation of small step mutations
+    /// * `large_step_probability` - The probability of a large step mutation
+    ///
+    /// # Results
+    /// * `Self` - The sampler
+    pub fn new(seed: u64, sigma: f32, large_step_probability: f32) -> Self {
+        let state = MltState {
+            rng: fastrand::Rng::with_seed(seed),
+            samples: Vec::new(),
+            index: 0,
+            iteration: 0,
+            large_step: true,
+            last_large_step: 0,
+        };
+
+        Self {
+            sigma,
+            large_step_probability,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Starts a new iteration, deciding on a large or small step mutation.
+    pub fn start_iteration(&self) {
+        let mut state = self.state.lock().expect("Sampler state is poisoned");
+
+        state.iteration += 1;
+        state.large_step = state.rng.f32() < self.large_step_probability;
+        state.index = 0;
+    }
+
+    /// Accepts the mutations of the current iteration.
+    pub fn accept(&self) {
+        let mut state = self.state.lock().expect("Sampler state is poisoned");
+
+        if state.large_step {
+            state.last_large_step = state.iteration;
+        }
+    }
+
+    /// Rejects the mutations of the current iteration and restores the previous samples.
+    pub fn reject(&self) {
+        let mut state = self.state.lock().expect("Sampler state is poisoned");
+
+        let iteration = state.iteration;
+        state
+            .samples
+            .iter_mut()
+            .filter(|s| s.last_modified == iteration)
+            .for_each(PrimarySample::restore);
+
+        state.iteration -= 1;
+    }
+
+    /// Returns a uniform random number, independent of the primary samples.
+    pub fn uniform(&self) -> f32 {
+        self.state
+            .lock()
+            .expect("Sampler state is poisoned")
+            .rng
+            .f32()
+    }
+}
+
+impl Sampler for MltSampler {
+    fn get_1d(&self) -> f32 {
+        let mut state = self.state.lock().expect("Sampler state is poisoned");
+        let MltState {
+            rng,
+            samples,
+            index,
+            iteration,
+            large_step,
+            last_large_step,
+        } = &mut *state;
+
+        let i = *index;
+        *index += 1;
+
+        if i >= samples.len() {
+            samples.resize(i + 1, PrimarySample::default());
+        }
+        let sample = &mut samples[i];
+
+        // samples not modified since the last accepted large step are outdated
+        if sample.last_modified < *last_large_step {
+            sample.value = rng.f32();
+            sample.last_modified = *last_large_step;
+        }
+
+        sample.backup();
+        if *large_step {
+            sample.value = rng.f32();
+        } else {
+            // apply all missed small steps at once
+            let small_steps = (*iteration - sample.last_modified) as f32;
+            let u = fast_clamp(rng.f32(), floats::EPSILON, 1.0 - floats::EPSILON);
+            let normal = SQRT_2 * erf_inv(2.0 * u - 1.0);
+
+            sample.value += normal * self.sigma * small_steps.sqrt();
+            sample.value -= sample.value.floor();
+        }
+        sample.last_modified = *iteration;
+
+        // guard against rounding up to one
+        sample.value.min(1.0 - f32::EPSILON)
+    }
+}
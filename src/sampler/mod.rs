@@ -1,5 +1,7 @@
 use ultraviolet::{Vec2, Vec3};
 
+pub mod mlt;
+
 /// A sample consisting of a 1D and 2D sample.
 #[derive(Debug)]
 pub struct Sample {
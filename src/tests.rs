@@ -0,0 +1,1663 @@
+#[cfg(test)]
+mod integrators {
+    use crate::bxdf::bsdf::BSDF;
+    use crate::bxdf::lambertian::LambertianReflection;
+    use crate::demo_scenes::cornell_box::*;
+    use crate::integrator::ambient_occlusion::AmbientOcclusion;
+    use crate::integrator::bdpt::Bdpt;
+    use crate::integrator::direct_lighting::{DirectLighting, LightStrategy};
+    use crate::integrator::light_tracing::LightTracing;
+    use crate::integrator::mlt::{Mlt, MltCore};
+    use crate::integrator::path::Path;
+    use crate::integrator::spectral_path::SpectralPath;
+    use crate::integrator::Integrator;
+    use crate::render::camera::Camera;
+    use crate::render::film::Film;
+    use crate::render::objects::emitter::EmitterObj;
+    use crate::render::objects::receiver::ReceiverObj;
+    use crate::render::objects::Instance;
+    use crate::render::objects::Instance::{Emitter, Receiver};
+    use crate::render::scene::Scene;
+    use crate::sampler::{RandomSampler, Sampler};
+    use crate::Spectrum;
+    use color::{Color, Spectral, Srgb, Xyz};
+    use geometry::aabb::Aabb;
+    use geometry::point::Point;
+    use geometry::ray::Ray;
+    use std::sync::Arc;
+    use ultraviolet::Vec3;
+
+    const WIDTH: u32 = 16;
+    const HEIGHT: u32 = 16;
+    const DEPTH: u32 = 3;
+
+    fn wall<S: Spectrum>(min: Vec3, max: Vec3, color: S) -> Instance<S> {
+        let lambertian = LambertianReflection::new(color * 0.5);
+        let bsdf = BSDF::new(vec![Box::new(lambertian)]);
+
+        Receiver(Arc::new(ReceiverObj::new(
+            Aabb::new(min, max),
+            Arc::new(bsdf),
+        )))
+    }
+
+    /// A cornell box of diffuse walls lit by a point light below the ceiling.
+    fn diffuse_box<S: Spectrum>() -> Scene<S> {
+        cornell_box(S::red(), S::green())
+    }
+
+    /// A cornell box of diffuse walls with the given colors on the left and right, otherwise
+    /// white.
+    fn cornell_box<S: Spectrum>(left_color: S, right_color: S) -> Scene<S> {
+        let mut scene = Scene::default();
+
+        let (left, right, back) = (LEFT_WALL - THICKNESS, RIGHT_WALL + THICKNESS, BACK_WALL);
+        scene
+            .add(wall(
+                Vec3::new(left, FLOOR, back),
+                Vec3::new(LEFT_WALL, CEILING, FRONT),
+                left_color,
+            ))
+            .add(wall(
+                Vec3::new(RIGHT_WALL, FLOOR, back),
+                Vec3::new(right, CEILING, FRONT),
+                right_color,
+            ))
+            .add(wall(
+                Vec3::new(left, FLOOR, back - THICKNESS),
+                Vec3::new(right, CEILING, back),
+                S::white(),
+            ))
+            .add(wall(
+                Vec3::new(left, FLOOR - THICKNESS, back),
+                Vec3::new(right, FLOOR, FRONT),
+                S::white(),
+            ))
+            .add(wall(
+                Vec3::new(left, CEILING, back),
+                Vec3::new(right, CEILING + THICKNESS, FRONT),
+                S::white(),
+            ));
+
+        let position = Vec3::new(X_CENTER, CEILING - RADIUS, Z_CENTER);
+        let bsdf = BSDF::new(vec![Box::new(LambertianReflection::new(S::white()))]);
+        let emission = S::white() * 20.0;
+        scene.add(Emitter(Arc::new(EmitterObj::new(
+            Point::new(position),
+            Arc::new(bsdf),
+            emission,
+        ))));
+
+        scene.build_bvh();
+        scene
+    }
+
+    fn camera() -> Camera {
+        let position = Vec3::new(X_CENTER, Y_CENTER, FRONT + 5.0);
+        let center = Vec3::new(X_CENTER, Y_CENTER, Z_CENTER);
+
+        Camera::new(position, center, Vec3::unit_y(), 70.0, WIDTH, HEIGHT)
+    }
+
+    /// Renders the average of the given number of samples per pixel, including the contributions
+    /// splatted onto the film.
+    fn render<S: Spectrum>(
+        integrator: &dyn Integrator<S>,
+        scene: &Scene<S>,
+        camera: &Camera,
+        film: &Film<S>,
+        spp: u32,
+    ) -> Vec<S> {
+        let sampler: Arc<dyn Sampler> = Arc::new(RandomSampler::default());
+
+        let mut image = vec![S::black(); (WIDTH * HEIGHT) as usize];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let pixel = &mut image[(y * WIDTH + x) as usize];
+                for _ in 0..spp {
+                    let ray = camera.primary_ray(x, y, &sampler.get_2d());
+                    *pixel += integrator.integrate(scene, &ray, sampler.clone());
+                }
+            }
+        }
+
+        image
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                let (x, y) = (i as u32 % WIDTH, i as u32 / WIDTH);
+                (*pixel + film.get(x, y)) / spp as f32
+            })
+            .collect()
+    }
+
+    /// The average of the quadrants of the image, a coarse comparison robust to noise.
+    fn quadrants<S: Spectrum>(image: &[S]) -> [S; 4] {
+        let mut quadrants = [S::black(); 4];
+        for (i, pixel) in image.iter().enumerate() {
+            let (x, y) = (i as u32 % WIDTH, i as u32 / WIDTH);
+            let quadrant = (2 * y / HEIGHT * 2 + 2 * x / WIDTH) as usize;
+            quadrants[quadrant] += *pixel / (image.len() / 4) as f32;
+        }
+
+        quadrants
+    }
+
+    fn assert_converges<S: Spectrum, R: Spectrum>(image: &[S], reference: &[R], tolerance: f32) {
+        let (quadrants, reference) = (quadrants(image), quadrants(reference));
+        for (q, r) in quadrants.iter().zip(reference.iter()) {
+            // spectra are compared by their color, single wavelengths are too noisy
+            let (q, r) = (q.to_rgb(), r.to_rgb());
+            for c in 0..3 {
+                let error = (q[c] - r[c]).abs() / r[c];
+                assert!(error < tolerance, "{:?} differs from {:?}", q, r);
+            }
+        }
+    }
+
+    fn reference<S: Spectrum>(scene: &Scene<S>, camera: &Camera) -> Vec<S> {
+        let film = Film::new(WIDTH, HEIGHT);
+        render(&Path::new(0, DEPTH), scene, camera, &film, 256)
+    }
+
+    #[test]
+    fn bdpt_converges_to_path() {
+        let (scene, camera) = (diffuse_box::<Srgb>(), Arc::new(camera()));
+        let film = Arc::new(Film::new(WIDTH, HEIGHT));
+
+        let bdpt = Bdpt::new(DEPTH, camera.clone(), film.clone());
+        let image = render(&bdpt, &scene, &camera, &film, 128);
+
+        assert_converges(&image, &reference(&scene, &camera), 0.05);
+    }
+
+    #[test]
+    fn mlt_converges_to_path() {
+        let (scene, camera) = (Arc::new(diffuse_box::<Srgb>()), Arc::new(camera()));
+        let film = Arc::new(Film::new(WIDTH, HEIGHT));
+
+        let core = MltCore::Path(Path::new(0, DEPTH));
+        let mlt = Mlt::new(core, camera.clone(), film.clone(), 4, 10_000, 64, 0.01, 0.3);
+        mlt.begin_pass(&scene, 0);
+        let image = render(&mlt, &scene, &camera, &film, 1024);
+
+        assert_converges(&image, &reference(&scene, &camera), 0.1);
+    }
+
+    #[test]
+    fn light_tracing_converges_to_path() {
+        let (scene, camera) = (diffuse_box::<Srgb>(), Arc::new(camera()));
+        let film = Arc::new(Film::new(WIDTH, HEIGHT));
+
+        let light_tracing = LightTracing::new(DEPTH, camera.clone(), film.clone());
+        let image = render(&light_tracing, &scene, &camera, &film, 128);
+
+        assert_converges(&image, &reference(&scene, &camera), 0.05);
+    }
+
+    #[test]
+    fn spectral_path_converges_to_path() {
+        let (scene, camera) = (diffuse_box::<Spectral>(), Arc::new(camera()));
+        let film = Arc::new(Film::new(WIDTH, HEIGHT));
+
+        let spectral_path = SpectralPath::new(DEPTH, DEPTH);
+        let image = render(&spectral_path, &scene, &camera, &film, 1024);
+
+        assert_converges(&image, &reference(&scene, &camera), 0.08);
+    }
+
+    /// Renders the neutral cornell box with the path tracer in the given colors.
+    fn render_neutral<S: Spectrum>() -> Vec<S> {
+        let scene = cornell_box(S::white(), S::white());
+        let film = Film::new(WIDTH, HEIGHT);
+
+        render(&Path::new(0, DEPTH), &scene, &camera(), &film, 256)
+    }
+
+    #[test]
+    fn path_agrees_across_spectra() {
+        let srgb = quadrants(&render_neutral::<Srgb>());
+        let xyz = quadrants(&render_neutral::<Xyz>());
+        let spectral = quadrants(&render_neutral::<Spectral>());
+
+        // colors only agree in their luminance, since products of XYZ colors are not products of
+        // reflectances
+        for i in 0..4 {
+            let reference = srgb[i].luminance();
+            assert!((xyz[i].luminance() - reference).abs() / reference < 0.01);
+            assert!((spectral[i].luminance() - reference).abs() / reference < 0.01);
+        }
+    }
+
+    #[test]
+    fn ambient_occlusion_within_distance() {
+        let mut scene = Scene::default();
+        scene
+            .add(wall(
+                Vec3::new(-50.0, -1.0, -50.0),
+                Vec3::new(50.0, 0.0, 50.0),
+                Srgb::white(),
+            ))
+            .add(wall(
+                Vec3::new(-50.0, 0.5, -50.0),
+                Vec3::new(50.0, 1.0, 50.0),
+                Srgb::white(),
+            ));
+        scene.build_bvh();
+
+        let ray = Ray::new(Vec3::new(0.0, 0.25, 0.0), -Vec3::unit_y());
+        let sampler: Arc<dyn Sampler> = Arc::new(RandomSampler::default());
+
+        let near = AmbientOcclusion::new(0.1, 16).integrate(&scene, &ray, sampler.clone());
+        assert_eq!(near, Srgb::white());
+
+        let far = AmbientOcclusion::new(10.0, 16).integrate(&scene, &ray, sampler.clone());
+        assert!(far[0] < 0.1, "{:?} is not occluded", far);
+
+        // no samples are clamped to a single one instead of dividing by zero
+        let none = AmbientOcclusion::new(0.1, 0).integrate(&scene, &ray, sampler);
+        assert_eq!(none, Srgb::white());
+    }
+
+    #[test]
+    fn direct_lighting_strategies_agree_with_path() {
+        let mut scene = diffuse_box::<Srgb>();
+        // a second, dimmer light makes the strategies differ in their choice
+        let position = Vec3::new(LEFT_WALL + 1.0, FLOOR + 1.0, Z_CENTER);
+        let bsdf = BSDF::new(vec![Box::new(LambertianReflection::new(Srgb::white()))]);
+        scene.add(Emitter(Arc::new(EmitterObj::new(
+            Point::new(position),
+            Arc::new(bsdf),
+            Srgb::white() * 2.0,
+        ))));
+        scene.build_bvh();
+
+        let camera = camera();
+        let film = Film::new(WIDTH, HEIGHT);
+        let reference = render(&Path::new(0, 1), &scene, &camera, &film, 256);
+
+        for strategy in [
+            LightStrategy::All,
+            LightStrategy::Uniform,
+            LightStrategy::Power,
+        ] {
+            let direct = DirectLighting::new(1, strategy, 4, &scene);
+            let image = render(&direct, &scene, &camera, &film, 256);
+
+            assert_converges(&image, &reference, 0.05);
+        }
+    }
+}
+
+#[cfg(test)]
+mod grid {
+    use crate::medium::grid::{DensityGrid, GridMedium};
+    use crate::medium::Medium;
+    use crate::sampler::RandomSampler;
+    use crate::Spectrum;
+    use color::{Spectral, Srgb};
+    use geometry::aabb::Aabb;
+    use geometry::ray::Ray;
+    use std::path::PathBuf;
+    use ultraviolet::Vec3;
+
+    fn write_grid(name: &str, header: &[u32], data: &[f32]) -> PathBuf {
+        let mut bytes = Vec::new();
+        for h in header {
+            bytes.extend_from_slice(&h.to_le_bytes());
+        }
+        for d in data {
+            bytes.extend_from_slice(&d.to_le_bytes());
+        }
+
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, bytes).expect("Could not write grid file");
+
+        path
+    }
+
+    fn ramp() -> Vec<f32> {
+        (0..2 * 3 * 4).map(|i| i as f32 / 4.0).collect()
+    }
+
+    #[test]
+    fn load() {
+        let path = write_grid("rust_v_load.grid", &[2, 3, 4], &ramp());
+        let grid = DensityGrid::load(path.to_str().unwrap()).unwrap();
+
+        assert_eq!((2, 3, 4), (grid.nx, grid.ny, grid.nz));
+        assert_eq!(23.0 / 4.0, grid.max());
+
+        // the voxel centers hold the densities
+        let center = Vec3::new(1.5 / 2.0, 2.5 / 3.0, 3.5 / 4.0);
+        assert!((grid.lookup(&center) - 23.0 / 4.0).abs() < 1e-5);
+        let center = Vec3::new(0.5 / 2.0, 1.5 / 3.0, 0.5 / 4.0);
+        assert!((grid.lookup(&center) - 2.0 / 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn load_missing_resolution() {
+        let path = write_grid("rust_v_missing_resolution.grid", &[2, 3], &[]);
+
+        assert!(DensityGrid::load(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn load_wrong_size() {
+        let path = write_grid("rust_v_wrong_size.grid", &[2, 3, 5], &ramp());
+
+        assert!(DensityGrid::load(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn load_negative() {
+        let path = write_grid("rust_v_negative.grid", &[1, 1, 1], &[-1.0]);
+
+        assert!(DensityGrid::load(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn load_shipped() {
+        let grid = DensityGrid::load("./resources/grids/smoke.grid").unwrap();
+
+        assert_eq!((32, 32, 32), (grid.nx, grid.ny, grid.nz));
+    }
+
+    /// The transmittance through the center of the unit cube, integrated numerically.
+    fn transmittance<S: Spectrum>(grid: &DensityGrid, sigma_t: S) -> S {
+        let steps = 10_000;
+        let depth: f32 = (0..steps)
+            .map(|i| grid.lookup(&Vec3::new((i as f32 + 0.5) / steps as f32, 0.5, 0.5)))
+            .sum::<f32>()
+            / steps as f32;
+
+        (sigma_t * -depth).exp()
+    }
+
+    fn assert_colored_extinction<S: Spectrum>() {
+        let grid = DensityGrid::new(2, 2, 2, vec![0.5, 1.0, 0.25, 0.75, 1.0, 0.5, 0.0, 0.25]);
+        let sigma_a = S::from_rgb(Srgb::new([0.2, 0.5, 1.0]));
+        let sigma_s = S::from_rgb(Srgb::new([0.3, 1.5, 2.0]));
+        let expected = transmittance(&grid, sigma_a + sigma_s);
+
+        let bounds = Aabb::new(Vec3::zero(), Vec3::one());
+        let medium = GridMedium::new(grid, bounds, sigma_a, sigma_s, 0.0);
+        let ray = Ray::new(Vec3::new(-1.0, 0.5, 0.5), Vec3::unit_x());
+        let sampler = RandomSampler::default();
+
+        let n = 100_000;
+        let mut ratio_tracking = S::black();
+        let mut escaped = S::black();
+        for _ in 0..n {
+            ratio_tracking += medium.transmittance(&ray, &sampler);
+
+            let sample = medium.sample(&ray, &sampler);
+            if sample.point.is_none() {
+                escaped += sample.beta;
+            }
+        }
+
+        for i in 0..S::SIZE {
+            assert!((ratio_tracking[i] / n as f32 - expected[i]).abs() < 0.01);
+            assert!((escaped[i] / n as f32 - expected[i]).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn colored_extinction() {
+        assert_colored_extinction::<Srgb>();
+        assert_colored_extinction::<Spectral>();
+    }
+}
+
+#[cfg(test)]
+mod spectral {
+    use color::{Color, Spectral, WavelengthSample, HERO_SAMPLES, SPECTRAL_SAMPLES};
+
+    #[test]
+    fn constant_spectrum_is_equal_energy_white() {
+        let xyz = Spectral::new_const(1.0).to_xyz();
+        let sum = xyz[0] + xyz[1] + xyz[2];
+
+        // the visible range misses a tiny bit of the color matching functions
+        assert!((xyz[1] - 1.0).abs() < 0.01, "{:?}", xyz);
+        assert!((xyz[0] / sum - 1.0 / 3.0).abs() < 0.01, "{:?}", xyz);
+        assert!((xyz[1] / sum - 1.0 / 3.0).abs() < 0.01, "{:?}", xyz);
+    }
+
+    #[test]
+    fn from_sampled_averages_unsorted_samples() {
+        let spectrum = Spectral::from_sampled(&[700.0, 400.0], &[1.0, 0.0], 2);
+
+        for i in 0..SPECTRAL_SAMPLES {
+            let expected = (Spectral::lambda(i) - 400.0) / 300.0;
+            assert!((spectrum[i] - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn primaries_dominate_their_channel() {
+        let primaries = [Spectral::red(), Spectral::green(), Spectral::blue()];
+
+        for (channel, primary) in primaries.iter().enumerate() {
+            let rgb = primary.to_rgb();
+            for other in (0..3).filter(|c| *c != channel) {
+                assert!(rgb[channel] > rgb[other], "{:?}", rgb);
+            }
+        }
+    }
+
+    #[test]
+    fn hero_wavelengths_are_unbiased() {
+        let mut full = Spectral::black();
+        let mut terminated = Spectral::black();
+
+        // every hero wavelength once
+        for i in 0..SPECTRAL_SAMPLES {
+            let mut wavelengths = WavelengthSample::new((i as f32 + 0.5) / SPECTRAL_SAMPLES as f32);
+            assert_eq!(i, wavelengths.hero());
+            let mask = wavelengths.mask();
+            assert_eq!(
+                HERO_SAMPLES as f32,
+                (0..SPECTRAL_SAMPLES).map(|i| mask[i]).sum()
+            );
+            full += wavelengths.mask() * wavelengths.weight();
+
+            wavelengths.terminate_secondary();
+            terminated += wavelengths.mask() * wavelengths.weight();
+        }
+
+        for i in 0..SPECTRAL_SAMPLES {
+            assert!((full[i] / SPECTRAL_SAMPLES as f32 - 1.0).abs() < 1e-5);
+            assert!((terminated[i] / SPECTRAL_SAMPLES as f32 - 1.0).abs() < 1e-5);
+        }
+    }
+}
+
+#[cfg(test)]
+mod upsampling {
+    use color::upsampling::{illuminant, reflectance};
+    use color::{Color, Srgb, SPECTRAL_SAMPLES};
+
+    fn colors() -> Vec<Srgb> {
+        vec![
+            Srgb::white(),
+            Srgb::new([0.5, 0.5, 0.5]),
+            Srgb::red(),
+            Srgb::green(),
+            Srgb::blue(),
+            Srgb::new([0.8, 0.4, 0.1]),
+            Srgb::new([0.2, 0.6, 0.9]),
+            Srgb::new([0.7, 0.2, 0.6]),
+        ]
+    }
+
+    #[test]
+    fn reflectance_is_bounded() {
+        for rgb in colors() {
+            let spectrum = reflectance(&rgb);
+            assert!((0..SPECTRAL_SAMPLES).all(|i| (0.0..=1.0).contains(&spectrum[i])));
+        }
+
+        // grays are flat
+        let gray = reflectance(&Srgb::new([0.5, 0.5, 0.5]));
+        assert!((0..SPECTRAL_SAMPLES).all(|i| (gray[i] - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn illuminant_round_trip() {
+        // the smooth basis spectra only approximate saturated colors
+        for rgb in colors() {
+            let round_trip = illuminant(&rgb).to_rgb();
+            for c in 0..3 {
+                assert!(
+                    (round_trip[c] - rgb[c]).abs() < 0.15,
+                    "{:?} != {:?}",
+                    round_trip,
+                    rgb
+                );
+            }
+        }
+
+        // white lights are exactly white
+        let white = illuminant(&Srgb::white()).to_rgb();
+        for c in 0..3 {
+            assert!((white[c] - 1.0).abs() < 1e-3, "{:?} is not white", white);
+        }
+    }
+}
+
+#[cfg(test)]
+mod dispersion {
+    use crate::bxdf::fresnel::Dielectric;
+    use crate::bxdf::refractive_index::{RefractiveIndex, REFERENCE_WAVELENGTH};
+    use crate::bxdf::specular::SpecularTransmission;
+    use crate::bxdf::{cos_theta, BxDF};
+    use color::{Color, Srgb};
+    use std::sync::Arc;
+    use ultraviolet::{Vec2, Vec3};
+
+    const BLUE: f32 = 450.0;
+    const RED: f32 = 650.0;
+
+    #[test]
+    fn refractive_indices() {
+        // the catalog values at the helium d line (587.6 nm) and the sodium D line (589.3 nm)
+        assert!((RefractiveIndex::bk7().at(587.6) - 1.5168).abs() < 1e-3);
+        assert!((RefractiveIndex::fused_silica().at(589.3) - 1.4585).abs() < 1e-3);
+        assert!((RefractiveIndex::diamond().at(589.3) - 2.417).abs() < 1e-2);
+
+        let constant = RefractiveIndex::Constant(1.5);
+        assert!(!constant.is_dispersive());
+        assert_eq!(constant.at(BLUE), constant.at(RED));
+    }
+
+    #[test]
+    fn normal_dispersion() {
+        for eta in [
+            RefractiveIndex::bk7(),
+            RefractiveIndex::fused_silica(),
+            RefractiveIndex::diamond(),
+            RefractiveIndex::Cauchy { a: 1.5, b: 0.004 },
+        ]
+        .iter()
+        {
+            assert!(eta.is_dispersive());
+            assert!(eta.at(BLUE) > eta.at(REFERENCE_WAVELENGTH));
+            assert!(eta.at(REFERENCE_WAVELENGTH) > eta.at(RED));
+        }
+    }
+
+    #[test]
+    fn blue_refracts_stronger() {
+        let fresnel =
+            Dielectric::dispersive(RefractiveIndex::Constant(1.0), RefractiveIndex::bk7());
+        let transmission = SpecularTransmission::new(Srgb::white(), Arc::new(fresnel));
+        assert!(transmission.is_dispersive());
+
+        let outgoing = Vec3::new(1.0, 1.0, 0.0).normalized();
+        let sample = Vec2::new(0.5, 0.5);
+        let blue = transmission.sample_wavelength(&outgoing, &sample, BLUE);
+        let red = transmission.sample_wavelength(&outgoing, &sample, RED);
+
+        // refracted into the lower hemisphere, blue closer to the normal
+        assert!(cos_theta(&blue.incident) < 0.0 && cos_theta(&red.incident) < 0.0);
+        assert!(cos_theta(&blue.incident).abs() > cos_theta(&red.incident).abs());
+
+        // Snell's law at the sampled wavelength
+        let sin_o = (1.0 - cos_theta(&outgoing).powi(2)).sqrt();
+        let sin_i = (1.0 - cos_theta(&blue.incident).powi(2)).sqrt();
+        assert!((sin_o - RefractiveIndex::bk7().at(BLUE) * sin_i).abs() < 1e-4);
+    }
+}
+
+#[cfg(test)]
+mod illuminant {
+    use color::color_space::{ChromaticAdaptation, ColorSpace};
+    use color::illuminant::Illuminant;
+    use color::response::ResponseCurve;
+    use std::convert::TryInto;
+    use ultraviolet::Vec3;
+
+    /// The chromaticity of the white point of the illuminant.
+    fn chromaticity(illuminant: Illuminant) -> (f32, f32) {
+        let white = illuminant.white();
+        let sum = white[0] + white[1] + white[2];
+
+        (white[0] / sum, white[1] / sum)
+    }
+
+    #[test]
+    fn fluorescent_chromaticities() {
+        // the chromaticities tabulated by the CIE
+        let expected = [
+            (Illuminant::F1, 0.3131, 0.3373),
+            (Illuminant::F2, 0.3721, 0.3751),
+            (Illuminant::F3, 0.4091, 0.3941),
+            (Illuminant::F4, 0.4402, 0.4031),
+            (Illuminant::F5, 0.3138, 0.3452),
+            (Illuminant::F6, 0.3779, 0.3882),
+            (Illuminant::F7, 0.3129, 0.3292),
+            (Illuminant::F8, 0.3458, 0.3586),
+            (Illuminant::F9, 0.3741, 0.3727),
+            (Illuminant::F10, 0.3458, 0.3588),
+            (Illuminant::F11, 0.3805, 0.3769),
+            (Illuminant::F12, 0.4370, 0.4042),
+        ];
+
+        for (illuminant, x, y) in expected.iter() {
+            let (cx, cy) = chromaticity(*illuminant);
+
+            assert!((cx - x).abs() < 0.005, "{:?}: x = {}", illuminant, cx);
+            assert!((cy - y).abs() < 0.005, "{:?}: y = {}", illuminant, cy);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let parse = |s: &str| -> Result<Illuminant, String> { s.try_into() };
+
+        assert!(matches!(parse("D65"), Ok(Illuminant::D65)));
+        assert!(matches!(parse("a"), Ok(Illuminant::A)));
+        assert!(matches!(parse("F7"), Ok(Illuminant::F7)));
+        assert!(matches!(parse("f12"), Ok(Illuminant::F12)));
+        assert!(matches!(parse("3200"), Ok(Illuminant::Blackbody(t)) if t == 3200.0));
+        assert!(matches!(parse("5000K"), Ok(Illuminant::Blackbody(t)) if t == 5000.0));
+
+        assert!(parse("F13").is_err());
+        assert!(parse("-100").is_err());
+        assert!(parse("tungsten").is_err());
+    }
+
+    #[test]
+    fn white_balance_neutralizes_white() {
+        let space = ColorSpace::Srgb;
+
+        for adaptation in [ChromaticAdaptation::Bradford, ChromaticAdaptation::Cat02].iter() {
+            for illuminant in [Illuminant::A, Illuminant::F2, Illuminant::Blackbody(9000.0)].iter()
+            {
+                let white = illuminant.white().to_vec3();
+                let balance = space.white_balance_mat(white, *adaptation);
+
+                // the light appears as white, and white of the color space as the light did
+                let rgb = balance * (space.from_xyz_mat() * white);
+                assert!(
+                    (rgb - Vec3::one()).abs().component_max() < 1e-3,
+                    "{:?}",
+                    rgb
+                );
+            }
+
+            // daylight is already balanced
+            let d65 = Illuminant::D65.white().to_vec3();
+            let rgb = space.white_balance_mat(d65, *adaptation) * Vec3::new(0.2, 0.5, 0.8);
+            assert!((rgb - Vec3::new(0.2, 0.5, 0.8)).abs().component_max() < 5e-3);
+        }
+    }
+
+    #[test]
+    fn response_curves() {
+        for response in [
+            ResponseCurve::Reinhard,
+            ResponseCurve::Filmic,
+            ResponseCurve::Aces,
+        ]
+        .iter()
+        {
+            assert!(response.apply(0.0).abs() < 1e-2);
+            assert!(response.apply(-1.0).abs() < 1e-2);
+            assert!(response.apply(1000.0) > 0.99 && response.apply(1000.0) <= 1.0);
+
+            // monotonic
+            let values: Vec<f32> = (0..100).map(|i| response.apply(i as f32 / 10.0)).collect();
+            assert!(values.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod color_space {
+    use crate::render::png;
+    use color::color_space::{ChromaticAdaptation, ColorSpace};
+    use image::ColorType;
+    use std::convert::TryInto;
+    use ultraviolet::{Mat3, Vec3};
+
+    const SPACES: [ColorSpace; 6] = [
+        ColorSpace::Srgb,
+        ColorSpace::Rec709,
+        ColorSpace::Rec2020,
+        ColorSpace::DisplayP3,
+        ColorSpace::AdobeRgb,
+        ColorSpace::AcesCg,
+    ];
+
+    fn assert_vec_eq(a: Vec3, b: Vec3) {
+        assert!((a - b).abs().component_max() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn assert_mat_eq(a: Mat3, b: Mat3) {
+        for (a, b) in a.cols.iter().zip(b.cols.iter()) {
+            assert_vec_eq(*a, *b);
+        }
+    }
+
+    #[test]
+    fn white_maps_to_white_point() {
+        for space in SPACES.iter() {
+            assert_vec_eq(space.white(), space.to_xyz_mat() * Vec3::one());
+            assert_vec_eq(Vec3::one(), space.from_xyz_mat() * space.white());
+        }
+    }
+
+    #[test]
+    fn srgb_white_is_d65() {
+        let d65 = ColorSpace::Srgb.white();
+
+        assert_vec_eq(d65, color::srgb_to_xyz_mat() * Vec3::one());
+        assert_vec_eq(Vec3::one(), color::xyz_to_srgb_mat() * d65);
+        assert_mat_eq(ColorSpace::Srgb.to_xyz_mat(), color::srgb_to_xyz_mat());
+    }
+
+    #[test]
+    fn conversions_invert() {
+        for adaptation in [ChromaticAdaptation::Bradford, ChromaticAdaptation::Cat02].iter() {
+            for from in SPACES.iter() {
+                assert_mat_eq(Mat3::identity(), from.conversion_mat(*from, *adaptation));
+
+                for to in SPACES.iter() {
+                    let there = from.conversion_mat(*to, *adaptation);
+                    let back = to.conversion_mat(*from, *adaptation);
+                    assert_mat_eq(Mat3::identity(), back * there);
+
+                    // white stays white, even across white points
+                    assert_vec_eq(Vec3::one(), there * Vec3::one());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn adaptation_maps_white_points() {
+        let (d65, aces) = (ColorSpace::Srgb.white(), ColorSpace::AcesCg.white());
+
+        for adaptation in [ChromaticAdaptation::Bradford, ChromaticAdaptation::Cat02].iter() {
+            assert_vec_eq(aces, adaptation.matrix(d65, aces) * d65);
+            assert_vec_eq(d65, adaptation.matrix(aces, d65) * aces);
+        }
+    }
+
+    #[test]
+    fn transfer_functions_invert() {
+        for space in SPACES.iter() {
+            assert_eq!(0.0, space.encode(0.0));
+            assert!((space.encode(1.0) - 1.0).abs() < 1e-5);
+
+            for i in 0..=100 {
+                let linear = i as f32 / 100.0;
+                assert!((space.decode(space.encode(linear)) - linear).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let expected = [
+            ("sRGB", ColorSpace::Srgb),
+            ("Rec709", ColorSpace::Rec709),
+            ("REC2020", ColorSpace::Rec2020),
+            ("P3", ColorSpace::DisplayP3),
+            ("adobe-rgb", ColorSpace::AdobeRgb),
+            ("ACEScg", ColorSpace::AcesCg),
+        ];
+        for (name, space) in expected.iter() {
+            assert_eq!(Ok(*space), (*name).try_into());
+        }
+
+        let invalid: Result<ColorSpace, String> = "CMYK".try_into();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn crc() {
+        // the checksum of every IEND chunk
+        assert_eq!(0xAE42_6082, png::crc32(b"IEND"));
+    }
+
+    /// Splits a PNG file into its chunks, checking their checksums.
+    fn read_chunks(file: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &file[..8]);
+
+        let mut chunks = Vec::new();
+        let mut rest = &file[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let (typ, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = &rest[8 + length..12 + length];
+
+            assert_eq!(&png::crc32(&rest[4..8 + length]).to_be_bytes(), crc);
+            chunks.push(([typ[0], typ[1], typ[2], typ[3]], data.to_vec()));
+            rest = &rest[12 + length..];
+        }
+
+        chunks
+    }
+
+    #[test]
+    fn tagged_png() {
+        let data = [0, 64, 128, 255, 255, 255, 128, 64, 0, 32, 32, 32];
+        let file = png::encode(&data, 2, 2, ColorType::Rgb8, ColorSpace::DisplayP3).unwrap();
+
+        let chunks = read_chunks(&file);
+        let types: Vec<&[u8; 4]> = chunks.iter().map(|(typ, _)| typ).collect();
+        assert_eq!(&[b"IHDR", b"gAMA", b"cHRM", b"IDAT", b"IEND"], &types[..]);
+
+        // in units of 1/100000
+        let values = |data: &[u8]| -> Vec<u32> {
+            data.chunks(4)
+                .map(|v| u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+                .collect()
+        };
+        assert_eq!(vec![45455], values(&chunks[1].1));
+        assert_eq!(
+            vec![31270, 32900, 68000, 32000, 26500, 69000, 15000, 6000],
+            values(&chunks[2].1)
+        );
+
+        // still readable, with unchanged pixels
+        let image = image::load_from_memory(&file).unwrap().to_rgb8();
+        assert_eq!(&data[..], &image.into_raw()[..]);
+
+        let file = png::encode(&data, 2, 2, ColorType::Rgb8, ColorSpace::Srgb).unwrap();
+        assert_eq!(b"sRGB", &read_chunks(&file)[1].0);
+    }
+}
+
+#[cfg(test)]
+mod polarization {
+    use crate::polarization::{fresnel_amplitudes, Complex, Mueller, Stokes};
+    use color::Srgb;
+    use std::f32::consts::FRAC_PI_3;
+
+    fn apply(mueller: &Mueller<Srgb>, stokes: &Stokes<Srgb>) -> Stokes<Srgb> {
+        let mut result = Stokes::black();
+        for row in 0..4 {
+            result.s[row] = (0..4).map(|k| mueller.m[row][k] * stokes.s[k]).sum();
+        }
+
+        result
+    }
+
+    fn assert_valid(stokes: &Stokes<Srgb>) {
+        for c in 0..stokes.s[0].len() {
+            let i = stokes.s[0][c];
+            let polarized = (1..4).map(|k| stokes.s[k][c].powi(2)).sum::<f32>().sqrt();
+
+            assert!(i >= 0.0, "negative intensity {:?}", stokes);
+            assert!(i + 1e-4 >= polarized, "I < |(Q, U, V)| in {:?}", stokes);
+        }
+    }
+
+    fn stokes(s: [f32; 4]) -> Stokes<Srgb> {
+        Stokes {
+            s: [
+                Srgb::new_const(s[0]),
+                Srgb::new_const(s[1]),
+                Srgb::new_const(s[2]),
+                Srgb::new_const(s[3]),
+            ],
+        }
+    }
+
+    fn interactions() -> Vec<Mueller<Srgb>> {
+        let etas = [
+            Complex::from(1.5),
+            Complex::from(1.0 / 1.5),
+            Complex::new(0.2, 3.5),
+            Complex::new(1.1, 6.8),
+        ];
+
+        let mut interactions = vec![
+            Mueller::identity(),
+            Mueller::depolarizer(),
+            Mueller::rotator(FRAC_PI_3),
+            Mueller::rotator(-0.4),
+        ];
+        for &eta in &etas {
+            for &cos_i in &[1.0, 0.8, 0.5, 0.2, 0.01] {
+                let (r_s, r_p, t_s, t_p) = fresnel_amplitudes(cos_i, eta);
+                interactions.push(Mueller::from_amplitudes(|_| (r_s, r_p)));
+                if eta.im == 0.0 {
+                    interactions.push(Mueller::from_amplitudes(|_| (t_s, t_p)));
+                }
+            }
+        }
+
+        interactions
+    }
+
+    #[test]
+    fn mueller_preserves_stokes_validity() {
+        let sources = [
+            stokes([1.0, 0.0, 0.0, 0.0]),
+            stokes([1.0, 1.0, 0.0, 0.0]),
+            stokes([1.0, 0.0, -1.0, 0.0]),
+            stokes([1.0, 0.0, 0.0, 1.0]),
+            stokes([1.0, 0.3, 0.4, -0.5]),
+        ];
+        let interactions = interactions();
+
+        for source in &sources {
+            for first in &interactions {
+                let once = apply(first, source);
+                assert_valid(&once);
+
+                for second in &interactions {
+                    assert_valid(&apply(&(*second * *first), source));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unpolarized_light_stays_valid() {
+        let intensity = Srgb::new_const(2.0);
+
+        for mueller in interactions() {
+            let stokes = mueller.apply_unpolarized(intensity);
+            assert_valid(&stokes);
+            assert!((stokes.s[0][0] - 2.0).abs() < 1e-4);
+        }
+
+        let unpolarized = Stokes::unpolarized(intensity);
+        assert_eq!(0.0, unpolarized.s[1][0]);
+        assert_valid(&unpolarized);
+    }
+}
+
+#[cfg(test)]
+mod fluorescence {
+    use crate::bxdf::bsdf::BSDF;
+    use crate::bxdf::fluorescence::{FluorescentReflection, Fluorophore, Reradiation};
+    use crate::bxdf::lambertian::LambertianReflection;
+    use crate::bxdf::BxDF;
+    use crate::integrator::spectral_path::SpectralPath;
+    use crate::integrator::Integrator;
+    use crate::render::objects::emitter::EmitterObj;
+    use crate::render::objects::receiver::ReceiverObj;
+    use crate::render::objects::Instance::{Emitter, Receiver};
+    use crate::render::scene::Scene;
+    use crate::sampler::{RandomSampler, Sampler};
+    use color::illuminant::Illuminant;
+    use color::{Color, Spectral, SPECTRAL_SAMPLES};
+    use geometry::aabb::Aabb;
+    use geometry::point::Point;
+    use geometry::ray::Ray;
+    use geometry::sphere::Sphere;
+    use std::sync::Arc;
+    use ultraviolet::Vec3;
+
+    /// Rhodamine on a black base, re-radiating light without reflecting it elastically.
+    fn rhodamine() -> Reradiation {
+        let dye = Fluorophore::Rhodamine;
+
+        Reradiation::fluorescent(
+            &Spectral::new_const(0.0),
+            &dye.excitation(),
+            &dye.emission(),
+            dye.quantum_yield(),
+        )
+    }
+
+    #[test]
+    fn sample_excitation() {
+        let bxdf = FluorescentReflection::new(rhodamine());
+        let light = Illuminant::D65.spectrum();
+        let expected = bxdf.reradiate(&light);
+        let sampler = RandomSampler::default();
+
+        // the light at the sampled excitation wavelengths estimates the re-radiated light
+        let n = 10_000;
+        for emission in 0..SPECTRAL_SAMPLES {
+            let mut reradiated = 0.0;
+            for _ in 0..n {
+                if let Some((excitation, weight)) =
+                    bxdf.sample_excitation(emission, sampler.get_1d())
+                {
+                    reradiated += light[excitation] * weight;
+                }
+            }
+
+            let reradiated = reradiated / n as f32;
+            let tolerance = 0.02 * expected[emission] + 1e-4;
+            assert!((reradiated - expected[emission]).abs() < tolerance);
+        }
+    }
+
+    /// # Summary
+    /// Renders the bottom of a sphere of the given material, which is only lit indirectly by the
+    /// light of a green lamp reflected from a white floor.
+    ///
+    /// # Arguments
+    /// * `bsdf` - The material of the sphere
+    ///
+    /// # Results
+    /// * `Spectral` - The mean radiance
+    fn render_indirect(bsdf: BSDF<Spectral>) -> Spectral {
+        let mut green = Spectral::new_const(0.0);
+        for i in 0..SPECTRAL_SAMPLES {
+            if Spectral::lambda(i) < 540.0 {
+                green[i] = 50.0;
+            }
+        }
+
+        let floor = Aabb::new(Vec3::new(-10.0, -1.0, -10.0), Vec3::new(10.0, 0.0, 10.0));
+        let white = BSDF::new(vec![Box::new(LambertianReflection::new(Spectral::white()))]);
+        let lamp = Point::new(Vec3::new(0.0, 10.0, 0.0));
+        let sphere = Sphere::new(Vec3::new(0.0, 2.0, 0.0), 1.0);
+
+        let mut scene = Scene::default();
+        scene
+            .add(Receiver(Arc::new(ReceiverObj::new(floor, Arc::new(white)))))
+            .add(Receiver(Arc::new(ReceiverObj::new(sphere, Arc::new(bsdf)))))
+            .add(Emitter(Arc::new(EmitterObj::new(
+                lamp,
+                Arc::new(BSDF::empty()),
+                green,
+            ))));
+        scene.build_bvh();
+
+        let integrator = SpectralPath::new(3, 5);
+        let sampler: Arc<dyn Sampler> = Arc::new(RandomSampler::default());
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::unit_y());
+
+        let n = 10_000;
+        let mut radiance = Spectral::black();
+        for _ in 0..n {
+            radiance += integrator.integrate(&scene, &ray, sampler.clone());
+        }
+
+        radiance / n as f32
+    }
+
+    /// The radiance at wavelengths longer than the lamp emits.
+    fn longer_wavelengths(radiance: &Spectral) -> f32 {
+        (0..SPECTRAL_SAMPLES)
+            .filter(|&i| Spectral::lambda(i) > 560.0)
+            .map(|i| radiance[i])
+            .sum()
+    }
+
+    #[test]
+    fn spectral_path_reradiates() {
+        let reradiation = rhodamine();
+        let apparent = reradiation.apparent_reflectance(&Illuminant::D65.spectrum());
+
+        let fluorescent = BSDF::new(vec![Box::new(FluorescentReflection::new(reradiation))]);
+        let radiance = render_indirect(fluorescent);
+        assert!(longer_wavelengths(&radiance) > 0.0);
+
+        // a plain diffuse material of the same apparent color cannot shift the wavelengths
+        let diffuse = BSDF::new(vec![Box::new(LambertianReflection::new(apparent))]);
+        let radiance = render_indirect(diffuse);
+        assert_eq!(0.0, longer_wavelengths(&radiance));
+    }
+}
+
+#[cfg(test)]
+mod configuration {
+    use crate::configuration::*;
+    use color::color_space::ChromaticAdaptation;
+    use color::{Spectral, Srgb, Xyz};
+
+    fn config(integrator_type: IntegratorType) -> Configuration {
+        Configuration {
+            verbose: false,
+            width: 8,
+            height: 8,
+            depth: 3,
+            passes: 1,
+            block_size: 8,
+            live: false,
+            threads: 1,
+            output: None,
+            pixel_type: PixelType::U8,
+            spectrum_type: SpectrumType::default_for(&integrator_type),
+            output_space: None,
+            adaptation: ChromaticAdaptation::Bradford,
+            white_balance: None,
+            response: None,
+            integrator_type,
+            photons: None,
+            radius: None,
+            ao_distance: None,
+            ao_samples: None,
+            demo_type: DemoType::SphereScene,
+        }
+    }
+
+    #[test]
+    fn renderers_of_every_spectrum() {
+        let config = config(IntegratorType::Path);
+        assert!(matches!(config.spectrum_type, SpectrumType::Srgb));
+
+        assert!(config.create_renderer::<Srgb>().is_ok());
+        assert!(config.create_renderer::<Xyz>().is_ok());
+        assert!(config.create_renderer::<Spectral>().is_ok());
+    }
+
+    #[test]
+    fn spectral_path_requires_spectra() {
+        let config = config(IntegratorType::SpectralPath);
+        assert!(matches!(config.spectrum_type, SpectrumType::Spectral));
+
+        assert!(config.create_renderer::<Spectral>().is_ok());
+        assert!(config.create_renderer::<Srgb>().is_err());
+        assert!(config.create_renderer::<Xyz>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod microfacet {
+    use crate::bxdf::microfacet::{MicrofacetDistribution, TrowbridgeReitzDistribution};
+    use crate::sampler::{RandomSampler, Sampler};
+    use std::f32::consts::PI;
+    use ultraviolet::{Vec2, Vec3};
+
+    /// Maps a sample uniformly onto the upper hemisphere (around the y-axis) of area `2 pi`.
+    fn uniform_hemisphere(sample: &Vec2) -> Vec3 {
+        let y = sample.x;
+        let r = f32::sqrt(1.0 - y * y);
+        let phi = 2.0 * PI * sample.y;
+
+        Vec3::new(r * phi.cos(), y, r * phi.sin())
+    }
+
+    fn outgoing_directions() -> Vec<Vec3> {
+        vec![
+            Vec3::unit_y(),
+            Vec3::new(0.5, 0.8, 0.0).normalized(),
+            Vec3::new(0.0, 0.4, -0.9).normalized(),
+            Vec3::new(-0.7, 0.2, 0.6).normalized(),
+            // inside, e.g. leaving a dielectric
+            Vec3::new(0.3, -0.6, 0.2).normalized(),
+        ]
+    }
+
+    #[test]
+    fn anisotropic_visible_normals_pdf() {
+        // rougher along the z-axis than along the x-axis
+        let distribution = TrowbridgeReitzDistribution::new(0.1, 0.4, true);
+        let sampler = RandomSampler::default();
+        let n = 1_000_000;
+
+        for outgoing in outgoing_directions() {
+            // the pdf of the normals integrates to 1
+            let integral = (0..n)
+                .map(|_| distribution.pdf(&outgoing, &uniform_hemisphere(&sampler.get_2d())))
+                .sum::<f32>()
+                * 2.0
+                * PI
+                / n as f32;
+            assert!((integral - 1.0).abs() < 0.02, "integral = {}", integral);
+
+            // the sampled normals are distributed by the pdf, compared by their spread along
+            // both tangents
+            let mut expected = Vec2::zero();
+            let mut sampled = Vec2::zero();
+            for _ in 0..n {
+                let wh = uniform_hemisphere(&sampler.get_2d());
+                let pdf = distribution.pdf(&outgoing, &wh) * 2.0 * PI;
+                expected += Vec2::new(wh.x * wh.x, wh.z * wh.z) * pdf;
+
+                let wh = distribution.sample_wh(&outgoing, &sampler.get_2d());
+                sampled += Vec2::new(wh.x * wh.x, wh.z * wh.z);
+            }
+            let expected = expected / n as f32;
+            let sampled = sampled / n as f32;
+
+            assert!(expected.x < expected.y);
+            assert!((expected - sampled).abs().component_max() < 0.005);
+        }
+    }
+}
+
+#[cfg(test)]
+mod bxdf_sampling {
+    use crate::bxdf::bsdf::BSDF;
+    use crate::bxdf::disney::{
+        DisneyClearcoat, DisneyDiffuse, DisneyFresnel, DisneyRetro, DisneySheen,
+    };
+    use crate::bxdf::fresnel::{fresnel_dielectric, Dielectric, FresnelNoOp};
+    use crate::bxdf::lambertian::LambertianReflection;
+    use crate::bxdf::layered::CoatedBxDF;
+    use crate::bxdf::microfacet::{
+        BeckmannDistribution, MicrofacetDistribution, MicrofacetReflection, MicrofacetTransmission,
+        RoughDielectric, TrowbridgeReitzDistribution,
+    };
+    use crate::bxdf::specular::SpecularReflection;
+    use crate::bxdf::{cos_theta, same_hemisphere, BxDF, BxDFType};
+    use crate::mc::{uniform_sample_sphere, uniform_sphere_pdf};
+    use crate::sampler::{RandomSampler, Sample, Sampler};
+    use color::{Color, Srgb};
+    use std::f32::consts::{FRAC_1_PI, PI};
+    use std::sync::Arc;
+    use ultraviolet::{Rotor3, Vec3};
+
+    /// The mean and the standard error of the mean of samples, accumulated in double precision.
+    #[derive(Default)]
+    struct Estimate {
+        n: f64,
+        sum: f64,
+        sum_sq: f64,
+    }
+
+    impl Estimate {
+        fn add(&mut self, x: f32) {
+            self.n += 1.0;
+            self.sum += x as f64;
+            self.sum_sq += (x * x) as f64;
+        }
+
+        fn mean(&self) -> f64 {
+            self.sum / self.n
+        }
+
+        fn error(&self) -> f64 {
+            let mean = self.mean();
+            let variance = (self.sum_sq / self.n - mean * mean).max(0.0);
+
+            (variance / self.n).sqrt()
+        }
+
+        /// Whether both estimates agree within five standard errors.
+        fn agrees(&self, other: &Self) -> bool {
+            let error = f64::sqrt(self.error().powi(2) + other.error().powi(2));
+
+            (self.mean() - other.mean()).abs() <= 5.0 * error + 1e-4
+        }
+    }
+
+    /// # Summary
+    /// Checks that the samples of a BxDF agree with its evaluation and pdf, by comparing
+    /// integrals over the sphere to their estimates from the samples.
+    ///
+    /// # Arguments
+    /// * `bxdf` - The BxDF, scattering the first channel
+    /// * `outgoing` - The outgoing direction
+    fn check_sampling(bxdf: &dyn BxDF<Srgb>, outgoing: &Vec3) {
+        let sampler = RandomSampler::default();
+        let n = 500_000;
+
+        let mut pdf_integral = Estimate::default();
+        let mut albedo_integral = Estimate::default();
+        let mut valid = Estimate::default();
+        let mut albedo_sampled = Estimate::default();
+        for _ in 0..n {
+            let incident = uniform_sample_sphere(&sampler.get_2d());
+            let cos = cos_theta(&incident).abs();
+            // like a BSDF, only evaluate the hemisphere the BxDF scatters to
+            let typ = bxdf.get_type();
+            let f = if same_hemisphere(&incident, outgoing) && typ.is_reflection()
+                || !same_hemisphere(&incident, outgoing) && typ.is_transmission()
+            {
+                bxdf.evaluate(&incident, outgoing)[0]
+            } else {
+                0.0
+            };
+            pdf_integral.add(bxdf.pdf(&incident, outgoing) / uniform_sphere_pdf());
+            albedo_integral.add(f * cos / uniform_sphere_pdf());
+
+            let sample = bxdf.sample(outgoing, &sampler.get_2d());
+            if sample.pdf > 0.0 {
+                let pdf = bxdf.pdf(&sample.incident, outgoing);
+                assert!(
+                    (sample.pdf - pdf).abs() <= 1e-3 * pdf,
+                    "{} != {}",
+                    sample.pdf,
+                    pdf
+                );
+
+                let cos = cos_theta(&sample.incident).abs();
+                valid.add(1.0);
+                albedo_sampled.add(sample.spectrum[0] * cos / sample.pdf);
+            } else {
+                valid.add(0.0);
+                albedo_sampled.add(0.0);
+            }
+        }
+
+        // the pdf integrates to the fraction of valid samples
+        assert!(
+            pdf_integral.agrees(&valid),
+            "{} != {}",
+            pdf_integral.mean(),
+            valid.mean()
+        );
+        assert!(
+            albedo_integral.agrees(&albedo_sampled),
+            "{} != {}",
+            albedo_integral.mean(),
+            albedo_sampled.mean()
+        );
+    }
+
+    /// # Summary
+    /// Checks that the samples of a BSDF agree with its evaluation and pdf like `check_sampling`,
+    /// and that non-specular samples carry the value and pdf of all lobes.
+    /// The value and pdf of stochastic BSDFs are estimates, such that only their albedos are
+    /// compared.
+    ///
+    /// # Arguments
+    /// * `bsdf` - The BSDF, scattering the first channel
+    /// * `outgoing` - The outgoing direction, with the normal being the y-axis
+    /// * `specular` - The albedo of the specular lobes, which are not evaluated
+    fn check_bsdf_sampling(bsdf: &BSDF<Srgb>, outgoing: &Vec3, specular: f32) {
+        let sampler = RandomSampler::default();
+        let normal = Vec3::unit_y();
+        let types = BxDFType::ALL;
+        let exact = !bsdf.is_stochastic();
+        let n = 500_000;
+
+        let mut pdf_integral = Estimate::default();
+        let mut albedo_integral = Estimate::default();
+        let mut non_specular = Estimate::default();
+        let mut albedo_sampled = Estimate::default();
+        for _ in 0..n {
+            let incident = uniform_sample_sphere(&sampler.get_2d());
+            let cos = cos_theta(&incident).abs();
+            let f = bsdf.evaluate(&normal, &incident, outgoing, types)[0];
+            pdf_integral.add(bsdf.pdf(&normal, &incident, outgoing, types) / uniform_sphere_pdf());
+            albedo_integral.add(f * cos / uniform_sphere_pdf() + specular);
+
+            let sample = bsdf
+                .sample(
+                    &normal,
+                    outgoing,
+                    types,
+                    &Sample::new(sampler.get_1d(), sampler.get_2d()),
+                )
+                .unwrap();
+            if sample.pdf <= 0.0 {
+                non_specular.add(0.0);
+                albedo_sampled.add(0.0);
+            } else if sample.typ.is_specular() {
+                non_specular.add(0.0);
+                albedo_sampled.add(sample.spectrum[0] / sample.pdf);
+            } else {
+                if exact {
+                    let pdf = bsdf.pdf(&normal, &sample.incident, outgoing, types);
+                    assert!(
+                        (sample.pdf - pdf).abs() <= 1e-3 * pdf,
+                        "{} != {}",
+                        sample.pdf,
+                        pdf
+                    );
+                    let f = bsdf.evaluate(&normal, &sample.incident, outgoing, types)[0];
+                    assert!(
+                        (sample.spectrum[0] - f).abs() <= 1e-4 * f,
+                        "{} != {}",
+                        sample.spectrum[0],
+                        f
+                    );
+                }
+
+                let cos = cos_theta(&sample.incident).abs();
+                non_specular.add(1.0);
+                albedo_sampled.add(sample.spectrum[0] * cos / sample.pdf);
+            }
+        }
+
+        // the pdf integrates to the fraction of valid non-specular samples
+        assert!(
+            !exact || pdf_integral.agrees(&non_specular),
+            "{} != {}",
+            pdf_integral.mean(),
+            non_specular.mean()
+        );
+        assert!(
+            albedo_integral.agrees(&albedo_sampled),
+            "{} != {}",
+            albedo_integral.mean(),
+            albedo_sampled.mean()
+        );
+    }
+
+    /// Directions outside and inside a surface, including grazing ones and ones beyond the
+    /// critical angle of glass.
+    fn outgoing_directions() -> Vec<Vec3> {
+        vec![
+            Vec3::unit_y(),
+            Vec3::new(0.6, 0.8, 0.0),
+            Vec3::new(0.0, 0.2, 0.98).normalized(),
+            Vec3::new(0.3, -0.9, 0.3).normalized(),
+            Vec3::new(-0.8, -0.5, 0.3).normalized(),
+        ]
+    }
+
+    #[test]
+    fn rough_dielectric() {
+        // the frosted glass of the cornell box
+        let beckmann = BeckmannDistribution::new(0.2, 0.2, true);
+        let frosted = RoughDielectric::new(
+            Srgb::white(),
+            Srgb::white(),
+            Box::new(beckmann),
+            Dielectric::new(1.0, 1.5),
+        );
+
+        let trowbridge_reitz = TrowbridgeReitzDistribution::new(0.3, 0.5, true);
+        let rough = RoughDielectric::new(
+            Srgb::white(),
+            Srgb::white(),
+            Box::new(trowbridge_reitz),
+            Dielectric::new(1.0, 1.33),
+        );
+
+        for outgoing in outgoing_directions() {
+            check_sampling(&frosted, &outgoing);
+            check_sampling(&rough, &outgoing);
+        }
+    }
+
+    #[test]
+    fn microfacet_transmission() {
+        let beckmann = BeckmannDistribution::new(0.2, 0.2, true);
+        let transmission = MicrofacetTransmission::new(
+            Srgb::white(),
+            Box::new(beckmann),
+            Dielectric::new(1.0, 1.5),
+        );
+
+        for outgoing in outgoing_directions() {
+            check_sampling(&transmission, &outgoing);
+        }
+    }
+
+    #[test]
+    fn disney() {
+        let color = Srgb::new([0.8, 0.1, 0.1]);
+        let diffuse = DisneyDiffuse::new(color);
+        let retro = DisneyRetro::new(color, 0.4);
+        let sheen = DisneySheen::new(color);
+        let clearcoat = DisneyClearcoat::new(1.0, 0.9);
+
+        // the anisotropic specular lobe of a half metallic material
+        let distribution = TrowbridgeReitzDistribution::new(0.2, 0.1, true);
+        let fresnel = DisneyFresnel::new(color, 0.5, 1.5);
+        let specular =
+            MicrofacetReflection::new(Srgb::white(), Box::new(distribution), Box::new(fresnel));
+
+        for outgoing in outgoing_directions() {
+            check_sampling(&diffuse, &outgoing);
+            check_sampling(&retro, &outgoing);
+            check_sampling(&sheen, &outgoing);
+            check_sampling(&clearcoat, &outgoing);
+            check_sampling(&specular, &outgoing);
+        }
+    }
+
+    #[test]
+    fn bsdf_combines_lobes() {
+        let distribution = TrowbridgeReitzDistribution::new(0.2, 0.2, true);
+        let glossy = MicrofacetReflection::new(
+            Srgb::new_const(0.7),
+            Box::new(distribution),
+            Box::new(FresnelNoOp),
+        );
+        let diffuse = LambertianReflection::new(Srgb::new_const(0.3));
+        let bsdf = BSDF::new(vec![Box::new(glossy), Box::new(diffuse)]);
+
+        for outgoing in outgoing_directions() {
+            check_bsdf_sampling(&bsdf, &outgoing, 0.0);
+        }
+    }
+
+    /// A white Lambertian reflection estimating no albedo at all.
+    #[derive(Debug)]
+    struct Underestimated;
+
+    impl BxDF<Srgb> for Underestimated {
+        fn get_type(&self) -> BxDFType {
+            BxDFType::DIFFUSE | BxDFType::REFLECTION
+        }
+
+        fn evaluate(&self, _: &Vec3, _: &Vec3) -> Srgb {
+            Srgb::new_const(FRAC_1_PI)
+        }
+
+        fn albedo(&self, _: &Vec3) -> f32 {
+            0.0
+        }
+    }
+
+    #[test]
+    fn bsdf_samples_underestimated_lobes() {
+        // the diffuse lobe scatters to directions the narrow glossy lobe never samples
+        let distribution = BeckmannDistribution::new(0.05, 0.05, true);
+        let glossy = MicrofacetReflection::new(
+            Srgb::new_const(0.5),
+            Box::new(distribution),
+            Box::new(FresnelNoOp),
+        );
+        let bsdf = BSDF::new(vec![Box::new(Underestimated), Box::new(glossy)]);
+
+        for outgoing in outgoing_directions() {
+            check_bsdf_sampling(&bsdf, &outgoing, 0.0);
+        }
+    }
+
+    #[test]
+    fn bsdf_samples_specular_lobes() {
+        let specular = SpecularReflection::new(Srgb::new_const(0.6), Arc::new(FresnelNoOp));
+        let diffuse = LambertianReflection::new(Srgb::new_const(0.3));
+        let bsdf = BSDF::new(vec![Box::new(specular), Box::new(diffuse)]);
+
+        for outgoing in outgoing_directions() {
+            check_bsdf_sampling(&bsdf, &outgoing, 0.6);
+        }
+    }
+
+    /// A white Lambertian reflection under a clear coat of glass.
+    fn coated_white(distribution: Option<Box<dyn MicrofacetDistribution>>) -> CoatedBxDF<Srgb> {
+        let base = BSDF::new(vec![Box::new(LambertianReflection::new(Srgb::white()))]);
+
+        CoatedBxDF::new(base, Dielectric::new(1.0, 1.5), distribution)
+    }
+
+    #[test]
+    fn coated_white_furnace() {
+        let sampler = RandomSampler::default();
+        let n = 100_000;
+
+        let rough = TrowbridgeReitzDistribution::new(0.3, 0.3, true);
+        let coats = vec![
+            (coated_white(None), true),
+            (coated_white(Some(Box::new(rough))), false),
+        ];
+
+        for (coated, smooth) in coats.iter() {
+            for outgoing in outgoing_directions() {
+                // the specular reflection of a smooth coat is not evaluated
+                let specular = if *smooth {
+                    fresnel_dielectric(cos_theta(&outgoing).abs(), 1.0, 1.5)
+                } else {
+                    0.0
+                };
+
+                let mut albedo_integral = Estimate::default();
+                let mut albedo_sampled = Estimate::default();
+                for _ in 0..n {
+                    let incident = uniform_sample_sphere(&sampler.get_2d());
+                    let f = coated.evaluate(&incident, &outgoing)[0];
+                    let cos = cos_theta(&incident).abs();
+                    albedo_integral.add(f * cos / uniform_sphere_pdf() + specular);
+
+                    let sample = coated.sample(&outgoing, &sampler.get_2d());
+                    albedo_sampled.add(if sample.pdf <= 0.0 {
+                        0.0
+                    } else if sample.typ.is_specular() {
+                        sample.spectrum[0] / sample.pdf
+                    } else {
+                        sample.spectrum[0] * cos_theta(&sample.incident).abs() / sample.pdf
+                    });
+                }
+
+                // the coat neither absorbs nor emits light, but the rough interface loses the
+                // light scattered between its microfacets
+                assert!(
+                    albedo_integral.agrees(&albedo_sampled),
+                    "{} != {}",
+                    albedo_integral.mean(),
+                    albedo_sampled.mean()
+                );
+                assert!(albedo_sampled.mean() - 5.0 * albedo_sampled.error() <= 1.0);
+                assert!(albedo_sampled.mean() > if *smooth { 0.9 } else { 0.6 });
+            }
+        }
+    }
+
+    #[test]
+    fn bsdf_combines_coated_lobes() {
+        for smooth in [true, false].iter() {
+            let distribution: Option<Box<dyn MicrofacetDistribution>> = if *smooth {
+                None
+            } else {
+                Some(Box::new(TrowbridgeReitzDistribution::new(0.3, 0.3, true)))
+            };
+            let coated = coated_white(distribution);
+            let diffuse = LambertianReflection::new(Srgb::new_const(0.3));
+            let bsdf = BSDF::new(vec![Box::new(diffuse), Box::new(coated)]);
+
+            for outgoing in outgoing_directions() {
+                // the specular reflection of a smooth coat is not evaluated
+                let specular = if *smooth {
+                    fresnel_dielectric(cos_theta(&outgoing).abs(), 1.0, 1.5)
+                } else {
+                    0.0
+                };
+
+                check_bsdf_sampling(&bsdf, &outgoing, specular);
+            }
+        }
+    }
+
+    #[test]
+    fn coated_reciprocity() {
+        let sampler = RandomSampler::default();
+        let n = 50_000;
+
+        let rough = TrowbridgeReitzDistribution::new(0.3, 0.3, true);
+        let coats = vec![coated_white(None), coated_white(Some(Box::new(rough)))];
+        let pairs = vec![
+            (Vec3::new(0.3, 0.9, 0.1), Vec3::new(-0.5, 0.7, 0.2)),
+            (Vec3::new(0.0, 0.2, 0.9), Vec3::new(0.1, 0.95, 0.0)),
+            (Vec3::new(0.8, 0.3, 0.0), Vec3::new(-0.2, -0.5, 0.6)),
+        ];
+
+        for coated in coats.iter() {
+            for (a, b) in pairs.iter() {
+                let (a, b) = (a.normalized(), b.normalized());
+
+                // the coats are isotropic, so rotating both directions around the normal gives
+                // independent estimates of the same value
+                let mut forward = Estimate::default();
+                let mut backward = Estimate::default();
+                for _ in 0..n {
+                    let rotation = Rotor3::from_rotation_xz(2.0 * PI * sampler.get_1d());
+                    let (a, b) = (rotation * a, rotation * b);
+
+                    forward.add(coated.evaluate(&a, &b)[0]);
+                    backward.add(coated.evaluate(&b, &a)[0]);
+                }
+
+                assert!(
+                    forward.agrees(&backward),
+                    "{} != {}",
+                    forward.mean(),
+                    backward.mean()
+                );
+            }
+        }
+    }
+}